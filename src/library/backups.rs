@@ -0,0 +1,284 @@
+/*
+        This is the module that keeps the bin files safe.
+
+        Saving writes to a temporary file next to the real one, syncs it to disk and then
+        renames it over the original, so a crash half way through never leaves a broken file.
+        Before every save the old file is copied into the backups directory with a time stamp,
+        only the newest "numberOfBackups" copies of each file are kept.
+
+        2026.09.16   Sven Ponelat
+*/
+
+use crate::library::data_dir::*;
+use crate::library::settings::*;
+use termion::{color, style};
+use thousands::{Separable};
+use std::fs::{copy, create_dir_all, read_dir, remove_file, rename, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+
+pub const BACKUP_DIR_NAME:      &str = "backups";
+pub const TEMP_EXTENSION:       &str = "tmp";
+// The files SQLite keeps next to a database while it is changing it
+pub const SIDE_FILE_SUFFIXES:   [&str; 3] = ["-wal", "-shm", "-journal"];
+
+
+// Function to get the directory where the backups are kept
+pub fn get_backup_dir() -> PathBuf {
+    get_data_dir().join(BACKUP_DIR_NAME)
+}
+
+
+// Function to write the bytes to a temp file, sync it and then rename it over the real file
+pub fn atomic_write(path: &str, bytes: &[u8]) -> Result<(), String> {
+    let target = Path::new(path);
+    let file_name = match target.file_name() {
+        Some(name) => { name.to_string_lossy().to_string() }
+        None       => { return Err(format!("Not a valid file name -> {}", path)); }
+    };
+    let temp = target.with_file_name(format!(".{}.{}", file_name, TEMP_EXTENSION));
+
+    let mut file = match OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&temp){
+            Ok(content) => content,
+            Err(_) => { return Err(format!("Problem opening the temporary file for {}", file_name)); }
+    };
+
+    if file.write_all(bytes).is_err() || file.sync_all().is_err() {
+        let _ = remove_file(&temp);
+        return Err(format!("Problem writing the temporary file for {}", file_name));
+    }
+    drop(file);
+
+    if rename(&temp, target).is_err() {
+        let _ = remove_file(&temp);
+        return Err(format!("Problem replacing {} with the new version", file_name));
+    }
+
+    // Make the rename itself durable, not all systems allow a directory to be synced
+    if let Some(dir) = target.parent() {
+        if let Ok(d) = File::open(dir) {
+            let _ = d.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+
+// Function to copy the current file into the backups directory and throw away the old ones.
+// If keep is zero, no backups are made at all.
+pub fn rotate_backup(path: &str, keep: usize) -> Result<(), String> {
+    rotate_backup_with(path, keep, |backup| {
+        match copy(path, backup) {
+            Ok(_)  => { Ok(()) }
+            Err(_) => { Err(format!("Problem making a backup of {}", path)) }
+        }
+    })
+}
+
+
+// Function to make a backup the way the caller copies the file (a database copies itself, so the
+// copy is whole while it is open) and then throw away the old ones
+pub fn rotate_backup_with(path: &str, keep: usize, copy_to: impl Fn(&Path) -> Result<(), String>) -> Result<(), String> {
+    let source = Path::new(path);
+    if keep == 0 || !source.is_file() {
+        return Ok(())
+    }
+    let file_name = source.file_name().unwrap().to_string_lossy().to_string();
+
+    let dir = get_backup_dir();
+    if create_dir_all(&dir).is_err() {
+        return Err(format!("Cannot create the backup directory -> {}", dir.display()));
+    }
+
+    let stamp = chrono::offset::Local::now().format("%Y.%m.%d_%H_%M_%S_%3f_").to_string();
+    let backup = dir.join(format!("{}{}", stamp, file_name));
+    let res = copy_to(&backup);
+    if res.is_err(){
+        let _ = remove_file(&backup);
+        return Err(res.err().unwrap())
+    }
+
+    // Newest first, so everything after keep goes
+    let all = list_backups_of(&file_name);
+    for old in all.iter().skip(keep) {
+        let _ = remove_file(old);
+    }
+
+    Ok(())
+}
+
+
+// Function to list the backups of one file, newest first
+pub fn list_backups_of(file_name: &str) -> Vec<PathBuf> {
+    let mut ret: Vec<PathBuf> = list_backups()
+        .into_iter()
+        .filter(|p| p.to_string_lossy().ends_with(&format!("_{}", file_name)))
+        .collect();
+    ret.sort_by(|a, b| b.cmp(a));
+    ret
+}
+
+
+// Function to list all the backups, newest first
+pub fn list_backups() -> Vec<PathBuf> {
+    let mut ret: Vec<PathBuf> = Vec::new();
+    let entries = match read_dir(get_backup_dir()) {
+        Ok(entries) => { entries }
+        Err(_)      => { return ret }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() {
+            ret.push(path);
+        }
+    }
+    // The time stamp is at the front of the name
+    ret.sort_by(|a, b| b.file_name().cmp(&a.file_name()));
+    ret
+}
+
+
+// Function to work out which live file a backup belongs to
+pub fn live_file_of_backup(backup: &Path, live_files: &[String]) -> Option<String> {
+    let name = backup.file_name()?.to_string_lossy().to_string();
+    for live in live_files {
+        let live_name = Path::new(live).file_name()?.to_string_lossy().to_string();
+        if name.ends_with(&format!("_{}", live_name)) {
+            return Some(live.to_string())
+        }
+    }
+    None
+}
+
+
+// Function to put a backup back in place, the current file is backed up first. Whatever has the
+// file open must let go of it first. Journal files SQLite left next to the old file are removed,
+// they would be mixed into the restored one otherwise.
+pub fn restore_backup(backup: &Path, live: &str, keep: usize) -> Result<(), String> {
+    let bytes = match std::fs::read(backup) {
+        Ok(bytes) => { bytes }
+        Err(_)    => { return Err(format!("Problem reading the backup -> {}", backup.display())); }
+    };
+
+    // Never lose the current state, even if the user has turned backups off
+    let res = rotate_backup(live, keep.max(1));
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+
+    let res = atomic_write(live, &bytes);
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    for suffix in SIDE_FILE_SUFFIXES {
+        let side = format!("{}{}", live, suffix);
+        if Path::new(&side).exists() && remove_file(&side).is_err() {
+            return Err(format!("Problem removing {}, it belongs to the file before the restore", side));
+        }
+    }
+    Ok(())
+}
+
+
+// Function to show the backups with a number, so one can be picked for restoring
+pub fn display_backups(options: &mut SettingsText, backups: &Vec<PathBuf>) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    println!("{}Backups in {}{}", color::Fg(my_light_blue), get_backup_dir().display(), style::Reset);
+    for (i, backup) in backups.iter().enumerate() {
+        let size = match backup.metadata() {
+            Ok(meta) => { meta.len() }
+            Err(_)   => { 0 }
+        };
+        let name = backup.file_name().unwrap().to_string_lossy().to_string();
+        println!("{:>4}  {}{:<50}{}{:>12} bytes", i + 1, color::Fg(my_normal_gray), name, style::Reset, size.separate_with_spaces());
+    }
+    println!("");
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{read, remove_dir_all};
+
+
+    #[ignore]
+    #[test]
+    fn t001_atomic_write() {
+        let dir = "./test/atomic";
+        create_dir_all(dir).unwrap();
+        let path = "./test/atomic/sightings.bin";
+
+        atomic_write(path, b"first").unwrap();
+        atomic_write(path, b"second").unwrap();
+        let content = read(path).unwrap();
+        let temp_left = Path::new("./test/atomic/.sightings.bin.tmp").exists();
+        remove_dir_all(dir).unwrap();
+
+        assert_eq!(content, b"second");
+        assert_eq!(temp_left, false);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_rotate_backup() {
+        std::env::set_var(DATA_DIR_ENV, "./test/rotate");
+        create_dir_all("./test/rotate").unwrap();
+        let path = data_file("species.bin");
+
+        for i in 0..4 {
+            atomic_write(&path, format!("version {}", i).as_bytes()).unwrap();
+            rotate_backup(&path, 2).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        let backups = list_backups_of("species.bin");
+        let newest = read(&backups[0]).unwrap();
+        remove_dir_all("./test/rotate").unwrap();
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert_eq!(backups.len(), 2);
+        assert_eq!(newest, b"version 3");
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_restore_removes_side_files() {
+        std::env::set_var(DATA_DIR_ENV, "./test/restore_side");
+        create_dir_all("./test/restore_side").unwrap();
+        let path = data_file("mybirding.db");
+        atomic_write(&path, b"old").unwrap();
+        let backup = get_backup_dir().join("2026.01.01_00_00_00_000_mybirding.db");
+        create_dir_all(get_backup_dir()).unwrap();
+        atomic_write(&backup.to_string_lossy(), b"restored").unwrap();
+        atomic_write(&format!("{}-wal", path), b"belongs to old").unwrap();
+
+        restore_backup(&backup, &path, 2).unwrap();
+        let content = read(&path).unwrap();
+        let wal_left = Path::new(&format!("{}-wal", path)).exists();
+        remove_dir_all("./test/restore_side").unwrap();
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert_eq!(content, b"restored");
+        assert_eq!(wal_left, false);
+    }
+}
@@ -0,0 +1,1276 @@
+/*
+        The layout of the species.bin and sightings.bin files.
+
+        Every file starts with a small header:
+            4 bytes     magic "MYBD"
+            1 byte      what is in the file (species or sightings)
+            1 byte      reserved, always 0
+            2 bytes     format version (little endian)
+        followed by the bincode of the whole BTreeMap or Vec.
+
+        Files written before the header existed are version 0. When a struct changes, bump the
+        version, keep a frozen copy of the old struct here, add it to AnySpecies or AnySighting with
+        its step up to the next version, and say which version it goes with in species_at_version or
+        sightings_at_version. An old file is decoded in its own layout and every record is brought
+        up one version at a time until it is current.
+
+        Species versions:       1  the original layout
+                                2  every species has a taxonomic sequence number
+                                3  a species can have a list of subspecies
+                                4  a species can be on any number of named lists
+                                5  a species can have names in other languages
+                                6  a species can have notes and the species it is confused with
+                                7  a species has a category (species, hybrid, escapee, introduced, ...)
+
+        Sightings versions:     1  the original layout
+                                2  every sighting has an id
+                                3  a sighting can have a subspecies
+                                4  a sighting can have a count of the birds (by age and sex as well)
+                                5  a sighting can have a local time of day and its time zone
+                                6  a sighting has the precision of its date (year, month, day or time)
+                                7  a sighting can have coordinates (latitude, longitude and accuracy)
+                                8  a sighting refers to its location in the registry
+
+        Loading never panics, it says why a file could not be used (LoadError). A damaged file
+        can be salvaged record by record with the repair functions at the bottom.
+
+        2026.09.18   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::*;
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+
+pub const MAGIC:                        [u8; 4] = *b"MYBD";
+pub const HEADER_LENGTH:                usize = 8;
+pub const SPECIES_FORMAT_VERSION:       u16 = 7;
+pub const SIGHTINGS_FORMAT_VERSION:     u16 = 8;
+
+// No single record is ever this big, it keeps a damaged length from eating all the memory
+pub const MAX_RECORD_BYTES:             u64 = 1_000_000;
+pub const REPAIRED_SUFFIX:              &str = "_repaired";
+
+
+// Why a bin file could not be loaded
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    Missing,
+    Unreadable(String),
+    Corrupt(String),
+    WrongVersion(String),
+}
+
+impl LoadError {
+    // Function to see if there is a file that could not be read, so it should not be saved over
+    pub fn is_damaged(&self) -> bool {
+        match self {
+            LoadError::Missing => { false }
+            _                  => { true }
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Missing              => { write!(f, "The file does not exist") }
+            LoadError::Unreadable(message)  => { write!(f, "{}", message) }
+            LoadError::Corrupt(message)     => { write!(f, "{}", message) }
+            LoadError::WrongVersion(message)=> { write!(f, "{}", message) }
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FileKind {
+    Species     = 1,
+    Sightings   = 2,
+}
+
+impl FileKind {
+    // Function to get the name used in messages
+    pub fn name(&self) -> &str {
+        match self {
+            FileKind::Species   => { "species" }
+            FileKind::Sightings => { "sightings" }
+        }
+    }
+}
+
+
+// Function to put the header in front of the bincode payload
+pub fn add_header(kind: FileKind, version: u16, payload: &[u8]) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::with_capacity(HEADER_LENGTH + payload.len());
+    ret.extend_from_slice(&MAGIC);
+    ret.push(kind as u8);
+    ret.push(0);
+    ret.extend_from_slice(&version.to_le_bytes());
+    ret.extend_from_slice(payload);
+    ret
+}
+
+
+// Function to read the header, returning the format version and the payload after it.
+// No magic means an old file from before headers, that is version 0 and all of it is payload.
+pub fn read_header(bytes: &[u8], kind: FileKind, current: u16) -> Result<(u16, &[u8]), LoadError> {
+    if bytes.len() < MAGIC.len() || bytes[..MAGIC.len()] != MAGIC {
+        return Ok((0, bytes))
+    }
+    if bytes.len() < HEADER_LENGTH {
+        return Err(LoadError::Corrupt(format!("The {} file header is cut short", kind.name())));
+    }
+
+    if bytes[4] != kind as u8 {
+        return Err(LoadError::Corrupt(format!("This is not a {} file", kind.name())));
+    }
+
+    let version = u16::from_le_bytes([bytes[6], bytes[7]]);
+    if version > current {
+        let message = format!("The {} file is format version {}, this version of mybirding only reads up to {}. Please upgrade mybirding.",
+                                kind.name(), version, current);
+        return Err(LoadError::WrongVersion(message));
+    }
+
+    Ok((version, &bytes[HEADER_LENGTH..]))
+}
+
+
+// Function to tell if a bin file on disk is in an older format than the one written now.
+// Old files are upgraded on load, but only saving them makes things like the sighting ids stick.
+pub fn is_older_format(bin_file: &str, kind: FileKind, current: u16) -> bool {
+    let file = read_bin_file(bin_file, kind);
+    if file.is_err(){
+        return false
+    }
+    let bytes = file.unwrap();
+    match read_header(&bytes[..], kind, current) {
+        Ok((version, _)) => { version < current }
+        Err(_)           => { false }
+    }
+}
+
+
+// Function to decode a payload without panicking
+fn decode<T: DeserializeOwned>(payload: &[u8], kind: FileKind, version: u16) -> Result<T, LoadError> {
+    match bincode::deserialize::<T>(payload) {
+        Ok(decoded) => { Ok(decoded) }
+        Err(_)      => {
+            let message = format!("The {} file (format version {}) is corrupt, try: repair {}", kind.name(), version, kind.name());
+            Err(LoadError::Corrupt(message))
+        }
+    }
+}
+
+
+// Function to bring a species file up to the current layout
+pub fn migrate_species(version: u16, payload: &[u8]) -> Result<BTreeMap<String, Species>, LoadError> {
+    let read = species_at_version(version, payload, false);
+    if read.is_err(){
+        return Err(read.err().unwrap())
+    }
+    Ok(read.unwrap().records.into_iter().collect())
+}
+
+
+// Function to bring a sightings file up to the current layout
+pub fn migrate_sightings(version: u16, payload: &[u8]) -> Result<Vec<Sightings>, LoadError> {
+    let read = sightings_at_version(version, payload, false);
+    if read.is_err(){
+        return Err(read.err().unwrap())
+    }
+    Ok(read.unwrap().records)
+}
+
+
+// Function to read a species payload in the layout of its version, the only place that knows
+// which layout goes with which version. A repair reads it record by record.
+fn species_at_version(version: u16, payload: &[u8], repair: bool) -> Result<Salvage<(String, Species)>, LoadError> {
+    match version {
+        // Version 0 has no header, but the same layout as version 1
+        0 | 1 => { read_species(payload, version, repair, AnySpecies::V1) }
+        2     => { read_species(payload, version, repair, AnySpecies::V2) }
+        3     => { read_species(payload, version, repair, AnySpecies::V3) }
+        4     => { read_species(payload, version, repair, AnySpecies::V4) }
+        5     => { read_species(payload, version, repair, AnySpecies::V5) }
+        6     => { read_species(payload, version, repair, AnySpecies::V6) }
+        7     => { read_species(payload, version, repair, AnySpecies::Current) }
+        _     => { Err(LoadError::WrongVersion(format!("Unknown species file format version {}", version))) }
+    }
+}
+
+
+// Function to read a sightings payload in the layout of its version, the only place that knows
+// which layout goes with which version. A repair reads it record by record.
+fn sightings_at_version(version: u16, payload: &[u8], repair: bool) -> Result<Salvage<Sightings>, LoadError> {
+    match version {
+        // Version 0 has no header, but the same layout as version 1
+        0 | 1 => { read_sightings(payload, version, repair, AnySighting::V1) }
+        2     => { read_sightings(payload, version, repair, AnySighting::V2) }
+        3     => { read_sightings(payload, version, repair, AnySighting::V3) }
+        4     => { read_sightings(payload, version, repair, AnySighting::V4) }
+        5     => { read_sightings(payload, version, repair, AnySighting::V5) }
+        6     => { read_sightings(payload, version, repair, AnySighting::V6) }
+        7     => { read_sightings(payload, version, repair, AnySighting::V7) }
+        8     => { read_sightings(payload, version, repair, AnySighting::Current) }
+        _     => { Err(LoadError::WrongVersion(format!("Unknown sightings file format version {}", version))) }
+    }
+}
+
+
+// Function to decode the species in the layout T and bring each one up to the current layout
+fn read_species<T: DeserializeOwned>(payload: &[u8], version: u16, repair: bool, layout: fn(T) -> AnySpecies)
+                                        -> Result<Salvage<(String, Species)>, LoadError> {
+    let current = |(code, species): (String, T)| (code, layout(species).current());
+    if repair {
+        return Ok(salvage(payload, current, species_looks_right))
+    }
+    // A map is written the same way as a list of pairs
+    let old = decode::<Vec<(String, T)>>(payload, FileKind::Species, version);
+    if old.is_err(){
+        return Err(old.err().unwrap())
+    }
+    let records: Vec<(String, Species)> = old.unwrap().into_iter().map(current).collect();
+    Ok(Salvage { expected: records.len() as u64, records: records, skipped_bytes: 0 })
+}
+
+
+// Function to decode the sightings in the layout T and bring each one up to the current layout
+fn read_sightings<T: DeserializeOwned>(payload: &[u8], version: u16, repair: bool, layout: fn(T) -> AnySighting)
+                                        -> Result<Salvage<Sightings>, LoadError> {
+    let current = |sighting: T| layout(sighting).current();
+    if repair {
+        return Ok(salvage(payload, current, sighting_looks_right))
+    }
+    let old = decode::<Vec<T>>(payload, FileKind::Sightings, version);
+    if old.is_err(){
+        return Err(old.err().unwrap())
+    }
+    let records: Vec<Sightings> = old.unwrap().into_iter().map(current).collect();
+    Ok(Salvage { expected: records.len() as u64, records: records, skipped_bytes: 0 })
+}
+
+
+// A species in any of the layouts it has had
+enum AnySpecies {
+    V1(SpeciesV1),
+    V2(SpeciesV2),
+    V3(SpeciesV3),
+    V4(SpeciesV4),
+    V5(SpeciesV5),
+    V6(SpeciesV6),
+    Current(Species),
+}
+
+impl AnySpecies {
+    // Function to bring it up one version
+    fn upgrade(self) -> AnySpecies {
+        match self {
+            AnySpecies::V1(old)         => { AnySpecies::V2(species_v1_to_v2(old)) }
+            AnySpecies::V2(old)         => { AnySpecies::V3(species_v2_to_v3(old)) }
+            AnySpecies::V3(old)         => { AnySpecies::V4(species_v3_to_v4(old)) }
+            AnySpecies::V4(old)         => { AnySpecies::V5(species_v4_to_v5(old)) }
+            AnySpecies::V5(old)         => { AnySpecies::V6(species_v5_to_v6(old)) }
+            AnySpecies::V6(old)         => { AnySpecies::Current(species_v6_to_v7(old)) }
+            AnySpecies::Current(species)=> { AnySpecies::Current(species) }
+        }
+    }
+
+    // Function to bring it up one version at a time until it is current
+    fn current(self) -> Species {
+        let mut species = self;
+        loop {
+            match species {
+                AnySpecies::Current(current) => { return current }
+                old                          => { species = old.upgrade(); }
+            }
+        }
+    }
+}
+
+
+// A sighting in any of the layouts it has had
+enum AnySighting {
+    V1(SightingsV1),
+    V2(SightingsV2),
+    V3(SightingsV3),
+    V4(SightingsV4),
+    V5(SightingsV5),
+    V6(SightingsV6),
+    V7(SightingsV7),
+    Current(Sightings),
+}
+
+impl AnySighting {
+    // Function to bring it up one version
+    fn upgrade(self) -> AnySighting {
+        match self {
+            AnySighting::V1(old)            => { AnySighting::V2(sightings_v1_to_v2(old)) }
+            AnySighting::V2(old)            => { AnySighting::V3(sightings_v2_to_v3(old)) }
+            AnySighting::V3(old)            => { AnySighting::V4(sightings_v3_to_v4(old)) }
+            AnySighting::V4(old)            => { AnySighting::V5(sightings_v4_to_v5(old)) }
+            AnySighting::V5(old)            => { AnySighting::V6(sightings_v5_to_v6(old)) }
+            AnySighting::V6(old)            => { AnySighting::V7(sightings_v6_to_v7(old)) }
+            AnySighting::V7(old)            => { AnySighting::Current(sightings_v7_to_v8(old)) }
+            AnySighting::Current(sighting)  => { AnySighting::Current(sighting) }
+        }
+    }
+
+    // Function to bring it up one version at a time until it is current
+    fn current(self) -> Sightings {
+        let mut sighting = self;
+        loop {
+            match sighting {
+                AnySighting::Current(current) => { return current }
+                old                           => { sighting = old.upgrade(); }
+            }
+        }
+    }
+}
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Old layouts  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+// Species as they were in format versions 0 and 1, before the taxonomic sequence. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeciesV1 {
+    pub sname: String,
+    pub name: String,
+    pub fname: String,
+    pub code: String,
+    pub order: String,
+    pub family: String,
+    pub status: String,
+    pub aname: String,
+    pub afname: String,
+    pub acode: String,
+    pub list: String,
+}
+
+
+// Version 1 -> 2: the sequence is not known yet, it comes with a checklist import
+fn species_v1_to_v2(old: SpeciesV1) -> SpeciesV2 {
+    SpeciesV2 {
+        sname: old.sname,
+        name: old.name,
+        fname: old.fname,
+        code: old.code,
+        order: old.order,
+        family: old.family,
+        status: old.status,
+        aname: old.aname,
+        afname: old.afname,
+        acode: old.acode,
+        list: old.list,
+        seq: 0,
+    }
+}
+
+
+// Species as they were in format version 2, before subspecies. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeciesV2 {
+    pub sname: String,
+    pub name: String,
+    pub fname: String,
+    pub code: String,
+    pub order: String,
+    pub family: String,
+    pub status: String,
+    pub aname: String,
+    pub afname: String,
+    pub acode: String,
+    pub list: String,
+    pub seq: u32,
+}
+
+
+// Version 2 -> 3: no subspecies yet, so any will do
+fn species_v2_to_v3(old: SpeciesV2) -> SpeciesV3 {
+    SpeciesV3 {
+        sname: old.sname,
+        name: old.name,
+        fname: old.fname,
+        code: old.code,
+        order: old.order,
+        family: old.family,
+        status: old.status,
+        aname: old.aname,
+        afname: old.afname,
+        acode: old.acode,
+        list: old.list,
+        seq: old.seq,
+        subspecies: Vec::new(),
+    }
+}
+
+
+// Species as they were in format version 3, before the named lists. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeciesV3 {
+    pub sname: String,
+    pub name: String,
+    pub fname: String,
+    pub code: String,
+    pub order: String,
+    pub family: String,
+    pub status: String,
+    pub aname: String,
+    pub afname: String,
+    pub acode: String,
+    pub list: String,
+    pub seq: u32,
+    pub subspecies: Vec<String>,
+}
+
+
+// Version 3 -> 4: the one list becomes the first of the lists
+fn species_v3_to_v4(old: SpeciesV3) -> SpeciesV4 {
+    let lists = match old.list.trim().len() {
+        0 => { Vec::new() }
+        _ => { vec![old.list.trim().to_string()] }
+    };
+    SpeciesV4 {
+        sname: old.sname,
+        name: old.name,
+        fname: old.fname,
+        code: old.code,
+        order: old.order,
+        family: old.family,
+        status: old.status,
+        aname: old.aname,
+        afname: old.afname,
+        acode: old.acode,
+        lists: lists,
+        seq: old.seq,
+        subspecies: old.subspecies,
+    }
+}
+
+
+// Species as they were in format version 4, before the names in other languages. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeciesV4 {
+    pub sname: String,
+    pub name: String,
+    pub fname: String,
+    pub code: String,
+    pub order: String,
+    pub family: String,
+    pub status: String,
+    pub aname: String,
+    pub afname: String,
+    pub acode: String,
+    pub lists: Vec<String>,
+    pub seq: u32,
+    pub subspecies: Vec<String>,
+}
+
+
+// Version 4 -> 5: only the English name is known
+fn species_v4_to_v5(old: SpeciesV4) -> SpeciesV5 {
+    SpeciesV5 {
+        sname: old.sname,
+        name: old.name,
+        fname: old.fname,
+        code: old.code,
+        order: old.order,
+        family: old.family,
+        status: old.status,
+        aname: old.aname,
+        afname: old.afname,
+        acode: old.acode,
+        lists: old.lists,
+        seq: old.seq,
+        subspecies: old.subspecies,
+        names: BTreeMap::new(),
+    }
+}
+
+
+// Species as they were in format version 5, before the notes and confusable species. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeciesV5 {
+    pub sname: String,
+    pub name: String,
+    pub fname: String,
+    pub code: String,
+    pub order: String,
+    pub family: String,
+    pub status: String,
+    pub aname: String,
+    pub afname: String,
+    pub acode: String,
+    pub lists: Vec<String>,
+    pub seq: u32,
+    pub subspecies: Vec<String>,
+    pub names: BTreeMap<String, String>,
+}
+
+
+// Version 5 -> 6: no notes yet, and nothing it is confused with
+fn species_v5_to_v6(old: SpeciesV5) -> SpeciesV6 {
+    SpeciesV6 {
+        sname: old.sname,
+        name: old.name,
+        fname: old.fname,
+        code: old.code,
+        order: old.order,
+        family: old.family,
+        status: old.status,
+        aname: old.aname,
+        afname: old.afname,
+        acode: old.acode,
+        lists: old.lists,
+        seq: old.seq,
+        subspecies: old.subspecies,
+        names: old.names,
+        notes: String::new(),
+        confusable: Vec::new(),
+    }
+}
+
+
+// Species as they were in format version 6, before the category. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpeciesV6 {
+    pub sname: String,
+    pub name: String,
+    pub fname: String,
+    pub code: String,
+    pub order: String,
+    pub family: String,
+    pub status: String,
+    pub aname: String,
+    pub afname: String,
+    pub acode: String,
+    pub lists: Vec<String>,
+    pub seq: u32,
+    pub subspecies: Vec<String>,
+    pub names: BTreeMap<String, String>,
+    pub notes: String,
+    pub confusable: Vec<String>,
+}
+
+
+// Version 6 -> 7: everything was a species
+fn species_v6_to_v7(old: SpeciesV6) -> Species {
+    Species {
+        sname: old.sname,
+        name: old.name,
+        fname: old.fname,
+        code: old.code,
+        order: old.order,
+        family: old.family,
+        status: Status::from(old.status),
+        aname: old.aname,
+        afname: old.afname,
+        acode: old.acode,
+        lists: old.lists,
+        seq: old.seq,
+        subspecies: old.subspecies,
+        names: old.names,
+        notes: old.notes,
+        confusable: old.confusable,
+        category: Category::Species,
+    }
+}
+
+
+// Sightings as they were in format versions 0 and 1, before they had an id. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV1 {
+    pub date: i64,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+}
+
+
+// Version 1 -> 2: every sighting gets its id
+fn sightings_v1_to_v2(old: SightingsV1) -> SightingsV2 {
+    SightingsV2 {
+        date: old.date,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: new_sighting_id(),
+    }
+}
+
+
+// Sightings as they were in format version 2, before the subspecies. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV2 {
+    pub date: i64,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+    pub id: String,
+}
+
+
+// Version 2 -> 3: no subspecies was recorded
+fn sightings_v2_to_v3(old: SightingsV2) -> SightingsV3 {
+    SightingsV3 {
+        date: old.date,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: old.id,
+        subspecies: "".to_string(),
+    }
+}
+
+
+// Sightings as they were in format version 3, before the counts. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV3 {
+    pub date: i64,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+    pub id: String,
+    pub subspecies: String,
+}
+
+
+// Version 3 -> 4: the birds were not counted
+fn sightings_v3_to_v4(old: SightingsV3) -> SightingsV4 {
+    SightingsV4 {
+        date: old.date,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: old.id,
+        subspecies: old.subspecies,
+        count: Counts::default(),
+    }
+}
+
+
+// Sightings as they were in format version 4, before the time of day. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV4 {
+    pub date: i64,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+    pub id: String,
+    pub subspecies: String,
+    pub count: Counts,
+}
+
+
+// Version 4 -> 5: only the day was known, not the time or its zone
+fn sightings_v4_to_v5(old: SightingsV4) -> SightingsV5 {
+    SightingsV5 {
+        date: old.date,
+        time: None,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: old.id,
+        subspecies: old.subspecies,
+        count: old.count,
+        zone: None,
+    }
+}
+
+
+// Sightings as they were in format version 5, before the precision of the date. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV5 {
+    pub date: i64,
+    pub time: Option<u16>,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+    pub id: String,
+    pub subspecies: String,
+    pub count: Counts,
+    pub zone: Option<i16>,
+}
+
+
+// Version 5 -> 6: every date was a full date, with the time when there was one
+fn sightings_v5_to_v6(old: SightingsV5) -> SightingsV6 {
+    let precision = if old.time.is_some() { Precision::Time } else { Precision::Day };
+    SightingsV6 {
+        date: old.date,
+        time: old.time,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: old.id,
+        subspecies: old.subspecies,
+        count: old.count,
+        zone: old.zone,
+        precision: precision,
+    }
+}
+
+
+// Sightings as they were in format version 6, before the coordinates. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV6 {
+    pub date: i64,
+    pub time: Option<u16>,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+    pub id: String,
+    pub subspecies: String,
+    pub count: Counts,
+    pub zone: Option<i16>,
+    pub precision: Precision,
+}
+
+
+// Version 6 -> 7: there were no coordinates
+fn sightings_v6_to_v7(old: SightingsV6) -> SightingsV7 {
+    SightingsV7 {
+        date: old.date,
+        time: old.time,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: old.id,
+        subspecies: old.subspecies,
+        count: old.count,
+        zone: old.zone,
+        precision: old.precision,
+        coordinates: None,
+    }
+}
+
+
+// Sightings as they were in format version 7, before the location registry. Never change this.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SightingsV7 {
+    pub date: i64,
+    pub time: Option<u16>,
+    pub sname: String,
+    pub location: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    pub seen: bool,
+    pub heard: bool,
+    pub ringed: bool,
+    pub dead: bool,
+    pub photo: bool,
+    pub male: bool,
+    pub female: bool,
+    pub adult: bool,
+    pub immature: bool,
+    pub breeding: bool,
+    pub eggs: bool,
+    pub nonbreeding: bool,
+    pub nest: bool,
+    pub chicks: bool,
+    pub comments: String,
+    pub id: String,
+    pub subspecies: String,
+    pub count: Counts,
+    pub zone: Option<i16>,
+    pub precision: Precision,
+    pub coordinates: Option<Coordinates>,
+}
+
+
+// Version 7 -> 8: no sighting was in the registry yet, that is done when the sightings are loaded
+fn sightings_v7_to_v8(old: SightingsV7) -> Sightings {
+    Sightings {
+        date: old.date,
+        time: old.time,
+        sname: old.sname,
+        location: old.location,
+        town: old.town,
+        province: old.province,
+        country: old.country,
+        seen: old.seen,
+        heard: old.heard,
+        ringed: old.ringed,
+        dead: old.dead,
+        photo: old.photo,
+        male: old.male,
+        female: old.female,
+        adult: old.adult,
+        immature: old.immature,
+        breeding: old.breeding,
+        eggs: old.eggs,
+        nonbreeding: old.nonbreeding,
+        nest: old.nest,
+        chicks: old.chicks,
+        comments: old.comments,
+        id: old.id,
+        subspecies: old.subspecies,
+        count: old.count,
+        zone: old.zone,
+        precision: old.precision,
+        coordinates: old.coordinates,
+        location_id: 0,
+    }
+}
+
+
+// Function to read the whole bin file, telling a missing file apart from one that cannot be read
+pub fn read_bin_file(bin_file: &str, kind: FileKind) -> Result<Vec<u8>, LoadError> {
+    if !Path::new(bin_file).exists() {
+        return Err(LoadError::Missing)
+    }
+    match std::fs::read(bin_file) {
+        Ok(bytes) => { Ok(bytes) }
+        Err(e)    => { Err(LoadError::Unreadable(format!("Problem reading the {} bin file: {}", kind.name(), e))) }
+    }
+}
+
+
+// Function to get the name of the file a repair is written to, species.bin -> species_repaired.bin
+pub fn repaired_path(bin_file: &str) -> String {
+    let path = Path::new(bin_file);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let ext = path.extension().unwrap_or_default().to_string_lossy().to_string();
+    path.with_file_name(format!("{}{}.{}", stem, REPAIRED_SUFFIX, ext)).to_string_lossy().to_string()
+}
+
+
+// What came out of repairing a file
+#[derive(Clone, Debug, Default)]
+pub struct Salvage<T> {
+    pub records: Vec<T>,
+    pub expected: u64,
+    pub skipped_bytes: usize,
+}
+
+
+// Function to decode one record from the front of the slice, moving the slice past it
+fn decode_one<T: DeserializeOwned>(slice: &mut &[u8]) -> Option<T> {
+    let options = bincode::DefaultOptions::new()
+                    .with_fixint_encoding()
+                    .allow_trailing_bytes()
+                    .with_limit(MAX_RECORD_BYTES);
+    let mut attempt: &[u8] = slice;
+    match options.deserialize_from::<_, T>(&mut attempt) {
+        Ok(record) => {
+            *slice = attempt;
+            Some(record)
+        }
+        Err(_) => { None }
+    }
+}
+
+
+// Function to go through the payload record by record, bringing each one up to the current layout.
+// When a record does not decode (or does not look right) one byte is skipped and it tries again,
+// until it finds its feet.
+fn salvage<T: DeserializeOwned, U>(payload: &[u8], current: impl Fn(T) -> U, looks_right: fn(&U) -> bool) -> Salvage<U> {
+    let mut ret: Salvage<U> = Salvage { records: Vec::new(), expected: 0, skipped_bytes: 0 };
+    if payload.len() < 8 {
+        return ret
+    }
+    ret.expected = u64::from_le_bytes(payload[..8].try_into().unwrap());
+
+    let mut slice: &[u8] = &payload[8..];
+    while slice.len() > 0 {
+        let mut attempt = slice;
+        if let Some(record) = decode_one::<T>(&mut attempt) {
+            let record = current(record);
+            if looks_right(&record) {
+                ret.records.push(record);
+                slice = attempt;
+                continue;
+            }
+        }
+        slice = &slice[1..];
+        ret.skipped_bytes += 1;
+    }
+    ret
+}
+
+
+// Function to see if a salvaged species could be real, it is checked once it is in the current layout
+fn species_looks_right(pair: &(String, Species)) -> bool {
+    pair.0 == pair.1.code && pair.1.sname.len() > 0 && pair.1.sname.len() < 200
+}
+
+
+// Function to see if a salvaged sighting could be real, it is checked once it is in the current layout.
+// A sighting from before the ids got one on the way up.
+fn sighting_looks_right(sighting: &Sightings) -> bool {
+    // Somewhere between 1800 and 2200
+    let valid_date = sighting.date > -5_364_662_400 && sighting.date < 7_258_118_400;
+    let valid_time = sighting.time.unwrap_or(0) < MINUTES_IN_DAY;
+    valid_date && sighting.sname.len() > 0 && sighting.sname.len() < 200 && is_sighting_id(&sighting.id) && valid_time
+}
+
+
+// Function to get the header (if it can) and the payload of a damaged file
+fn header_for_repair(bytes: &[u8], kind: FileKind, current: u16) -> Result<(u16, &[u8]), LoadError> {
+    match read_header(bytes, kind, current) {
+        Ok(res)                         => { Ok(res) }
+        Err(LoadError::Corrupt(_))      => {
+            // A broken header, assume the current layout behind it
+            if bytes.len() > HEADER_LENGTH && bytes[..MAGIC.len()] == MAGIC {
+                return Ok((current, &bytes[HEADER_LENGTH..]))
+            }
+            Ok((0, bytes))
+        }
+        Err(e)                          => { Err(e) }
+    }
+}
+
+
+// Function to get as many species as possible out of a damaged file
+pub fn repair_species(bytes: &[u8]) -> Result<Salvage<(String, Species)>, LoadError> {
+    let header = header_for_repair(bytes, FileKind::Species, SPECIES_FORMAT_VERSION);
+    if header.is_err(){
+        return Err(header.err().unwrap())
+    }
+    let (version, payload) = header.unwrap();
+    species_at_version(version, payload, true)
+}
+
+
+// Function to get as many sightings as possible out of a damaged file
+pub fn repair_sightings(bytes: &[u8]) -> Result<Salvage<Sightings>, LoadError> {
+    let header = header_for_repair(bytes, FileKind::Sightings, SIGHTINGS_FORMAT_VERSION);
+    if header.is_err(){
+        return Err(header.err().unwrap())
+    }
+    let (version, payload) = header.unwrap();
+    sightings_at_version(version, payload, true)
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{copy, remove_file};
+
+
+    #[ignore]
+    #[test]
+    fn t001_header_round_trip() {
+        let bytes = add_header(FileKind::Sightings, 1, b"payload");
+        let (version, payload) = read_header(&bytes, FileKind::Sightings, 1).unwrap();
+
+        assert_eq!(version, 1);
+        assert_eq!(payload, b"payload");
+        assert_eq!(read_header(&bytes, FileKind::Species, 1).is_err(), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_newer_version() {
+        let bytes = add_header(FileKind::Species, SPECIES_FORMAT_VERSION + 1, b"payload");
+        let res = read_header(&bytes, FileKind::Species, SPECIES_FORMAT_VERSION);
+
+        assert_eq!(res.is_err(), true);
+        assert_eq!(res.err().unwrap().to_string().contains("upgrade"), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_legacy_sightings_file() {
+        let source = "./test/store/sightings/sightings.bin";
+        let destination = "./test/sightings_legacy.bin";
+        copy(source,destination).expect("Failed to copy");
+
+        let old = Sightings::load(destination).unwrap();
+        Sightings::save(destination, &old).unwrap();
+        let new = Sightings::load(destination).unwrap();
+        let bytes = std::fs::read(destination).unwrap();
+        remove_file(destination).expect("Cleanup test failed");
+
+        assert_eq!(bytes[..4], MAGIC);
+        assert_eq!(old.len(), new.len());
+        assert_eq!(is_sighting_id(&old[0].id), true);
+        assert_eq!(old[0].id, new[0].id);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t004_repair_sightings() {
+        let source = "./test/store/sightings/sightings.bin";
+        let mut bytes = std::fs::read(source).unwrap();
+        let expected = Sightings::load(source).unwrap().len();
+        for i in 50_000..50_200 {
+            bytes[i] = 0xff;
+        }
+        let destination = "./test/sightings_corrupt.bin";
+        std::fs::write(destination, &bytes).unwrap();
+
+        let res = Sightings::load(destination);
+        let salvage = repair_sightings(&bytes).unwrap();
+        remove_file(destination).expect("Cleanup test failed");
+
+        assert_eq!(res.err().unwrap().is_damaged(), true);
+        assert_eq!(salvage.expected as usize, expected);
+        assert_eq!(salvage.records.len() > expected - 5, true);
+        assert_eq!(repaired_path("/a/sightings.bin"), "/a/sightings_repaired.bin");
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t005_older_species_file() {
+        let source = "./test/store/species/species.bin";
+        let destination = "./test/species_older.bin";
+        copy(source,destination).expect("Failed to copy");
+
+        let outdated = is_older_format(destination, FileKind::Species, SPECIES_FORMAT_VERSION);
+        let old = Species::load(destination).unwrap();
+        Species::save(destination, &old).unwrap();
+        let updated = is_older_format(destination, FileKind::Species, SPECIES_FORMAT_VERSION);
+        let new = Species::load(destination).unwrap();
+        remove_file(destination).expect("Cleanup test failed");
+
+        assert_eq!(outdated, true);
+        assert_eq!(updated, false);
+        assert_eq!(old.len(), new.len());
+        assert_eq!(new.values().all(|s| s.seq == 0), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t006_repair_older_species_file() {
+        let source = "./test/store/species/species.bin";
+        let bytes = std::fs::read(source).unwrap();
+        let loaded = Species::load(source).unwrap();
+        let (version, _) = read_header(&bytes, FileKind::Species, SPECIES_FORMAT_VERSION).unwrap();
+
+        let salvage = repair_species(&bytes).unwrap();
+
+        assert_eq!(version < SPECIES_FORMAT_VERSION, true);
+        assert_eq!(salvage.expected as usize, loaded.len());
+        assert_eq!(salvage.skipped_bytes, 0);
+        assert_eq!(salvage.records.into_iter().collect::<BTreeMap<String, Species>>(), loaded);
+    }
+}
@@ -0,0 +1,530 @@
+/*
+        Importing the published world checklists, straight from a downloaded csv file.
+
+            ioc         the IOC World Bird List. Either the master list (one row per order, family,
+                        genus, species and subspecies) or the multilingual list (one row per species).
+            ebird       the eBird taxonomy (TAXON_ORDER, CATEGORY, PRIMARY_COM_NAME, SCI_NAME, ...)
+            clements    the Clements checklist (sort, category, English name, scientific name, ...)
+
+        The columns are found by their header, so it does not matter which year's file it is.
+        A status column (IUCN category) is taken when there is one, and Clements marks the extinct ones.
+        Only the rows that are a species are imported, the rest (subspecies, spuhs, slashes, hybrids)
+        are counted per category. The codes are made with Species::make_code, as for any new species,
+        and the position in the checklist becomes the taxonomic sequence.
+
+        The names in other languages come from a multilingual list (like the IOC one), with a column per
+        language. These only go onto the species we have, found by their scientific name.
+
+        2026.10.05   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::NAME_39;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::BTreeMap;
+
+
+// The rows that are imported
+const SPECIES_CATEGORY: &str = "species";
+
+// How many of the rejected rows are shown
+const SHOW_REJECTED: usize = 10;
+
+
+// The language columns of a multilingual checklist, and their codes
+const LANGUAGES: [(&str, &str); 30] = [
+    ("afrikaans", "af"), ("catalan", "ca"), ("chinese", "zh"), ("chinese (traditional)", "zht"), ("czech", "cs"),
+    ("danish", "da"), ("dutch", "nl"), ("estonian", "et"), ("finnish", "fi"), ("french", "fr"),
+    ("german", "de"), ("hungarian", "hu"), ("icelandic", "is"), ("indonesian", "id"), ("italian", "it"),
+    ("japanese", "ja"), ("korean", "ko"), ("latvian", "lv"), ("lithuanian", "lt"), ("northern sami", "se"),
+    ("norwegian", "no"), ("polish", "pl"), ("portuguese", "pt"), ("russian", "ru"), ("slovak", "sk"),
+    ("slovenian", "sl"), ("spanish", "es"), ("swedish", "sv"), ("turkish", "tr"), ("ukrainian", "uk"),
+];
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Checklist {
+    Ioc,
+    Ebird,
+    Clements,
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct ChecklistReport {
+    pub imported: usize,
+    // The rows that are not a species, by category
+    pub skipped: BTreeMap<String, usize>,
+    // The species rows that could not be made into a species, with the reason
+    pub rejected: Vec<String>,
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct NamesReport {
+    // The species that got at least one name
+    pub updated: usize,
+    // How many names were put on, by language
+    pub names: BTreeMap<String, usize>,
+    // The rows whose scientific name is not one of our species
+    pub unknown: usize,
+}
+
+
+// Where the wanted fields are in a checklist file
+#[derive(Clone, Debug, Default)]
+struct Columns {
+    seq: Option<usize>,
+    category: Option<usize>,
+    sname: Option<usize>,
+    genus: Option<usize>,
+    epithet: Option<usize>,
+    subspecies: Option<usize>,
+    name: Option<usize>,
+    order: Option<usize>,
+    family: Option<usize>,
+    family_english: Option<usize>,
+    status: Option<usize>,
+    extinct: Option<usize>,
+}
+
+
+impl Checklist {
+
+    // Function to read the checklist from the command line, None if it is not one
+    pub fn from_arg(arg: &str) -> Option<Checklist> {
+        match arg.to_lowercase().trim() {
+            "ioc"      => { Some(Checklist::Ioc) }
+            "ebird"    => { Some(Checklist::Ebird) }
+            "clements" => { Some(Checklist::Clements) }
+            _          => { None }
+        }
+    }
+
+
+    pub fn name(&self) -> &str {
+        match self {
+            Checklist::Ioc      => { "IOC" }
+            Checklist::Ebird    => { "eBird" }
+            Checklist::Clements => { "Clements" }
+        }
+    }
+
+
+    // Function to find the columns from the header line. A name ending in * is matched on the start,
+    // since the IOC and Clements headers carry the version (IOC_14.1, sort v2023).
+    fn columns(&self, headers: &Vec<String>) -> Result<Columns, String> {
+        let mut cols = Columns::default();
+        match self {
+            Checklist::Ioc => {
+                cols.seq            = find_column(headers, &["seq.", "seq", "sequence"]);
+                cols.sname          = find_column(headers, &["ioc_*", "scientific name"]);
+                cols.genus          = find_column(headers, &["genus"]);
+                cols.epithet        = find_column(headers, &["species (scientific)"]);
+                cols.subspecies     = find_column(headers, &["subspecies"]);
+                cols.name           = find_column(headers, &["english", "species (english)", "english name"]);
+                cols.order          = find_column(headers, &["order"]);
+                cols.family         = find_column(headers, &["family", "family (scientific)"]);
+                cols.family_english = find_column(headers, &["family (english)"]);
+            }
+            Checklist::Ebird => {
+                cols.seq            = find_column(headers, &["taxon_order"]);
+                cols.category       = find_column(headers, &["category"]);
+                cols.sname          = find_column(headers, &["sci_name", "scientific name"]);
+                cols.name           = find_column(headers, &["primary_com_name", "common name", "english name"]);
+                cols.order          = find_column(headers, &["order", "order1"]);
+                cols.family         = find_column(headers, &["family"]);
+            }
+            Checklist::Clements => {
+                cols.seq            = find_column(headers, &["sort v*", "sort*"]);
+                cols.category       = find_column(headers, &["category"]);
+                cols.sname          = find_column(headers, &["scientific name"]);
+                cols.name           = find_column(headers, &["english name"]);
+                cols.order          = find_column(headers, &["order"]);
+                cols.family         = find_column(headers, &["family"]);
+                cols.extinct        = find_column(headers, &["extinct"]);
+            }
+        }
+        cols.status = find_column(headers, &["status", "iucn*", "red list*"]);
+
+        let has_sname = cols.sname.is_some() || (cols.genus.is_some() && cols.epithet.is_some());
+        if !has_sname || cols.name.is_none() || cols.order.is_none() || cols.family.is_none() {
+            return Err(format!("This does not look like an {} checklist, it needs at least the scientific name, \
+                                English name, order and family columns", self.name()));
+        }
+        Ok(cols)
+    }
+}
+
+
+// Function to find a column by any of its names, case does not matter
+fn find_column(headers: &Vec<String>, names: &[&str]) -> Option<usize> {
+    for name in names {
+        let found = match name.strip_suffix('*') {
+            Some(start) => { headers.iter().position(|h| h.starts_with(start)) }
+            None        => { headers.iter().position(|h| h == name) }
+        };
+        if found.is_some() {
+            return found
+        }
+    }
+    None
+}
+
+
+// Function to get a field, empty when the column is not there or the row is short
+fn field(record: &csv::StringRecord, column: Option<usize>) -> String {
+    match column.and_then(|c| record.get(c)) {
+        Some(text) => { text.trim().to_string() }
+        None       => { String::new() }
+    }
+}
+
+
+// Function to import a checklist into birds (normally an empty map). The file may be comma or tab delimited.
+pub fn import_checklist(csv_file: &str, checklist: Checklist, birds: &mut BTreeMap<String,Species>)
+                            -> Result<ChecklistReport, String> {
+    let text = std::fs::read_to_string(csv_file);
+    if text.is_err(){
+        return Err(format!("Cannot open checklist file for reading -> {}", csv_file));
+    }
+    let text = text.unwrap();
+    let first_line = text.lines().next().unwrap_or("");
+    let delimiter = if first_line.contains('\t') { b'\t' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+                        .delimiter(delimiter)
+                        .flexible(true)
+                        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(headers) => { headers.iter().map(|h| h.trim().to_lowercase()).collect::<Vec<String>>() }
+        Err(_)      => { return Err(format!("Cannot read the header line of the checklist -> {}", csv_file)); }
+    };
+    let cols = checklist.columns(&headers);
+    if cols.is_err(){
+        return Err(cols.err().unwrap())
+    }
+    let cols = cols.unwrap();
+
+    let mut report = ChecklistReport::default();
+
+    // The IOC master list only fills in the order, family and genus on the row where they start
+    let mut order = String::new();
+    let mut family = String::new();
+    let mut family_english = String::new();
+    let mut genus = String::new();
+    let mut counter: u32 = 0;
+
+    for (line, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => { record }
+            Err(_)     => { return Err(format!("Problem reading the checklist, around line {}", line + 2)); }
+        };
+
+        let this_order = field(&record, cols.order);
+        if this_order.len() > 0 { order = this_order; }
+        let this_family = field(&record, cols.family);
+        if this_family.len() > 0 {
+            family = this_family;
+            family_english = field(&record, cols.family_english);
+        }
+        let this_genus = field(&record, cols.genus);
+        if this_genus.len() > 0 { genus = this_genus; }
+
+        // What sort of row is it
+        let mut sname = field(&record, cols.sname);
+        let category = match cols.category {
+            Some(_) => { field(&record, cols.category).to_lowercase() }
+            None    => {
+                if field(&record, cols.subspecies).len() > 0 {
+                    "subspecies".to_string()
+                } else if sname.len() == 0 && field(&record, cols.epithet).len() > 0 {
+                    sname = format!("{} {}", genus, field(&record, cols.epithet));
+                    SPECIES_CATEGORY.to_string()
+                } else if sname.len() > 0 {
+                    SPECIES_CATEGORY.to_string()
+                } else {
+                    // An order, family or genus row
+                    continue;
+                }
+            }
+        };
+        if category != SPECIES_CATEGORY {
+            *report.skipped.entry(category).or_default() += 1;
+            continue;
+        }
+
+        counter += 1;
+        let full_family = match family_english.len() {
+            0 => { family.clone() }
+            _ => { format!("{} ({})", family, family_english) }
+        };
+        let mut status = field(&record, cols.status);
+        if status.len() == 0 && field(&record, cols.extinct) == "1" {
+            status = Status::Extinct.code().to_string();
+        }
+        let species = Species::build_species(birds, sname.clone(), field(&record, cols.name), order.clone(),
+                                                full_family, status, String::new(), String::new());
+        if species.is_err(){
+            report.rejected.push(format!("line {}  {}: {}", line + 2, sname, species.err().unwrap()));
+            continue;
+        }
+        let mut species = species.unwrap();
+
+        species.seq = match cols.seq {
+            Some(_) => {
+                // eBird has whole numbers, but some exports write them as 123.0
+                match field(&record, cols.seq).parse::<f64>() {
+                    Ok(number) if number >= 1.0 => { number as u32 }
+                    _ => {
+                        report.rejected.push(format!("line {}  {}: not a valid sequence number", line + 2, sname));
+                        continue;
+                    }
+                }
+            }
+            None => { counter }
+        };
+
+        birds.insert(species.code.clone(), species);
+        report.imported += 1;
+    }
+
+    if report.imported == 0 {
+        return Err(format!("Not a single species was found in the {} checklist -> {}", checklist.name(), csv_file));
+    }
+    Ok(report)
+}
+
+
+// Function to find the language of a column header, either its name (German) or its code (de)
+fn header_language(header: &str) -> Option<String> {
+    let found = LANGUAGES.iter().find(|(name, _)| *name == header);
+    if found.is_some() {
+        return Some(found.unwrap().1.to_string())
+    }
+    if LANGUAGES.iter().any(|(_, code)| *code == header) {
+        return Some(header.to_string())
+    }
+    None
+}
+
+
+// Function to read the names in other languages from a multilingual checklist onto the species we have.
+// Only the languages asked for are taken, or all of them when none are given.
+pub fn import_names(csv_file: &str, languages: Option<Vec<String>>, birds: &mut BTreeMap<String,Species>)
+                            -> Result<NamesReport, String> {
+    let text = std::fs::read_to_string(csv_file);
+    if text.is_err(){
+        return Err(format!("Cannot open checklist file for reading -> {}", csv_file));
+    }
+    let text = text.unwrap();
+    let first_line = text.lines().next().unwrap_or("");
+    let delimiter = if first_line.contains('\t') { b'\t' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+                        .delimiter(delimiter)
+                        .flexible(true)
+                        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers = match reader.headers() {
+        Ok(headers) => { headers.iter().map(|h| h.trim().to_lowercase()).collect::<Vec<String>>() }
+        Err(_)      => { return Err(format!("Cannot read the header line of the checklist -> {}", csv_file)); }
+    };
+    let sname_col = find_column(&headers, &["scientific name", "sci_name", "ioc_*"]);
+    if sname_col.is_none(){
+        return Err(format!("There is no scientific name column in -> {}", csv_file));
+    }
+
+    // The columns with a language we want
+    let mut columns: Vec<(usize, String)> = Vec::new();
+    for (i, header) in headers.iter().enumerate() {
+        let language = header_language(header);
+        if language.is_none() {
+            continue;
+        }
+        let language = language.unwrap();
+        if languages.is_none() || languages.as_ref().unwrap().contains(&language) {
+            columns.push((i, language));
+        }
+    }
+    if columns.len() == 0 {
+        return Err(format!("None of the languages asked for has a column in -> {}", csv_file));
+    }
+
+    let mut codes: BTreeMap<String, String> = BTreeMap::new();
+    for (code, species) in birds.iter() {
+        codes.insert(species.sname.to_lowercase(), code.clone());
+    }
+
+    let mut report = NamesReport::default();
+    for (line, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => { record }
+            Err(_)     => { return Err(format!("Problem reading the checklist, around line {}", line + 2)); }
+        };
+
+        let sname = field(&record, sname_col);
+        if sname.len() == 0 {
+            continue;
+        }
+        let code = codes.get(&sname.to_lowercase());
+        if code.is_none() {
+            report.unknown += 1;
+            continue;
+        }
+        let species = birds.get_mut(code.unwrap()).unwrap();
+
+        let mut changed = false;
+        for (column, language) in &columns {
+            let name = limit_length(line_to_words(&field(&record, Some(*column))).join(" "), NAME_39);
+            if name.len() == 0 || species.names.get(language) == Some(&name) {
+                continue;
+            }
+            species.names.insert(language.clone(), name);
+            *report.names.entry(language.clone()).or_default() += 1;
+            changed = true;
+        }
+        if changed {
+            report.updated += 1;
+        }
+    }
+    Ok(report)
+}
+
+
+// Function to tell the user which names were put on
+pub fn display_names_report(report: &NamesReport) {
+    let names: Vec<String> = report.names.iter()
+                                .map(|(language, number)| format!("{} {}", number.separate_with_spaces(), language))
+                                .collect();
+    let mut message = format!("{} species got names in other languages", report.updated.separate_with_spaces());
+    if names.len() > 0 {
+        message.push_str(&format!(" ({})", names.join(", ")));
+    }
+    message.push_str(&format!(", {} rows are not one of our species.", report.unknown.separate_with_spaces()));
+    feedback(Feedback::Info, message);
+}
+
+
+// Function to tell the user what was imported, and what was not
+pub fn display_checklist_report(options: &mut SettingsText, checklist: Checklist, report: &ChecklistReport) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    if report.rejected.len() > 0 {
+        println!("{}Species rows that could not be imported{}", color::Fg(my_light_blue), style::Reset);
+        for rejected in report.rejected.iter().take(SHOW_REJECTED) {
+            println!("{}{}{}", color::Fg(my_normal_gray), rejected, style::Reset);
+        }
+        if report.rejected.len() > SHOW_REJECTED {
+            println!("{}... and {} more{}", color::Fg(my_normal_gray),
+                        (report.rejected.len() - SHOW_REJECTED).separate_with_spaces(), style::Reset);
+        }
+        println!();
+    }
+
+    let skipped: Vec<String> = report.skipped.iter()
+                                    .map(|(category, number)| format!("{} {}", number.separate_with_spaces(), category))
+                                    .collect();
+    let mut message = format!("{} species read from the {} checklist, {} could not be imported.",
+                                report.imported.separate_with_spaces(), checklist.name(),
+                                report.rejected.len().separate_with_spaces());
+    if skipped.len() > 0 {
+        message.push_str(&format!(" Not species, so left out: {}.", skipped.join(", ")));
+    }
+    feedback(Feedback::Info, message);
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[ignore]
+    #[test]
+    fn t001_ebird_taxonomy() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        let report = import_checklist("./test/store/species/ebird_taxonomy.csv", Checklist::Ebird, &mut birds).unwrap();
+        let ostrich = birds.values().find(|s| s.sname == "Struthio camelus").unwrap();
+
+        assert_eq!(report.imported, 4);
+        assert_eq!(report.skipped.get("spuh"), Some(&1));
+        assert_eq!(report.skipped.get("issf"), Some(&1));
+        assert_eq!(ostrich.name, "Common Ostrich");
+        assert_eq!(ostrich.order, "Struthioniformes");
+        assert_eq!(ostrich.family, "Struthionidae (Ostriches)");
+        assert_eq!(ostrich.seq, 2);
+        assert_eq!(ostrich.code, "osco");
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_ioc_master_list() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        let report = import_checklist("./test/store/species/ioc_master.csv", Checklist::Ioc, &mut birds).unwrap();
+        let seqs: Vec<u32> = crate::library::bird_species_support::ordered_species_by(&birds,
+                                crate::library::bird_species_support::SpeciesOrder::Taxonomic)
+                                .iter().map(|s| s.seq).collect();
+        let duck = birds.values().find(|s| s.sname == "Anas undulata").unwrap();
+
+        assert_eq!(report.imported, 3);
+        assert_eq!(report.skipped.get("subspecies"), Some(&2));
+        assert_eq!(seqs, [1, 2, 3]);
+        assert_eq!(duck.family, "Anatidae (Ducks, Geese, Swans)");
+        assert_eq!(duck.order, "Anseriformes");
+        assert_eq!(Checklist::from_arg("Clements"), Some(Checklist::Clements));
+        assert_eq!(import_checklist("./test/store/species/ioc_master.csv", Checklist::Ebird, &mut birds).is_err(), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_clements_extinct() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        let report = import_checklist("./test/store/species/clements.csv", Checklist::Clements, &mut birds).unwrap();
+        let dodo = birds.values().find(|s| s.sname == "Raphus cucullatus").unwrap();
+        let ostrich = birds.values().find(|s| s.sname == "Struthio camelus").unwrap();
+
+        assert_eq!(report.imported, 2);
+        assert_eq!(dodo.status, Status::Extinct);
+        assert_eq!(dodo.seq, 3);
+        assert_eq!(ostrich.status, Status::NotSet);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t004_import_names() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        import_checklist("./test/store/species/ioc_master.csv", Checklist::Ioc, &mut birds).unwrap();
+        let mut some = birds.clone();
+        let report = import_names("./test/store/species/ioc_multilingual.csv", None, &mut birds).unwrap();
+        let duck = birds.values().find(|s| s.sname == "Anas undulata").unwrap();
+
+        assert_eq!(report.updated, 2);
+        assert_eq!(report.unknown, 1);
+        assert_eq!(duck.names.get("af"), Some(&"Geelbekeend".to_string()));
+        assert_eq!(duck.names.get("de"), Some(&"Gelbschnabelente".to_string()));
+
+        let report = import_names("./test/store/species/ioc_multilingual.csv", Some(vec!["nl".to_string()]), &mut some).unwrap();
+        let duck = some.values().find(|s| s.sname == "Anas undulata").unwrap();
+        assert_eq!(report.names.len(), 1);
+        assert_eq!(duck.names.get("af"), None);
+        assert_eq!(import_names("./test/store/species/ioc_multilingual.csv", Some(vec!["xx".to_string()]), &mut some).is_err(), true);
+    }
+}
@@ -0,0 +1,169 @@
+/*
+        The number of birds counted in the sightings (Sightings.count), added up per species and site.
+
+        A site is the location together with its town, so "Home" in two towns are two sites.
+        For each species at a site the report has how many sightings were counted, the highest
+        count (the best guess of how many birds there are) and the sum of the counts.
+        Sightings without a count are left out and only mentioned at the end.
+
+        The report is of all the sightings, or of the ones that match search terms as with so.
+
+        2026.10.14   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_species_support::*;
+use crate::library::bird_sightings::*;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::{BTreeMap, HashSet};
+
+
+// The counts of one species at one site
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CountRow {
+    pub sname: String,
+    pub name: String,
+    pub site: String,
+    // Sightings with a count
+    pub sightings: usize,
+    pub max: u32,
+    pub total: u32,
+}
+
+
+// What the counts in the sightings add up to
+#[derive(Clone, Debug, Default)]
+pub struct CountReport {
+    pub rows: Vec<CountRow>,
+    pub uncounted: usize,
+}
+
+
+// Function to give the site of a sighting, the location and the town
+pub fn site_of(sighting: &Sightings) -> String {
+    match sighting.location.trim().len() {
+        0 => { sighting.town.clone() }
+        _ => { format!("{}, {}", sighting.location.trim(), sighting.town) }
+    }
+}
+
+
+// Function to add up the counts of the sightings per species and site, in the order of the species
+pub fn count_report(sbirds: &BTreeMap<String, Species>, sightings: &[Sightings]) -> CountReport {
+    let mut report = CountReport::default();
+    let mut rows: BTreeMap<(String, String), CountRow> = BTreeMap::new();
+
+    for sighting in sightings {
+        if sighting.count.total == 0 {
+            report.uncounted += 1;
+            continue;
+        }
+        let site = site_of(sighting);
+        let row = rows.entry((sighting.sname.clone(), site.clone())).or_insert_with(|| {
+            let name = match sbirds.get(&sighting.sname) {
+                Some(species) => { species.display_name() }
+                None          => { sighting.sname.clone() }
+            };
+            CountRow { sname: sighting.sname.clone(), name: name, site: site, ..Default::default() }
+        });
+        row.sightings += 1;
+        row.max = row.max.max(sighting.count.total);
+        row.total = row.total.saturating_add(sighting.count.total);
+    }
+
+    for species in ordered_species(sbirds) {
+        let first = (species.sname.clone(), "".to_string());
+        let keys: Vec<(String, String)> = rows.range(first..).take_while(|(key, _)| key.0 == species.sname)
+                                              .map(|(key, _)| key.clone()).collect();
+        for key in keys {
+            report.rows.push(rows.remove(&key).unwrap());
+        }
+    }
+    // Species that are not in the database any more come last
+    report.rows.extend(rows.into_values());
+    report
+}
+
+
+// Function to show the counts per species and site
+pub fn display_count_report(options: &mut SettingsText, report: &CountReport) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    if report.rows.len() > 0 {
+        println!("{}{:<40}  {:<50}  {:>9}  {:>7}  {:>8}{}", color::Fg(my_light_blue), "Species", "Site", "Sightings",
+                    "Maximum", "Total", style::Reset);
+        for row in &report.rows {
+            println!("{}{:<40}  {:<50}  {:>9}  {:>7}  {:>8}{}", color::Fg(my_normal_gray), limit_length(row.name.clone(), 40),
+                        limit_length(row.site.clone(), 50), row.sightings.separate_with_spaces(), row.max.separate_with_spaces(),
+                        row.total.separate_with_spaces(), style::Reset);
+        }
+        println!();
+    }
+
+    let counted: usize = report.rows.iter().map(|row| row.sightings).sum();
+    let species: HashSet<&str> = report.rows.iter().map(|row| row.sname.as_str()).collect();
+    let mut message = format!("{} counted {} of {} species at {} {}.", counted.separate_with_spaces(), plural("sighting", counted),
+                            species.len().separate_with_spaces(), report.rows.len().separate_with_spaces(),
+                            plural("site", report.rows.len()));
+    if report.uncounted > 0 {
+        message.push_str(&format!(" {} without a count.", report.uncounted.separate_with_spaces()));
+    }
+    feedback(Feedback::Info, message);
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn sighting(sname: &str, location: &str, count: &str) -> Sightings {
+        let mut sighting = Sightings::new();
+        sighting.sname = sname.to_string();
+        sighting.location = location.to_string();
+        sighting.town = "Hermanus".to_string();
+        sighting.count = Counts::from_text(count).unwrap();
+        sighting
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_count_report() {
+        let mut sbirds: BTreeMap<String, Species> = BTreeMap::new();
+        let mut gannet = Species::new();
+        gannet.sname = "Morus capensis".to_string();
+        gannet.name = "Cape Gannet".to_string();
+        sbirds.insert(gannet.sname.clone(), gannet);
+        let sightings = vec![sighting("Morus capensis", "Harbour", "42"), sighting("Morus capensis", "Harbour", "10 3m 1f"),
+                             sighting("Morus capensis", "Cliffs", "5"), sighting("Morus capensis", "Cliffs", "")];
+
+        let report = count_report(&sbirds, &sightings);
+
+        assert_eq!(report.uncounted, 1);
+        assert_eq!(report.rows.len(), 2);
+        assert_eq!(report.rows[0].site, "Cliffs, Hermanus");
+        assert_eq!(report.rows[1].name, "Cape Gannet");
+        assert_eq!(report.rows[1].sightings, 2);
+        assert_eq!(report.rows[1].max, 42);
+        assert_eq!(report.rows[1].total, 52);
+
+        let flock = vec![sighting("Morus capensis", "Harbour", "4000000000"), sighting("Morus capensis", "Harbour", "4000000000")];
+        assert_eq!(count_report(&sbirds, &flock).rows[0].total, u32::MAX);
+    }
+
+
+} // end of all tests
@@ -0,0 +1,502 @@
+/*
+        Named lists of species, like "Southern Africa", "Garden" or "UK BOU".
+
+        A species can be on any number of lists (Species.lists). The names of the lists that were
+        made with lc are kept in the speciesLists option, so an empty list is not lost and a typo
+        in la does not quietly start a new one. A list that only comes in with the species (an
+        import) is known as well.
+
+        Species are put on (or taken off) a list in bulk, either
+            from a file     one species per line, by code, scientific name or name (# starts a comment)
+            from a search   the species of the sightings that match the search terms, as with so
+
+        The report of a list shows which of its species have been seen and which not yet.
+        What counts is up to the counting rules in the options: countedCategories says which
+        categories of species are on the lists (species and introduced ones, unless changed), and
+        deadBirdIsSighting whether a sighting of a dead bird counts as having seen it.
+        These rules are only for the lists (l): the totals of o and so, and the species and
+        lifer numbers of the sightings, go on counting every sighting.
+
+        2026.10.11   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_species_support::*;
+use crate::library::bird_sightings::*;
+use crate::library::bird_sightings_supp::*;
+use crate::library::bird_locations::*;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+
+// The option where the names of the lists are kept
+pub const LISTS_KEY: &str = "speciesLists";
+// The option with the categories that count, and what it is when the option is not there
+pub const COUNTED_KEY: &str = "countedCategories";
+const COUNTED_DEFAULT: &str = "species,introduced";
+
+
+// What a list looks like against the sightings
+#[derive(Clone, Debug, Default)]
+pub struct ListReport {
+    pub name: String,
+    // The species seen, with their number of sightings
+    pub seen: Vec<(Species, usize)>,
+    pub not_seen: Vec<Species>,
+    // On the list, but of a category that does not count
+    pub left_out: usize,
+}
+
+
+// What is counted on the lists (only used by l, the other counts take every sighting)
+#[derive(Clone, Debug, PartialEq)]
+pub struct CountingRules {
+    pub categories: Vec<Category>,
+    pub dead_birds: bool,
+}
+
+
+impl CountingRules {
+
+    // Function to get the rules from the options
+    pub fn from_options(options: &SettingsText) -> Result<CountingRules, String> {
+        let text = match options.map.get(COUNTED_KEY) {
+            Some(sdata) => { sdata.value.clone() }
+            None        => { COUNTED_DEFAULT.to_string() }
+        };
+        let categories = Category::validate_categories(&text);
+        if categories.is_err(){
+            return Err(format!("The option {} is not right: {}", COUNTED_KEY, categories.err().unwrap()))
+        }
+        let categories = categories.unwrap();
+        if categories.len() == 0 {
+            return Err(format!("The option {} has no categories, nothing would count", COUNTED_KEY))
+        }
+        Ok(CountingRules { categories: categories, dead_birds: options.get_bool("deadBirdIsSighting") })
+    }
+
+
+    pub fn counts_species(&self, species: &Species) -> bool {
+        self.categories.contains(&species.category)
+    }
+
+
+    pub fn counts_sighting(&self, sighting: &Sightings) -> bool {
+        self.dead_birds || !sighting.dead
+    }
+}
+
+
+// Function to get the lists made with lc
+fn registered_lists(options: &SettingsText) -> Vec<String> {
+    match options.map.get(LISTS_KEY) {
+        Some(sdata) => { Species::validate_lists(&sdata.value).unwrap_or_default() }
+        None        => { Vec::new() }
+    }
+}
+
+
+// Function to keep the lists made with lc
+fn register_lists(options: &mut SettingsText, lists: &Vec<String>) -> Result<(), String> {
+    options.set_value_for_key(LISTS_KEY, lists.join(","))
+}
+
+
+// Function to get the names of the lists made with lc as they are kept, for the journal
+pub fn list_registry(options: &SettingsText) -> String {
+    match options.map.get(LISTS_KEY) {
+        Some(sdata) => { sdata.value.clone() }
+        None        => { String::new() }
+    }
+}
+
+
+// Function to put back the names of the lists made with lc (undo and redo)
+pub fn set_list_registry(options: &mut SettingsText, lists: &str) -> Result<(), String> {
+    options.set_value_for_key(LISTS_KEY, lists.to_string())
+}
+
+
+// Function to get all the lists there are, the ones made with lc and the ones species are on
+pub fn known_lists(options: &SettingsText, birds: &BTreeMap<String, Species>) -> Vec<String> {
+    let mut ret = registered_lists(options);
+    for species in birds.values() {
+        for list in &species.lists {
+            if !ret.iter().any(|l| l.to_lowercase() == list.to_lowercase()) {
+                ret.push(list.clone());
+            }
+        }
+    }
+    ret.sort_by_key(|l| l.to_lowercase());
+    ret
+}
+
+
+// Function to find a list by its name as it is typed, case does not matter
+pub fn find_list(name: &str, options: &SettingsText, birds: &BTreeMap<String, Species>) -> Result<String, String> {
+    let wanted = line_to_words(name).join(" ").to_lowercase();
+    match known_lists(options, birds).into_iter().find(|l| l.to_lowercase() == wanted) {
+        Some(list) => { Ok(list) }
+        None       => { Err(format!("There is no list called -> {}   (make it with lc)", name.trim())) }
+    }
+}
+
+
+// Function to make a new, empty list
+pub fn create_list(name: &str, options: &mut SettingsText, birds: &BTreeMap<String, Species>) -> Result<String, String> {
+    let r_lists = Species::validate_lists(name);
+    if r_lists.is_err(){
+        return Err(r_lists.err().unwrap())
+    }
+    let lists = r_lists.unwrap();
+    if lists.len() != 1 {
+        return Err(format!("Not a valid list name -> {}   (one name, without commas)", name.trim()));
+    }
+    let list = lists[0].clone();
+    if find_list(&list, options, birds).is_ok() {
+        return Err(format!("There is already a list called -> {}", list));
+    }
+
+    let mut registered = registered_lists(options);
+    registered.push(list.clone());
+    let res = register_lists(options, &registered);
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    Ok(list)
+}
+
+
+// Function to delete a list, it is taken off all its species. Returns how many species were on it.
+pub fn delete_list(list: &str, options: &mut SettingsText, birds: &mut BTreeMap<String, Species>) -> Result<usize, String> {
+    let mut count: usize = 0;
+    for species in birds.values_mut() {
+        if species.is_on_list(list) {
+            species.lists.retain(|l| l.to_lowercase() != list.to_lowercase());
+            count += 1;
+        }
+    }
+
+    let mut registered = registered_lists(options);
+    registered.retain(|l| l.to_lowercase() != list.to_lowercase());
+    let res = register_lists(options, &registered);
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    Ok(count)
+}
+
+
+// Function to read the species in a file, one per line. Returns the codes found and the lines that were not.
+pub fn species_from_file(file: &str, birds: &BTreeMap<String, Species>) -> Result<(Vec<String>, Vec<String>), String> {
+    let text = std::fs::read_to_string(file);
+    if text.is_err(){
+        return Err(format!("Cannot open the species file for reading -> {}", file));
+    }
+
+    let mut by_name: HashMap<String, String> = HashMap::new();
+    for species in birds.values() {
+        by_name.insert(species.name.to_lowercase(), species.code.clone());
+        by_name.insert(species.sname.to_lowercase(), species.code.clone());
+    }
+
+    let mut codes: Vec<String> = Vec::new();
+    let mut not_found: Vec<String> = Vec::new();
+    for line in text.unwrap().lines() {
+        let wanted = line_to_words(line.split('#').next().unwrap_or("")).join(" ");
+        if wanted.len() == 0 {
+            continue;
+        }
+        let code = match birds.contains_key(&wanted.to_lowercase()) {
+            true  => { Some(wanted.to_lowercase()) }
+            false => { by_name.get(&wanted.to_lowercase()).cloned() }
+        };
+        match code {
+            Some(code) => { if !codes.contains(&code) { codes.push(code) } }
+            None       => { not_found.push(wanted) }
+        }
+    }
+    Ok((codes, not_found))
+}
+
+
+// Function to get the species of the sightings that match the search terms
+pub fn species_from_search(terms: &str, birds: &BTreeMap<String, Species>, sightings: &Vec<Sightings>,
+                            places: &BTreeMap<u32, Place>) -> Result<Vec<String>, String> {
+    let sbirds = make_sname_btree(birds);
+    let result = get_searched_slice_of_sightings(terms, &sbirds, sightings, places);
+    if result.is_err(){
+        return Err(result.err().unwrap())
+    }
+
+    let mut codes: Vec<String> = Vec::new();
+    for sighting in result.unwrap().1 {
+        let code = sbirds.get(&sighting.sname).unwrap().code.clone();
+        if !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    Ok(codes)
+}
+
+
+// Function to get the species to put on (or take off) a list, from a file if there is one, else from a search
+pub fn species_for_list(arg: &str, birds: &BTreeMap<String, Species>, sightings: &Vec<Sightings>,
+                            places: &BTreeMap<u32, Place>) -> Result<(Vec<String>, Vec<String>), String> {
+    if Path::new(arg).is_file() {
+        return species_from_file(arg, birds)
+    }
+    match species_from_search(arg, birds, sightings, places) {
+        Ok(codes) => { Ok((codes, Vec::new())) }
+        Err(e)    => { Err(e) }
+    }
+}
+
+
+// Function to put species on a list, returns how many were not on it yet
+pub fn add_to_list(list: &str, codes: &Vec<String>, birds: &mut BTreeMap<String, Species>) -> usize {
+    let mut count: usize = 0;
+    for code in codes {
+        if let Some(species) = birds.get_mut(code) {
+            if !species.is_on_list(list) {
+                species.lists.push(list.to_string());
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+
+// Function to take species off a list, returns how many were on it
+pub fn remove_from_list(list: &str, codes: &Vec<String>, birds: &mut BTreeMap<String, Species>) -> usize {
+    let mut count: usize = 0;
+    for code in codes {
+        if let Some(species) = birds.get_mut(code) {
+            if species.is_on_list(list) {
+                species.lists.retain(|l| l.to_lowercase() != list.to_lowercase());
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+
+// Function for la and lr: put the species of a file or search on a list (or take them off), returns what was done
+pub fn change_list(adding: bool, list_arg: &str, species_arg: &str, options: &SettingsText, birds: &mut BTreeMap<String, Species>,
+                    sightings: &Vec<Sightings>, places: &BTreeMap<u32, Place>) -> Result<String, String> {
+    let list = find_list(list_arg, options, birds);
+    if list.is_err(){
+        return Err(list.err().unwrap())
+    }
+    let list = list.unwrap();
+    let found = species_for_list(species_arg, birds, sightings, places);
+    if found.is_err(){
+        return Err(found.err().unwrap())
+    }
+    let (codes, not_found) = found.unwrap();
+    for wanted in &not_found {
+        let message = format!("There is no species called -> {}", wanted);
+        feedback(Feedback::Warning, message);
+    }
+
+    let message = if adding {
+        let number = add_to_list(&list, &codes, birds);
+        format!("{} species put on {} ({} of the {} were on it already).", number.separate_with_spaces(),
+                    list, (codes.len() - number).separate_with_spaces(), codes.len().separate_with_spaces())
+    } else {
+        let number = remove_from_list(&list, &codes, birds);
+        format!("{} species taken off {} ({} of the {} were not on it).", number.separate_with_spaces(),
+                    list, (codes.len() - number).separate_with_spaces(), codes.len().separate_with_spaces())
+    };
+    Ok(message)
+}
+
+
+// Function to see which species of a list have been seen, in the order the species are walked in
+pub fn list_report(list: &str, rules: &CountingRules, birds: &BTreeMap<String, Species>, sightings: &Vec<Sightings>) -> ListReport {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for sighting in sightings.iter().filter(|s| rules.counts_sighting(s)) {
+        *counts.entry(&sighting.sname).or_default() += 1;
+    }
+
+    let mut report = ListReport { name: list.to_string(), ..Default::default() };
+    for species in ordered_species(birds) {
+        if !species.is_on_list(list) {
+            continue;
+        }
+        if !rules.counts_species(species) {
+            report.left_out += 1;
+            continue;
+        }
+        match counts.get(species.sname.as_str()) {
+            Some(number) => { report.seen.push((species.clone(), *number)) }
+            None         => { report.not_seen.push(species.clone()) }
+        }
+    }
+    report
+}
+
+
+// Function to show the seen and not seen species of a list
+pub fn display_list_report(options: &mut SettingsText, report: &ListReport) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+    let my_green: color::Rgb = options.get_color("myGreen");
+
+    if report.seen.len() > 0 {
+        println!("{}Seen{}", color::Fg(my_light_blue), style::Reset);
+        for (species, number) in &report.seen {
+            println!("{}{:<10}  {:<40}  {:<39}  {:>6} {}{}", color::Fg(my_green), species.code, species.display_name(), species.sname,
+                        number.separate_with_spaces(), plural("sighting", *number), style::Reset);
+        }
+        println!();
+    }
+    if report.not_seen.len() > 0 {
+        println!("{}Not seen yet{}", color::Fg(my_light_blue), style::Reset);
+        for species in &report.not_seen {
+            println!("{}{:<10}  {:<40}  {:<39}{}", color::Fg(my_normal_gray), species.code, species.display_name(), species.sname, style::Reset);
+        }
+        println!();
+    }
+
+    let total = report.seen.len() + report.not_seen.len();
+    let percent = if total > 0 { report.seen.len() * 100 / total } else { 0 };
+    let mut message = format!("{}: {} of the {} species seen ({}%), {} not seen yet.", report.name,
+                            report.seen.len().separate_with_spaces(), total.separate_with_spaces(), percent,
+                            report.not_seen.len().separate_with_spaces());
+    if report.left_out > 0 {
+        message.push_str(&format!(" {} left out by the counting rules ({}).", report.left_out.separate_with_spaces(), COUNTED_KEY));
+    }
+    feedback(Feedback::Info, message);
+}
+
+
+// Function to show all the lists, with how many of their species have been seen
+pub fn display_lists(options: &mut SettingsText, rules: &CountingRules, birds: &BTreeMap<String, Species>, sightings: &Vec<Sightings>) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let lists = known_lists(options, birds);
+    if lists.len() == 0 {
+        let message = format!("There are no lists yet, make one with:  lc \"Garden\"");
+        feedback(Feedback::Info, message);
+        return
+    }
+
+    let seen: HashSet<&str> = sightings.iter().filter(|s| rules.counts_sighting(s)).map(|s| s.sname.as_str()).collect();
+    for list in &lists {
+        let on_list: Vec<&Species> = birds.values().filter(|s| s.is_on_list(list) && rules.counts_species(s)).collect();
+        let number_seen = on_list.iter().filter(|s| seen.contains(s.sname.as_str())).count();
+        println!("{}{:<40}  {:>6} species  {:>6} seen{}", color::Fg(my_normal_gray), list, on_list.len().separate_with_spaces(),
+                    number_seen.separate_with_spaces(), style::Reset);
+    }
+    println!();
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn species(code: &str, sname: &str, name: &str) -> Species {
+        let mut species = Species::new();
+        species.code = code.to_string();
+        species.sname = sname.to_string();
+        species.name = name.to_string();
+        species
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_lists() {
+        let mut options = SettingsText::default();
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        birds.insert("duye".to_string(), species("duye", "Anas undulata", "Yellow-billed Duck"));
+        birds.insert("spho".to_string(), species("spho", "Passer domesticus", "House Sparrow"));
+        birds.insert("bisor".to_string(), species("bisor", "Euplectes orix", "Southern Red Bishop"));
+        let mut sighting = Sightings::new();
+        sighting.sname = "Passer domesticus".to_string();
+        let sightings = vec![sighting];
+
+        let list = create_list("  UK   BOU ", &mut options, &birds).unwrap();
+        let added = add_to_list(&list, &vec!["duye".to_string(), "spho".to_string()], &mut birds);
+        let report = list_report("uk bou", &CountingRules::from_options(&options).unwrap(), &birds, &sightings);
+
+        assert_eq!(list, "UK BOU");
+        assert_eq!(create_list("uk bou", &mut options, &birds).is_err(), true);
+        assert_eq!(find_list("Garden", &options, &birds).is_err(), true);
+        assert_eq!(added, 2);
+        assert_eq!(report.seen.len(), 1);
+        assert_eq!(report.not_seen[0].code, "duye");
+        assert_eq!(remove_from_list("UK BOU", &vec!["duye".to_string()], &mut birds), 1);
+        assert_eq!(delete_list("UK BOU", &mut options, &mut birds).unwrap(), 1);
+        assert_eq!(known_lists(&options, &birds).len(), 0);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_counting_rules() {
+        let mut options = SettingsText::default();
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        for (code, sname, name, category) in [("duye", "Anas undulata", "Yellow-billed Duck", Category::Species),
+                                              ("spho", "Passer domesticus", "House Sparrow", Category::Introduced),
+                                              ("mall", "Anas platyrhynchos", "Mallard", Category::Escapee)] {
+            let mut bird = species(code, sname, name);
+            bird.category = category;
+            bird.lists = vec!["Garden".to_string()];
+            birds.insert(code.to_string(), bird);
+        }
+        let mut dead = Sightings::new();
+        dead.sname = "Anas undulata".to_string();
+        dead.dead = true;
+        let sightings = vec![dead];
+
+        let rules = CountingRules::from_options(&options).unwrap();
+        let report = list_report("Garden", &rules, &birds, &sightings);
+        assert_eq!(report.seen.len(), 1);
+        assert_eq!(report.not_seen.len(), 1);
+        assert_eq!(report.left_out, 1);
+
+        options.map.insert(COUNTED_KEY.to_string(), Sdata { value: "species, escaped, sp.".to_string(), show: true });
+        options.map.insert("deadBirdIsSighting".to_string(), Sdata { value: "false".to_string(), show: true });
+        let rules = CountingRules::from_options(&options).unwrap();
+        let report = list_report("Garden", &rules, &birds, &sightings);
+        assert_eq!(rules.categories, [Category::Species, Category::Escapee, Category::Spuh]);
+        assert_eq!(report.seen.len(), 0);
+        assert_eq!(report.not_seen.len(), 2);
+        assert_eq!(report.left_out, 1);
+
+        options.map.insert(COUNTED_KEY.to_string(), Sdata { value: "species, wild".to_string(), show: true });
+        assert_eq!(CountingRules::from_options(&options).is_err(), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_species_from_file() {
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        birds.insert("duye".to_string(), species("duye", "Anas undulata", "Yellow-billed Duck"));
+        birds.insert("spho".to_string(), species("spho", "Passer domesticus", "House Sparrow"));
+
+        let (codes, not_found) = species_from_file("./test/store/species/garden_list.txt", &birds).unwrap();
+
+        assert_eq!(codes, vec!["spho".to_string(), "duye".to_string()]);
+        assert_eq!(not_found, vec!["Dodo".to_string()]);
+    }
+}
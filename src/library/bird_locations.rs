@@ -0,0 +1,511 @@
+/*
+        The registry of locations, so the same place is written the same way in every sighting.
+
+        A location is a site in a town, in a province and in a country. It has an id, other names
+        the site is known by (aliases) and maybe its coordinates. Every sighting has the id of its
+        location (Sightings.location_id) and still carries the names, so all the searches and the
+        displays work as before. The registry keeps those names the same as the location's:
+
+            a sighting whose names are not those of its location is looked up again by the
+            names (ignoring case and spaces, and the site can be an alias) and gets the names
+            of the location it finds, or a new location is made from it.
+
+        That is done when the sightings are loaded and after every command.
+
+        An id is never given out twice: a location that was merged away stays in the registry with
+        the id it was merged into (Place.merged_into), so the highest id is always still there.
+
+            lo      list the locations by country, province and town
+            loe     change the names, aliases or coordinates of a location, its sightings follow
+            lom     merge a location into another, its sightings and names go along
+
+        The BinStore keeps the registry in locations.json, the SqliteStore in a locations table.
+
+        2026.10.16   Sven Ponelat
+*/
+
+use crate::library::bird_sightings::*;
+use crate::library::backups::*;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use serde::{Serialize, Deserialize};
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+
+pub const LOCATIONS_FILENAME: &str = "locations.json";
+
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub id: u32,
+    pub site: String,
+    pub town: String,
+    pub province: String,
+    pub country: String,
+    // Other names of the site, a sighting with one of them is put in this location
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub coordinates: Option<Coordinates>,
+    // The location this one was merged into, 0 while it is in use
+    #[serde(default)]
+    pub merged_into: u32,
+}
+
+
+impl Place {
+
+    // Function to make a location from where a sighting was
+    pub fn from_sighting(id: u32, sighting: &Sightings) -> Place {
+        Place {
+            id: id,
+            site: sighting.location.clone(),
+            town: sighting.town.clone(),
+            province: sighting.province.clone(),
+            country: sighting.country.clone(),
+            aliases: Vec::new(),
+            coordinates: sighting.coordinates,
+            merged_into: 0,
+        }
+    }
+
+
+    // Function to tell if the location was merged into another one, so it is only kept for its id
+    pub fn is_merged(&self) -> bool {
+        self.merged_into != 0
+    }
+
+
+    // Function to tell if the sighting has exactly the names of this location
+    pub fn names_match(&self, sighting: &Sightings) -> bool {
+        sighting.location == self.site && sighting.town == self.town
+            && sighting.province == self.province && sighting.country == self.country
+    }
+
+
+    // Function to give the names of this location to a sighting
+    pub fn name_sighting(&self, sighting: &mut Sightings) {
+        sighting.location_id = self.id;
+        sighting.location = self.site.clone();
+        sighting.town = self.town.clone();
+        sighting.province = self.province.clone();
+        sighting.country = self.country.clone();
+    }
+
+
+    // Function to give what the location is looked up by, its names and the names with every alias
+    fn keys(&self) -> Vec<String> {
+        let mut ret = vec![place_key(&self.site, &self.town, &self.province, &self.country)];
+        for alias in &self.aliases {
+            ret.push(place_key(alias, &self.town, &self.province, &self.country));
+        }
+        ret
+    }
+
+
+    // Function to tell if the site is called this, by its name or an alias
+    pub fn is_called(&self, name: &str) -> bool {
+        let name = normalize(name);
+        normalize(&self.site) == name || self.aliases.iter().any(|alias| normalize(alias) == name)
+    }
+
+
+    // Function to tell if the name of the site or one of its aliases has this in it, as an a= search looks
+    pub fn site_has(&self, text: &str) -> bool {
+        let text = normalize(text);
+        normalize(&self.site).contains(&text) || self.aliases.iter().any(|alias| normalize(alias).contains(&text))
+    }
+
+
+    // Function to describe the location, e.g. "Delta Park, Johannesburg, Gauteng, South Africa"
+    pub fn describe(&self) -> String {
+        let parts = [&self.site, &self.town, &self.province, &self.country];
+        parts.iter().filter(|part| part.trim().len() > 0).map(|part| part.as_str()).collect::<Vec<&str>>().join(", ")
+    }
+}
+
+
+// Function to make a name comparable, the case and the spaces do not matter
+fn normalize(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<&str>>().join(" ").to_lowercase()
+}
+
+
+// Function to make the key a location is looked up by
+fn place_key(site: &str, town: &str, province: &str, country: &str) -> String {
+    format!("{}|{}|{}|{}", normalize(site), normalize(town), normalize(province), normalize(country))
+}
+
+
+// Function to get the id the next new location gets, the merged ones are still there so no id comes back
+fn next_place_id(places: &BTreeMap<u32, Place>) -> u32 {
+    match places.keys().next_back() {
+        Some(id) => { id + 1 }
+        None     => { 1 }
+    }
+}
+
+
+// Function to count the locations that are in use
+pub fn count_places(places: &BTreeMap<u32, Place>) -> usize {
+    places.values().filter(|place| !place.is_merged()).count()
+}
+
+
+// Function to read the registry, a missing file is just an empty one
+pub fn load_places(json_file: &str) -> Result<BTreeMap<u32, Place>, String> {
+    if !Path::new(json_file).exists() {
+        return Ok(BTreeMap::new())
+    }
+    let text = match std::fs::read_to_string(json_file) {
+        Ok(text) => { text }
+        Err(_)   => { return Err(format!("Problem reading the locations -> {}", json_file)); }
+    };
+    let list: Vec<Place> = match serde_json::from_str(&text) {
+        Ok(list) => { list }
+        Err(_)   => { return Err(format!("The locations file is corrupt, restore it or move it out of the way -> {}", json_file)); }
+    };
+    Ok(list.into_iter().map(|place| (place.id, place)).collect())
+}
+
+
+// Function to write the registry
+pub fn save_places(json_file: &str, places: &BTreeMap<u32, Place>) -> Result<(), String> {
+    let list: Vec<&Place> = places.values().collect();
+    let text = match serde_json::to_string_pretty(&list) {
+        Ok(text) => { text }
+        Err(_)   => { return Err("Problem writing the locations".to_string()); }
+    };
+    atomic_write(json_file, text.as_bytes())
+}
+
+
+// Function to put every sighting in the registry. A sighting that does not have the names of its
+// location is looked up by its names, or a new location is made for it. Gives how many sightings changed.
+pub fn register_sightings(places: &mut BTreeMap<u32, Place>, sightings: &mut Vec<Sightings>) -> usize {
+    let mut index: HashMap<String, u32> = HashMap::new();
+    for place in places.values().filter(|place| !place.is_merged()) {
+        for key in place.keys() {
+            index.entry(key).or_insert(place.id);
+        }
+    }
+
+    let mut changed = 0;
+    for sighting in sightings.iter_mut() {
+        if let Some(place) = places.get(&sighting.location_id) {
+            if !place.is_merged() && place.names_match(sighting) {
+                continue;
+            }
+        }
+
+        let key = place_key(&sighting.location, &sighting.town, &sighting.province, &sighting.country);
+        let id = match index.get(&key) {
+            Some(id) => { *id }
+            None     => {
+                let id = next_place_id(places);
+                places.insert(id, Place::from_sighting(id, sighting));
+                index.insert(key, id);
+                id
+            }
+        };
+        let place = places.get_mut(&id).unwrap();
+        if place.coordinates.is_none() {
+            place.coordinates = sighting.coordinates;
+        }
+        place.name_sighting(sighting);
+        changed += 1;
+    }
+
+    // The names may have changed, so the order may have too
+    if changed > 0 {
+        sightings.sort();
+    }
+    changed
+}
+
+
+// Function to find a location by its id, or by the name (or an alias) of its site
+pub fn find_place(text: &str, places: &BTreeMap<u32, Place>) -> Result<u32, String> {
+    if let Ok(id) = text.trim().parse::<u32>() {
+        return match places.get(&id) {
+            Some(place) if place.is_merged() => { Err(format!("Location {} was merged into {}", id, place.merged_into)) }
+            Some(_)                          => { Ok(id) }
+            None                             => { Err(format!("There is no location with the id {}", id)) }
+        }
+    }
+
+    let found: Vec<&Place> = places.values().filter(|place| !place.is_merged() && place.is_called(text)).collect();
+    match found.len() {
+        0 => { Err(format!("There is no location called {}, see  lo", text)) }
+        1 => { Ok(found[0].id) }
+        _ => {
+            let which: Vec<String> = found.iter().map(|place| format!("{} ({})", place.id, place.describe())).collect();
+            Err(format!("There are {} locations called {}, use the id: {}", found.len(), text, which.join("   ")))
+        }
+    }
+}
+
+
+// Function to change a location with terms like "a=Delta Park#w=Johannesburg#k=Delta,Delta park#g=-26.13,28.01",
+// the sightings of the location get the new names. Gives how many sightings changed.
+pub fn edit_place(id: u32, arg: &str, places: &mut BTreeMap<u32, Place>, sightings: &mut Vec<Sightings>) -> Result<usize, String> {
+    let mut place = match places.get(&id) {
+        Some(place) if !place.is_merged() => { place.clone() }
+        _                                 => { return Err(format!("There is no location with the id {}", id)); }
+    };
+    let old_site = place.site.clone();
+
+    let mut terms_str = arg.to_string();
+    terms_str.retain(|c| !r#"""#.contains(c));
+    for line in terms_str.split("#") {
+        let vec1: Vec<&str> = line.split("=").collect();
+        if vec1.len() != 2 || vec1[0].trim().len() == 0 {
+            return Err(format!("Not a valid location term -> {}   (like a=Delta Park#w=Johannesburg)", line))
+        }
+        let first = vec1[0].trim().to_lowercase().chars().nth(0).unwrap();
+        match first {
+            'a' => { place.site = vec1[1].trim().to_string(); }
+            'w' => { place.town = title_case(vec1[1].trim()).trim().to_string(); }
+            'p' => { place.province = title_case(vec1[1].trim()).trim().to_string(); }
+            't' => { place.country = title_case(vec1[1].trim()).trim().to_string(); }
+            'k' => {
+                // The aliases, all of them (k= takes them away)
+                place.aliases = vec1[1].split(',').map(|alias| alias.trim().to_string()).filter(|alias| alias.len() > 0).collect();
+            }
+            'g' => {
+                if vec1[1].trim().len() == 0 {
+                    place.coordinates = None;
+                } else {
+                    let coordinates = Coordinates::from_text(vec1[1]);
+                    if coordinates.is_err(){
+                        return Err(coordinates.err().unwrap())
+                    }
+                    place.coordinates = Some(coordinates.unwrap());
+                }
+            }
+            _   => { return Err(format!("Not a valid location term -> {}   (a w p t k or g)", line)) }
+        }
+    }
+
+    if place.town.len() == 0 {
+        return Err("Error in giving no town".to_string());
+    }
+    if place.country.len() == 0 {
+        return Err("Error in giving no country".to_string());
+    }
+
+    // The old name of the site is still known
+    if old_site.trim().len() > 0 && !place.is_called(&old_site) {
+        place.aliases.push(old_site);
+    }
+    let site = normalize(&place.site);
+    let mut seen: Vec<String> = vec![site];
+    place.aliases.retain(|alias| {
+        let alias = normalize(alias);
+        if seen.contains(&alias) {
+            return false
+        }
+        seen.push(alias);
+        true
+    });
+
+    for key in place.keys() {
+        if let Some(other) = places.values().find(|other| other.id != id && !other.is_merged() && other.keys().contains(&key)) {
+            return Err(format!("That is already location {} ({}), merge them with  lom {} {}", other.id, other.describe(), id, other.id))
+        }
+    }
+
+    let mut changed = 0;
+    for sighting in sightings.iter_mut().filter(|sighting| sighting.location_id == id) {
+        place.name_sighting(sighting);
+        changed += 1;
+    }
+    places.insert(id, place);
+    sightings.sort();
+    Ok(changed)
+}
+
+
+// Function to merge a location into another one, its sightings move and its names become aliases.
+// The old one is kept, marked as merged, so its id is not used again. Gives how many sightings moved.
+pub fn merge_places(from: u32, into: u32, places: &mut BTreeMap<u32, Place>, sightings: &mut Vec<Sightings>) -> Result<usize, String> {
+    if from == into {
+        return Err("A location cannot be merged into itself".to_string())
+    }
+    if !places.get(&into).is_some_and(|place| !place.is_merged()) {
+        return Err(format!("There is no location with the id {}", into))
+    }
+    let old = match places.get_mut(&from) {
+        Some(old) if !old.is_merged() => { old.merged_into = into; old.clone() }
+        _                             => { return Err(format!("There is no location with the id {}", from)); }
+    };
+
+    let place = places.get_mut(&into).unwrap();
+    let mut names = vec![old.site.clone()];
+    names.extend(old.aliases.iter().cloned());
+    for name in names {
+        if name.trim().len() > 0 && !place.is_called(&name) {
+            place.aliases.push(name);
+        }
+    }
+    if place.coordinates.is_none() {
+        place.coordinates = old.coordinates;
+    }
+
+    let place = place.clone();
+    let mut moved = 0;
+    for sighting in sightings.iter_mut().filter(|sighting| sighting.location_id == from) {
+        place.name_sighting(sighting);
+        moved += 1;
+    }
+    sightings.sort();
+    Ok(moved)
+}
+
+
+// Function to show the locations by country, province and town, with how many sightings each has.
+// With a text only the locations that have it in their names or aliases.
+pub fn display_places(options: &mut SettingsText, places: &BTreeMap<u32, Place>, sightings: &Vec<Sightings>, text: Option<&str>) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_dark_gray: color::Rgb = options.get_color("myDarkGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for sighting in sightings {
+        *counts.entry(sighting.location_id).or_insert(0) += 1;
+    }
+
+    let wanted = normalize(text.unwrap_or(""));
+    let mut list: Vec<&Place> = places.values()
+        .filter(|place| !place.is_merged())
+        .filter(|place| normalize(&place.describe()).contains(&wanted)
+                        || place.aliases.iter().any(|alias| normalize(alias).contains(&wanted)))
+        .collect();
+    list.sort_by_key(|place| (normalize(&place.country), normalize(&place.province), normalize(&place.town), normalize(&place.site)));
+
+    let (mut country, mut province, mut town) = (None, None, None);
+    for place in &list {
+        if country != Some(&place.country) {
+            println!("{}{}{}", color::Fg(my_light_blue), place.country, style::Reset);
+            country = Some(&place.country);
+            province = None;
+        }
+        if province != Some(&place.province) {
+            println!("    {}{}{}", color::Fg(my_light_blue), place.province, style::Reset);
+            province = Some(&place.province);
+            town = None;
+        }
+        if town != Some(&place.town) {
+            println!("        {}{}{}", color::Fg(my_light_blue), place.town, style::Reset);
+            town = Some(&place.town);
+        }
+
+        let site = if place.site.trim().len() == 0 { "(no site)".to_string() } else { place.site.clone() };
+        let count = *counts.get(&place.id).unwrap_or(&0);
+        let coordinates = match place.coordinates {
+            Some(coordinates) => { coordinates.describe() }
+            None              => { "".to_string() }
+        };
+        let mut line = format!("{:>12}  {:<40}  {:>7} {:<9}  {:<34}", place.id, limit_length(site, 40),
+                                count.separate_with_spaces(), plural("sighting", count), coordinates);
+        let also = match place.aliases.len() {
+            0 => { line = line.trim_end().to_string(); "".to_string() }
+            _ => { format!("  also {}", place.aliases.join(", ")) }
+        };
+        println!("{}{}{}{}{}", color::Fg(my_normal_gray), line, color::Fg(my_dark_gray), also, style::Reset);
+    }
+    if list.len() > 0 {
+        println!();
+    }
+
+    match text {
+        Some(text) if list.len() == 0 => {
+            let message = format!("There is no location with {} in it.", text);
+            feedback(Feedback::Warning, message);
+        }
+        _ => {
+            let message = format!("{} {} of {}.", list.len().separate_with_spaces(), plural("location", list.len()),
+                                    count_places(places).separate_with_spaces());
+            feedback(Feedback::Info, message);
+        }
+    }
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn sighting(location: &str, town: &str) -> Sightings {
+        let mut sighting = Sightings::new();
+        sighting.sname = "Anas undulata".to_string();
+        sighting.location = location.to_string();
+        sighting.town = town.to_string();
+        sighting.province = "Gauteng".to_string();
+        sighting.country = "South Africa".to_string();
+        sighting
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_register_sightings() {
+        let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+        let mut sightings = vec![sighting("Delta Park", "Johannesburg"), sighting("delta  park", "johannesburg"),
+                                 sighting("Zoo Lake", "Johannesburg")];
+
+        let changed = register_sightings(&mut places, &mut sightings);
+        let again = register_sightings(&mut places, &mut sightings);
+
+        assert_eq!(changed, 3);
+        assert_eq!(again, 0);
+        assert_eq!(places.len(), 2);
+        assert_eq!(sightings.iter().filter(|s| s.location == "Delta Park" && s.town == "Johannesburg").count(), 2);
+        assert_eq!(find_place("zoo lake", &places), Ok(sightings[2].location_id));
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_edit_and_merge() {
+        let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+        let mut sightings = vec![sighting("Delta Park", "Johannesburg"), sighting("Delta", "Johannesburg")];
+        register_sightings(&mut places, &mut sightings);
+        let park = find_place("Delta Park", &places).unwrap();
+        let delta = find_place("Delta", &places).unwrap();
+
+        let renamed = edit_place(park, "a=Delta Park Conservancy#g=-26.13,28.01", &mut places, &mut sightings).unwrap();
+        let clash = edit_place(delta, "a=delta park conservancy", &mut places, &mut sightings);
+        let moved = merge_places(delta, park, &mut places, &mut sightings).unwrap();
+
+        // A sighting with an old name goes in the merged location, a new location does not get the merged id
+        sightings.push(sighting("Delta", "Johannesburg"));
+        sightings.push(sighting("Zoo Lake", "Johannesburg"));
+        register_sightings(&mut places, &mut sightings);
+        let zoo = find_place("Zoo Lake", &places).unwrap();
+
+        assert_eq!(renamed, 1);
+        assert_eq!(clash.is_err(), true);
+        assert_eq!(moved, 1);
+        assert_eq!(count_places(&places), 2);
+        assert_eq!(places[&delta].merged_into, park);
+        assert_eq!(find_place(&delta.to_string(), &places).is_err(), true);
+        assert_eq!(places[&park].aliases, vec!["Delta Park".to_string(), "Delta".to_string()]);
+        assert_eq!(places[&park].coordinates.is_some(), true);
+        assert_eq!(zoo > delta, true);
+        assert_eq!(sightings.iter().filter(|s| s.location_id == park && s.location == "Delta Park Conservancy").count(), 3);
+    }
+}
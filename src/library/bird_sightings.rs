@@ -14,6 +14,9 @@ use super::bird_sightings_supp::*;
 // use super::bird_sightings_box::show_sighting;
 // use super::bird_species;
 use super::settings::SettingsText;
+use crate::library::data_dir::*;
+use crate::library::backups::*;
+use crate::library::bin_format::*;
 use std::io::prelude::*;
 use std::path::Path;
 use std::fs::{ OpenOptions };
@@ -28,23 +31,28 @@ use substring::Substring;
 use termion::{color, style};
 use chrono::prelude::*;
 use chrono::Utc;
-use std::time::{UNIX_EPOCH, Duration};
 // use core::str::ParseBoolError;
 use std::io::{BufReader, BufWriter};
 use std::fs::File;
+use std::collections::HashSet;
+use ulid::{Generator, Ulid};
+use std::sync::Mutex;
 
 
 #[allow(dead_code)]
-pub const SIGHTINGS_BIN_FILENAME:  &str = "./sightings.bin";
+pub const SIGHTINGS_BIN_FILENAME:  &str = "sightings.bin";
 #[allow(dead_code)]
-pub const SIGHTINGS_JSON_FILENAME: &str = "./sightings.json";
+pub const SIGHTINGS_JSON_FILENAME: &str = "sightings.json";
 
 pub const N_LEN:     usize =  7;
 pub const P_LEN:     usize =  9;
 // pub const CODE_LEN:  usize = 10;
-pub const DATE_LEN:  usize = 12;
+pub const DATE_LEN:  usize = 18;
 pub const NAME_39:   usize = 39;
 pub const FAMILY_59: usize = 59;
+pub const MINUTES_IN_DAY: u16 = 1440;
+
+static ID_GENERATOR: Mutex<Generator> = Mutex::new(Generator::new());
 
 
 #[allow(non_snake_case)]
@@ -52,7 +60,11 @@ pub const FAMILY_59: usize = 59;
 #[derivative(Default)]
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 pub struct Sightings {
+    // The local day (at midnight UTC), so the day is the same wherever it is shown
     pub date: i64,
+    // The local time of day in minutes after midnight, if noted. Next to the date so a day sorts by it
+    #[serde(default)]
+    pub time: Option<u16>,
     pub sname: String,
     pub location: String,
     pub town: String,
@@ -73,6 +85,261 @@ pub struct Sightings {
     pub nest: bool,
     pub chicks: bool,
     pub comments: String,
+    // Stays the same for the life of the sighting (a ULID), unlike its position
+    #[serde(default)]
+    pub id: String,
+    // Just the third name, the sighting still counts for the species (sname)
+    #[serde(default)]
+    pub subspecies: String,
+    // How many birds, zero when they were not counted
+    #[serde(default)]
+    pub count: Counts,
+    // The time zone of the time, in minutes ahead of UTC
+    #[serde(default)]
+    pub zone: Option<i16>,
+    // How much of the date is known, the date is then the first day of the year or month
+    #[serde(default)]
+    pub precision: Precision,
+    // Where it was seen, if known (from a GPS)
+    #[serde(default)]
+    pub coordinates: Option<Coordinates>,
+    // The location in the registry (bird_locations), 0 until it has been put in there
+    #[serde(default)]
+    pub location_id: u32,
+}
+
+
+// A position on earth. The degrees are kept in millionths (about 10 cm), so they compare exactly
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Coordinates {
+    pub latitude: i32,
+    pub longitude: i32,
+    // How close the position is, in metres (0 when not known)
+    pub accuracy: u32,
+}
+
+
+impl Coordinates {
+
+    // Function to read coordinates in degrees like "-26.1,28.0" or with the accuracy "-26.1,28.0,10m"
+    pub fn from_text(text: &str) -> Result<Coordinates, String> {
+        let message = format!("Coordinates error: {}   (like -26.1,28.0 or -26.1,28.0,10m)", text);
+        let parts: Vec<&str> = text.split(',').map(|p| p.trim()).collect();
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(message)
+        }
+        let latitude = parts[0].parse::<f64>();
+        let longitude = parts[1].parse::<f64>();
+        if latitude.is_err() || longitude.is_err(){
+            return Err(message)
+        }
+        let (latitude, longitude) = (latitude.unwrap(), longitude.unwrap());
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(format!("Coordinates error: the latitude goes from -90 to 90 and the longitude from -180 to 180 -> {}", text))
+        }
+        let mut accuracy = 0;
+        if parts.len() == 3 {
+            let metres = convert_distance_text(parts[2], 1.0);
+            if metres.is_err(){
+                return Err(metres.err().unwrap())
+            }
+            accuracy = metres.unwrap().round() as u32;
+        }
+
+        Ok(Coordinates {
+            latitude: (latitude * 1_000_000.0).round() as i32,
+            longitude: (longitude * 1_000_000.0).round() as i32,
+            accuracy: accuracy,
+        })
+    }
+
+
+    pub fn latitude_degrees(&self) -> f64 {
+        self.latitude as f64 / 1_000_000.0
+    }
+
+
+    pub fn longitude_degrees(&self) -> f64 {
+        self.longitude as f64 / 1_000_000.0
+    }
+
+
+    // Function to describe the position, e.g. "-26.100000, 28.000000   (10 m)"
+    pub fn describe(&self) -> String {
+        let ret = format!("{:.6}, {:.6}", self.latitude_degrees(), self.longitude_degrees());
+        match self.accuracy {
+            0 => { ret }
+            _ => { format!("{}   ({} m)", ret, self.accuracy) }
+        }
+    }
+
+
+    // Function to give the distance in metres to another position (along a great circle)
+    pub fn distance_to(&self, other: &Coordinates) -> f64 {
+        let earth_radius = 6_371_000.0;
+        let (lat1, lat2) = (self.latitude_degrees().to_radians(), other.latitude_degrees().to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lon = (other.longitude_degrees() - self.longitude_degrees()).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * earth_radius * a.sqrt().asin()
+    }
+}
+
+
+// Function to read a distance like 5km or 250m into metres, a plain number is in the unit given (1000.0 for km)
+pub fn convert_distance_text(text: &str, unit: f64) -> Result<f64, String> {
+    let lower = text.trim().to_lowercase();
+    let (number, unit) = if lower.ends_with("km") {
+        (lower.trim_end_matches("km"), 1000.0)
+    } else if lower.ends_with('m') {
+        (lower.trim_end_matches('m'), 1.0)
+    } else {
+        (&lower[..], unit)
+    };
+    let value = number.trim().parse::<f64>();
+    if value.is_err() || value.clone().unwrap() < 0.0 {
+        return Err(format!("Something wrong in the distance -> {}   (like 5km or 250m)", text))
+    }
+    Ok(value.unwrap() * unit)
+}
+
+
+// How much of the date of a sighting is known, an old notebook may only have the year.
+// It is kept as its text (year, month, day, time), like the status of a species.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Precision {
+    Year,
+    Month,
+    #[default]
+    Day,
+    Time,
+}
+
+
+impl Precision {
+    pub fn code(&self) -> &str {
+        match self {
+            Precision::Year  => { "year" }
+            Precision::Month => { "month" }
+            Precision::Day   => { "day" }
+            Precision::Time  => { "time" }
+        }
+    }
+}
+
+
+impl From<String> for Precision {
+    fn from(text: String) -> Precision {
+        match text.to_lowercase().trim() {
+            "year"  => { Precision::Year }
+            "month" => { Precision::Month }
+            "time"  => { Precision::Time }
+            _       => { Precision::Day }
+        }
+    }
+}
+
+
+impl From<Precision> for String {
+    fn from(precision: Precision) -> String {
+        precision.code().to_string()
+    }
+}
+
+
+// The birds counted in a sighting, the total and (when known) by sex and age
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Counts {
+    pub total: u32,
+    pub male: u32,
+    pub female: u32,
+    pub adult: u32,
+    pub immature: u32,
+    pub chicks: u32,
+}
+
+
+impl Counts {
+
+    // Function to read counts like "42 3m 1f 2i", a plain number is the total
+    // and a number with m, f, a, i or c counts the males, females, adults, immatures or chicks
+    pub fn from_text(text: &str) -> Result<Counts, String> {
+        let mut ret = Counts::default();
+        let mut total_given = false;
+
+        for word in text.split(|c| c == ' ' || c == ',').filter(|w| w.trim().len() > 0) {
+            let word = word.trim().to_lowercase();
+            let (number, class) = match word.chars().last() {
+                Some(c) if c.is_ascii_alphabetic() => { (&word[..word.len() - 1], Some(c)) }
+                _                                  => { (&word[..], None) }
+            };
+            let r_number = number.parse::<u32>();
+            if r_number.is_err(){
+                return Err(format!("Count error: {} is not a number (like 12, or 3m 1f 2a 4i 5c)", word))
+            }
+            let number = r_number.unwrap();
+            match class {
+                None      => { ret.total = number; total_given = true; }
+                Some('m') => { ret.male = number; }
+                Some('f') => { ret.female = number; }
+                Some('a') => { ret.adult = number; }
+                Some('i') => { ret.immature = number; }
+                Some('c') => { ret.chicks = number; }
+                Some(_)   => {
+                    return Err(format!("Count error: {} should end in m, f, a, i or c", word))
+                }
+            }
+        }
+
+        let by_sex = ret.male.checked_add(ret.female);
+        let by_age = ret.adult.checked_add(ret.immature).and_then(|n| n.checked_add(ret.chicks));
+        if by_sex.is_none() || by_age.is_none(){
+            return Err(format!("Count error: too many birds to add up -> {}", text.trim()))
+        }
+        let (by_sex, by_age) = (by_sex.unwrap(), by_age.unwrap());
+        if !total_given {
+            ret.total = by_sex.max(by_age);
+        }
+        if ret.total < by_sex || ret.total < by_age {
+            return Err(format!("Count error: a total of {} is less than the birds counted by sex ({}) or age ({})",
+                                ret.total, by_sex, by_age))
+        }
+
+        Ok(ret)
+    }
+
+
+    // Function to give the counts back the way from_text reads them, empty when not counted
+    pub fn text(&self) -> String {
+        if self.total == 0 {
+            return "".to_string()
+        }
+        let mut ret = vec![self.total.to_string()];
+        let classes = [(self.male, 'm'), (self.female, 'f'), (self.adult, 'a'), (self.immature, 'i'), (self.chicks, 'c')];
+        for (number, class) in classes {
+            if number > 0 {
+                ret.push(format!("{}{}", number, class));
+            }
+        }
+        ret.join(" ")
+    }
+
+
+    // Function to describe the counts, e.g. "42 (3 male, 1 female)"
+    pub fn describe(&self) -> String {
+        if self.total == 0 {
+            return "".to_string()
+        }
+        let classes = [(self.male, "male"), (self.female, "female"), (self.adult, "adult"),
+                       (self.immature, "immature"), (self.chicks, "chicks")];
+        let parts: Vec<String> = classes.iter().filter(|(number, _)| *number > 0)
+                                        .map(|(number, class)| format!("{} {}", number, class)).collect();
+        if parts.len() == 0 {
+            return self.total.to_string()
+        }
+        format!("{} ({})", self.total, parts.join(", "))
+    }
 }
 
 
@@ -127,11 +394,16 @@ impl Sightings {
         let time_only = NaiveTime::from_hms(0, 0, 0); 
         let date_time: NaiveDateTime;
         let i_date: i64;
-        if p_date.is_ok(){
+        let mut i_precision = Precision::Day;
+        if let Some((year_month, precision)) = convert_year_month(t_date) {
+            // Only the year, or the year and month, are known
+            i_date = year_month;
+            i_precision = precision;
+        } else if p_date.is_ok(){
             date_time = p_date.unwrap().and_time(time_only);
             i_date = date_time.timestamp();
         } else {
-            return Err("Error in parsing the date string, use something like 2022.02.13".to_string());
+            return Err("Error in parsing the date string, use something like 2022.02.13 (or 1987.05, 1974)".to_string());
         }
 
         // Lets do the location                                                                 -- location
@@ -218,6 +490,7 @@ impl Sightings {
         Ok(Sightings {
             sname:  i_sname,
             date: i_date,
+            time: None,
             location: i_location,
             town: i_town,
             province: i_province,
@@ -236,7 +509,14 @@ impl Sightings {
             nonbreeding: i_nonbreeding,
             nest: i_nest,
             chicks: i_chicks,
-            comments: i_comments.to_string()
+            comments: i_comments.to_string(),
+            id: new_sighting_id(),
+            subspecies: "".to_string(),
+            count: Counts::default(),
+            zone: None,
+            precision: i_precision,
+            coordinates: None,
+            location_id: 0,
         })
             
     }   // end of build_species
@@ -265,11 +545,16 @@ impl Sightings {
     }
 
     pub fn export_path(extension: &str, options: &mut SettingsText ) -> String {
-        let mut assembly = "./".to_string();
+        let mut assembly = String::new();
         assembly.push_str(&options.date_time_str());
         assembly.push_str("sightings.");
         assembly.push_str(extension);
-        assembly
+        data_file(&assembly)
+    }
+
+    // The full path of the sightings bin file in the data directory
+    pub fn bin_path() -> String {
+        data_file(SIGHTINGS_BIN_FILENAME)
     }
 
     pub fn export_csv(csv_file: &str, sights: &Vec<Sightings>) -> Result<(), String> {
@@ -288,7 +573,7 @@ impl Sightings {
         let mut file = BufWriter::new(f);
         
         // Do Header
-        let header = "sname\tdate\tlocation\ttown\tprovince\tcountry\tseen\theard\tringed\tdead\tphoto\tmale\tfemale\tadult\timmature\tbreeding\teggs\tnonbreeding\tnest\tchicks\tcomments\n";
+        let header = "sname\tdate\tlocation\ttown\tprovince\tcountry\tseen\theard\tringed\tdead\tphoto\tmale\tfemale\tadult\timmature\tbreeding\teggs\tnonbreeding\tnest\tchicks\tcomments\tid\tsubspecies\tcount\ttime\tzone\tlatitude\tlongitude\taccuracy\n";
 
         match file.write_all(header.as_bytes()) {
             Err(_) => { return Err("Problem writing sightings in csv file".to_string()); } 
@@ -342,6 +627,21 @@ impl Sightings {
             assembly.push_str(&v.chicks.to_string());
             assembly.push('\t');
             assembly.push_str(&v.comments.to_string());
+            assembly.push('\t');
+            assembly.push_str(&v.id);
+            assembly.push('\t');
+            assembly.push_str(&v.subspecies);
+            assembly.push('\t');
+            assembly.push_str(&v.count.text());
+            assembly.push('\t');
+            assembly.push_str(&v.display_time());
+            assembly.push('\t');
+            assembly.push_str(&v.display_zone());
+            let position = match v.coordinates {
+                Some(c) => { format!("\t{:.6}\t{:.6}\t{}", c.latitude_degrees(), c.longitude_degrees(), c.accuracy) }
+                None    => { "\t\t\t".to_string() }
+            };
+            assembly.push_str(&position);
             assembly.push('\n');
             
             match file.write_all(assembly.as_bytes()) {
@@ -364,10 +664,13 @@ impl Sightings {
             Err(_) => { return Err("Problem importing species json file".to_string()); }
         };
         
-        let map: Vec<Sightings> = match serde_json::from_str(&content){
+        let mut map: Vec<Sightings> = match serde_json::from_str(&content){
             Ok(map) => map,
             Err(_) => { return Err("Problem converting species json file".to_string()); }
         };
+        
+        // Older exports have no ids
+        ensure_sighting_ids(&mut map);
         Ok(map)
     }
     
@@ -400,6 +703,7 @@ impl Sightings {
                 
             }
         }
+        ensure_sighting_ids(&mut s);
 
 
         Ok(s)
@@ -408,60 +712,66 @@ impl Sightings {
     pub fn save(bin_file: &str, sightings: &Vec<Sightings>) -> Result<(), String> {
         
         // let encoded:  BTreeMap<String,Species>;
-        let encoded: Vec<u8> = bincode::serialize(sightings).unwrap();
+        let payload: Vec<u8> = bincode::serialize(sightings).unwrap();
+        let encoded = add_header(FileKind::Sightings, SIGHTINGS_FORMAT_VERSION, &payload);
                 
-        // Lets open the bin file
-        let mut file = match OpenOptions::new()
-            .read(false)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(bin_file){
-                Ok(content) => content,
-                Err(_) => { return Err("Problem saving sightings bin file".to_string()); }
-        };
-        
-        match file.write_all(&encoded) {
-            Ok(file) => file,
-            Err(_) => { return Err("Problem writing to sightings bin file".to_string()); }
+        // Write next to the old file and swap it in, so a crash never leaves half a file
+        let res = atomic_write(bin_file, &encoded);
+        if res.is_err(){
+            return Err(res.err().unwrap())
         }
         Ok(())
     }
 
 
-    // Load all the bird species from binary file into map
-    pub fn load(bin_file: &str) -> Result<Vec<Sightings>, String> {
+    // Load all the bird sightings from binary file into vector
+    pub fn load(bin_file: &str) -> Result<Vec<Sightings>, LoadError> {
         
         let decoded:  Vec<Sightings>;
                 
-        // Lets open the bin file
-        let mut file = match OpenOptions::new()
-            .read(true)
-            .write(false)
-            .create(false)
-            .open(bin_file){
-                Ok(content) => content,
-                Err(_) => { return Err("Problem opening species bin file".to_string()); }
-        };
+        // Lets read the bin file, a missing file is not the same as a broken one
+        let file = read_bin_file(bin_file, FileKind::Sightings);
+        if file.is_err(){
+            return Err(file.err().unwrap())
+        }
+        let buffer = file.unwrap();
         
-        let mut buffer = Vec::<u8>::new();
-        match file.read_to_end(&mut buffer) {
-            Ok(content)  => { content }
-            Err(_) => { return Err("Problem reading species bin file".to_string()); }
-        };
+        // Older files are brought up to the current layout
+        let header = read_header(&buffer[..], FileKind::Sightings, SIGHTINGS_FORMAT_VERSION);
+        if header.is_err(){
+            return Err(header.err().unwrap())
+        }
+        let (version, payload) = header.unwrap();
         
-        decoded = bincode::deserialize(&buffer[..]).unwrap();
+        let result = migrate_sightings(version, payload);
+        if result.is_err(){
+            return Err(result.err().unwrap())
+        }
+        decoded = result.unwrap();
         
         Ok(decoded)
     }
 
-    // Display the date like 2020.02.15
+    // The full name, with the subspecies when there is one
+    pub fn trinomial(&self) -> String {
+        match self.subspecies.len() {
+            0 => { self.sname.clone() }
+            _ => { format!("{} {}", self.sname, self.subspecies) }
+        }
+    }
+
+    // Display the date like 2020.02.15, or as much of it as is known (1987.05 or 1974)
     pub fn display_date(&self) -> String {
         
-        let d = UNIX_EPOCH + Duration::from_secs(self.date as u64);
-        let datetime = DateTime::<Utc>::from(d);
+        // Before 1970 the date is negative
+        let datetime = DateTime::<Utc>::from_timestamp(self.date, 0).unwrap_or_default();
 
-        let timestamp_str = datetime.format("%Y.%m.%d" ).to_string();
+        let format = match self.precision {
+            Precision::Year  => { "%Y" }
+            Precision::Month => { "%Y.%m" }
+            _                => { "%Y.%m.%d" }
+        };
+        let timestamp_str = datetime.format(format).to_string();
         // if sep == "."{
             // timestamp_str = datetime.format("%Y.%m.%d" ).to_string();
         // } else {
@@ -470,12 +780,42 @@ impl Sightings {
 
         return timestamp_str
     }    
+
+    // The last day of the year or month that the date stands for, the date itself when the day is known
+    pub fn period_end(&self) -> i64 {
+        period_end(self.date, self.precision)
+    }
+
+    // Display the time like 06:45, empty when there is none
+    pub fn display_time(&self) -> String {
+        match self.time {
+            Some(minutes) => { time_text(minutes) }
+            None          => { "".to_string() }
+        }
+    }
+
+    // Display the time zone like UTC+02:00, empty when there is none
+    pub fn display_zone(&self) -> String {
+        match self.zone {
+            Some(minutes) => { zone_text(minutes) }
+            None          => { "".to_string() }
+        }
+    }
+
+    // Display the date with the local time when there is one, like 2020.02.15 06:45
+    pub fn display_when(&self) -> String {
+        match self.time {
+            Some(minutes) => { format!("{} {}", self.display_date(), time_text(minutes)) }
+            None          => { self.display_date() }
+        }
+    }
         
     // Make an empty sighting to build on    
     pub fn new() -> Sightings {
         let ret = Sightings {
             sname:  "".to_string(),
             date: 0,
+            time: None,
             location: "".to_string(),
             town: "".to_string(),
             province: "".to_string(),
@@ -495,6 +835,13 @@ impl Sightings {
             nest: false,
             chicks: false,
             comments: "".to_string(),
+            id: new_sighting_id(),
+            subspecies: "".to_string(),
+            count: Counts::default(),
+            zone: None,
+            precision: Precision::Day,
+            coordinates: None,
+            location_id: 0,
         };
 
         return ret
@@ -615,6 +962,17 @@ impl Sightings {
         
         
         
+    // Function to give the booleans back as the codes do_booleans reads, e.g. "sha"
+    pub fn boolean_codes(&self) -> String {
+        let codes = [(self.seen, 's'), (self.heard, 'h'), (self.ringed, 'r'), (self.breeding, 'b'),
+                     (self.nonbreeding, 'n'), (self.nest, 't'), (self.eggs, 'g'), (self.chicks, 'c'),
+                     (self.immature, 'i'), (self.dead, 'e'), (self.male, 'm'), (self.female, 'f'),
+                     (self.adult, 'a'), (self.photo, 'p')];
+        codes.iter().filter(|(on, _)| *on).map(|(_, code)| *code).collect()
+    }
+        
+        
+        
     // Function to allocate bird, date and places
     pub fn do_places(mut self, arg: &str, birds: &BTreeMap<String, Species>) -> Result<Sightings, String> {
 
@@ -640,12 +998,16 @@ impl Sightings {
                             self.sname = r_sname.unwrap().clone().sname
                         }
                         'd' => {
-                            let date = convert_assumed_date(vec1[1]);
-                            if date.is_err(){
-                                let message = format!("Something wrong in the date string");
-                                return Err(message);   
+                            // The day, and maybe the time and its zone: 2022.03.29 06:45 +02:00 (or just 1987.05, 1974)
+                            let when = convert_when(vec1[1]);
+                            if when.is_err(){
+                                return Err(when.err().unwrap());   
                             }
-                            self.date = date.unwrap();
+                            let (date, precision, time, zone) = when.unwrap();
+                            self.date = date;
+                            self.precision = precision;
+                            self.time = time;
+                            self.zone = zone;
                         }
                         'a' => {
                             self.location = vec1[1].trim().to_string();
@@ -662,6 +1024,39 @@ impl Sightings {
                         'o' => {
                             self.comments = vec1[1].trim().to_string();
                         }
+                        'v' => {
+                            // The trinomial or just the third name
+                            let words = line_to_words(vec1[1]);
+                            self.subspecies = match words.last() {
+                                Some(word) => { word.trim().to_lowercase() }
+                                None       => { "".to_string() }
+                            };
+                        }
+                        'g' => {
+                            // Latitude and longitude in degrees, with the accuracy if known (g= clears them)
+                            if vec1[1].trim().len() == 0 {
+                                self.coordinates = None;
+                            } else {
+                                let coordinates = Coordinates::from_text(vec1[1]);
+                                if coordinates.is_err(){
+                                    return Err(coordinates.err().unwrap())
+                                }
+                                self.coordinates = Some(coordinates.unwrap());
+                            }
+                        }
+                        'n' => {
+                            // The number of birds, the ages and sexes counted are also ticked
+                            let counts = Counts::from_text(vec1[1]);
+                            if counts.is_err(){
+                                return Err(counts.err().unwrap())
+                            }
+                            self.count = counts.unwrap();
+                            if self.count.male > 0     { self.male = true; }
+                            if self.count.female > 0   { self.female = true; }
+                            if self.count.adult > 0    { self.adult = true; }
+                            if self.count.immature > 0 { self.immature = true; }
+                            if self.count.chicks > 0   { self.chicks = true; }
+                        }
 
                         _ => {
                             return Err("Wrong char given.".to_string())
@@ -716,6 +1111,24 @@ impl Sightings {
             return Err("Validation error: no date given".to_string())
         }
 
+        // Lets do the Precision, a time goes with the full date                                -- Precision
+        if self.time.is_some() {
+            if self.precision < Precision::Day {
+                return Err("Validation error: a time needs the full date, not just the year or month".to_string())
+            }
+            self.precision = Precision::Time;
+        } else if self.precision == Precision::Time {
+            self.precision = Precision::Day;
+        }
+
+        // Lets do the Subspecies, when the species has a list it must be on it                 -- Subspecies
+        let known = &r_sname.unwrap().subspecies;
+        if self.subspecies.len() > 0 && known.len() > 0 && !known.contains(&self.subspecies) {
+            let message = format!("Validation error: {} is not a known subspecies of {}   (known: {})",
+                                    self.subspecies, self.sname, known.join(", "));
+            return Err(message)
+        }
+
         // Lets do the Town                                                                     -- Town
         let mut i_town = title_case(self.town.trim());
         if i_town.len() == 0 {
@@ -831,6 +1244,165 @@ pub fn convert_date_text(text: &str) -> Result<i64, String> {
 }
 
 
+// Function to read the day with an optional time and time zone, like 2022.03.29 06:45 +02:00,
+// or just the year and month (1987.05) or year (1974) of an old record
+pub fn convert_when(text: &str) -> Result<(i64, Precision, Option<u16>, Option<i16>), String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.len() == 0 || words.len() > 3 {
+        return Err("Something wrong in the date string   (like 2022.03.29, or 2022.03.29 06:45 +02:00)".to_string())
+    }
+
+    if let Some((year_month, precision)) = convert_year_month(words[0]) {
+        if words.len() > 1 {
+            return Err("A time needs the full date, not just the year or month".to_string())
+        }
+        return Ok((year_month, precision, None, None))
+    }
+    let date = convert_assumed_date(words[0]);
+    if date.is_err(){
+        return Err("Something wrong in the date string".to_string())
+    }
+    let mut precision = Precision::Day;
+    let mut time = None;
+    let mut zone = None;
+    if words.len() > 1 {
+        let r_time = convert_time_text(words[1]);
+        if r_time.is_err(){
+            return Err(r_time.err().unwrap())
+        }
+        time = Some(r_time.unwrap());
+        precision = Precision::Time;
+    }
+    if words.len() > 2 {
+        let r_zone = convert_zone_text(words[2]);
+        if r_zone.is_err(){
+            return Err(r_zone.err().unwrap())
+        }
+        zone = Some(r_zone.unwrap());
+    }
+
+    Ok((date.unwrap(), precision, time, zone))
+}
+
+
+// Function to read a date of which only the year (1974) or the year and month (1987.05) is known
+pub fn convert_year_month(text: &str) -> Option<(i64, Precision)> {
+    let parts: Vec<&str> = text.trim().split(|c| c == '.' || c == '-').collect();
+    if parts[0].len() != 4 || parts.len() > 2 {
+        return None
+    }
+    let year = parts[0].parse::<i32>();
+    if year.is_err(){
+        return None
+    }
+    if parts.len() == 1 {
+        return Some((convert_date_to_timestamp(year.unwrap(), 1, 1), Precision::Year))
+    }
+    let month = parts[1].parse::<u32>();
+    if month.is_err() || month.clone().unwrap() < 1 || month.clone().unwrap() > 12 {
+        return None
+    }
+    Some((convert_date_to_timestamp(year.unwrap(), month.unwrap(), 1), Precision::Month))
+}
+
+
+// Function to convert a date text (2022.03.29), or a year and month (1987.05) or a year (1974), into unix time
+pub fn convert_period_text(text: &str) -> Result<(i64, Precision), String> {
+    if let Some(year_month) = convert_year_month(text) {
+        return Ok(year_month)
+    }
+    let date = convert_date_text(text);
+    if date.is_err(){
+        return Err(date.err().unwrap())
+    }
+    Ok((date.unwrap(), Precision::Day))
+}
+
+
+// Function to give the last day of the year or month a date stands for, or the date itself
+pub fn period_end(date: i64, precision: Precision) -> i64 {
+    let day = DateTime::<Utc>::from_timestamp(date, 0).unwrap_or_default().date_naive();
+    let last = match precision {
+        Precision::Year  => { NaiveDate::from_ymd_opt(day.year(), 12, 31) }
+        Precision::Month => {
+            let (year, month) = if day.month() == 12 { (day.year() + 1, 1) } else { (day.year(), day.month() + 1) };
+            NaiveDate::from_ymd_opt(year, month, 1).and_then(|first| first.pred_opt())
+        }
+        _                => { Some(day) }
+    };
+    match last {
+        Some(last) => { last.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() }
+        None       => { date }
+    }
+}
+
+
+// Function to convert a time like 06:45 into minutes after midnight
+pub fn convert_time_text(text: &str) -> Result<u16, String> {
+    let message = format!("Something wrong in the time -> {}   (like 06:45)", text);
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() != 2 || parts[1].len() != 2 {
+        return Err(message)
+    }
+    let hours = parts[0].parse::<u16>();
+    let minutes = parts[1].parse::<u16>();
+    if hours.is_err() || minutes.is_err(){
+        return Err(message)
+    }
+    let (hours, minutes) = (hours.unwrap(), minutes.unwrap());
+    if hours > 23 || minutes > 59 {
+        return Err(message)
+    }
+    Ok(hours * 60 + minutes)
+}
+
+
+// Function to convert a time zone like +02:00, UTC-5 or Z into minutes ahead of UTC
+pub fn convert_zone_text(text: &str) -> Result<i16, String> {
+    let message = format!("Something wrong in the time zone -> {}   (like +02:00, UTC-5 or UTC)", text);
+    let upper = text.trim().to_uppercase();
+    let offset = upper.trim_start_matches("UTC").trim_start_matches("GMT");
+    if offset.len() == 0 || offset == "Z" {
+        return Ok(0)
+    }
+
+    let sign: i16 = match offset.chars().nth(0).unwrap() {
+        '+' => { 1 }
+        '-' => { -1 }
+        _   => { return Err(message) }
+    };
+    let digits = offset[1..].replace(':', "");
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => { (digits.parse::<i16>(), Ok(0)) }
+        3 | 4 => { (digits[..digits.len() - 2].parse::<i16>(), digits[digits.len() - 2..].parse::<i16>()) }
+        _     => { return Err(message) }
+    };
+    if hours.is_err() || minutes.is_err(){
+        return Err(message)
+    }
+    let (hours, minutes) = (hours.unwrap(), minutes.unwrap());
+    // The zones of the world go from UTC-12:00 to UTC+14:00
+    let zone = sign * (hours * 60 + minutes);
+    if minutes > 59 || zone < -12 * 60 || zone > 14 * 60 {
+        return Err(message)
+    }
+    Ok(zone)
+}
+
+
+// Function to give minutes after midnight as a time like 06:45
+pub fn time_text(minutes: u16) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+
+// Function to give minutes ahead of UTC as a time zone like UTC+02:00
+pub fn zone_text(minutes: i16) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    format!("UTC{}{:02}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+
 
 // Function to show the sighting
 pub fn show_sightings_number<'a>(wn: WhatNumber, options: &'a mut SettingsText,
@@ -931,6 +1503,9 @@ pub fn add_sighting<'a>(last: Vec<String>, arg: &str, birds: &BTreeMap<String, S
     let mut to_be_added = Sightings::new();
     if scut_sight.clone().unwrap().is_some(){
         to_be_added = scut_sight.unwrap().unwrap();
+        
+        // The shortcut is a copy of another sighting, this one is new
+        to_be_added.id = new_sighting_id();
     }
 
 
@@ -1036,6 +1611,48 @@ pub fn add_sighting<'a>(last: Vec<String>, arg: &str, birds: &BTreeMap<String, S
 
 
 
+// Function to make a new id for a sighting
+// Ids made in the same run always go up, so sightings that are otherwise the same keep their order when sorted
+pub fn new_sighting_id() -> String {
+    let mut generator = ID_GENERATOR.lock().unwrap();
+    match generator.generate() {
+        Ok(id) => { id.to_string() }
+        Err(_) => { Ulid::new().to_string() }
+    }
+}
+
+
+// Function to see if the text looks like a sighting id
+pub fn is_sighting_id(text: &str) -> bool {
+    let trimmed = text.trim();
+    trimmed.len() == ulid::ULID_LEN && Ulid::from_string(&trimmed.to_uppercase()).is_ok()
+}
+
+
+// Function to give sightings without an id (or with an id already used) a new one,
+// returns how many were given one
+pub fn ensure_sighting_ids(sightings: &mut Vec<Sightings>) -> usize {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut count = 0;
+    
+    for sight in sightings.iter_mut() {
+        if sight.id.len() == 0 || seen.contains(&sight.id) {
+            sight.id = new_sighting_id();
+            count += 1;
+        }
+        seen.insert(sight.id.clone());
+    }
+    count
+}
+
+
+// Function to find the position (index) of the sighting with the given id
+pub fn find_index_of_id(id: &str, sightings: &Vec<Sightings>) -> Option<usize> {
+    let upper = id.trim().to_uppercase();
+    sightings.iter().position(|s| s.id == upper)
+}
+
+
 // Function to find position (index) of sighting in vector
 pub fn find_index_of_sighting(sight: Sightings, sightings: Vec<Sightings>) -> Result<usize, String> {
     
@@ -1089,10 +1706,11 @@ pub fn delete_sighting(wn: WhatNumber, options: &mut SettingsText ,
 
     sightings.remove(wn.number.unwrap().clone());
     let sight_number = wn.number.unwrap() + 1;
-    let message = format!("The bird sighting:  {}{}{} {} with the code:  {}{}{} {} in position  {}{}{} {} was deleted.\n",
+    let message = format!("The bird sighting:  {}{}{} {} with the code:  {}{}{} {} in position  {}{}{} {} (id {}) was deleted.\n",
                     color::Fg(color::Yellow), style::Italic ,&wn.clone().species.unwrap().name, style::Reset,
-                    color::Fg(color::Yellow), style::Italic ,&wn.species.unwrap().code, style::Reset,
-                    color::Fg(color::Yellow), style::Italic ,&sight_number, style::Reset);
+                    color::Fg(color::Yellow), style::Italic ,&wn.clone().species.unwrap().code, style::Reset,
+                    color::Fg(color::Yellow), style::Italic ,&sight_number, style::Reset,
+                    &wn.sighting.unwrap().id);
     feedback(Feedback::Info, message);
 
 }
@@ -1102,21 +1720,23 @@ pub fn get_sighting_from_line(line: &str, sbirds: &BTreeMap<String,Species> ) ->
     let mut new = Sightings::new();
     let temp = line.split("\t");
     let vec: Vec<&str> = temp.collect();
-    if vec.len() != 21 {
-        return Err("Line does not have 21 fields".to_string());
+    // The id, subspecies, count, time, zone and coordinates columns were added later, older files do not have them
+    if vec.len() < 21 || vec.len() > 29 {
+        return Err("Line does not have 21 (or 22 with the id, 23 with the subspecies, 24 with the count, 26 with the time and zone, 29 with the coordinates) fields".to_string());
     }
     
-    for i in 0..21 {
+    for i in 0..vec.len() {
         match i {
             0 => {
                 new.sname    = vec[i].to_string();
             }
             1 => {
-                let r_date = convert_date_text(vec[i]);
+                // A full date, or only the year and month (or year) of an old record
+                let r_date = convert_period_text(vec[i]);
                 if r_date.is_err(){
                     return Err("Line does not have 21 fields".to_string());
                 }
-                new.date     = r_date.unwrap();
+                (new.date, new.precision) = r_date.unwrap();
             }
             2 => {
                 new.location = vec[i].to_string();
@@ -1231,6 +1851,63 @@ pub fn get_sighting_from_line(line: &str, sbirds: &BTreeMap<String,Species> ) ->
             20 => {
                 new.comments = vec[i].to_string();
             }
+            21 => {
+                let id = vec[i].trim();
+                if id.len() > 0 {
+                    if !is_sighting_id(id) {
+                        return Err(format!("id -> {} is not a valid id", id));
+                    }
+                    new.id = id.to_uppercase();
+                }
+            }
+            22 => {
+                new.subspecies = vec[i].trim().to_lowercase();
+            }
+            23 => {
+                let counts = Counts::from_text(vec[i]);
+                if counts.is_err(){
+                    return Err(counts.err().unwrap());
+                }
+                new.count = counts.unwrap();
+            }
+            24 => {
+                if vec[i].trim().len() > 0 {
+                    let time = convert_time_text(vec[i]);
+                    if time.is_err(){
+                        return Err(time.err().unwrap());
+                    }
+                    // As in validate, a time goes with the full date
+                    if new.precision < Precision::Day {
+                        return Err(format!("time -> {} needs the full date, not just the year or month", vec[i].trim()));
+                    }
+                    new.time = Some(time.unwrap());
+                    new.precision = Precision::Time;
+                }
+            }
+            25 => {
+                if vec[i].trim().len() > 0 {
+                    let zone = convert_zone_text(vec[i]);
+                    if zone.is_err(){
+                        return Err(zone.err().unwrap());
+                    }
+                    new.zone = Some(zone.unwrap());
+                }
+            }
+            26 => {
+                // The latitude, with the longitude and accuracy in the next columns
+                if vec[i].trim().len() > 0 {
+                    let mut text = format!("{},{}", vec[i], vec.get(27).unwrap_or(&""));
+                    let accuracy = vec.get(28).unwrap_or(&"").trim();
+                    if accuracy.len() > 0 && accuracy != "0" {
+                        text.push_str(&format!(",{}m", accuracy));
+                    }
+                    let coordinates = Coordinates::from_text(&text);
+                    if coordinates.is_err(){
+                        return Err(coordinates.err().unwrap());
+                    }
+                    new.coordinates = Some(coordinates.unwrap());
+                }
+            }
             _ => {
                 
             }
@@ -1313,6 +1990,7 @@ mod tests {
     use std::fs::copy;
     // use substring::Substring;
     use std::fs::remove_file;
+    use std::time::{UNIX_EPOCH, Duration};
 
     
     #[ignore]
@@ -1860,6 +2538,177 @@ mod tests {
         assert_eq!(non,true);
     }
 
+    
+    #[ignore]
+    #[test]
+    fn t021_sighting_ids() {
+        let source = "./test/store/sightings/sightings.bin";
+        let destination = "./test/sights_ids.bin";
+        copy(source,destination).expect("Failed to copy");
+        let sightings = Sightings::load(destination).unwrap();
+        remove_file(destination).expect("Cleanup test failed");
+        
+        let source = "./test/store/species/species.bin";
+        let destination = "./test/birds_ids.bin";
+        copy(source,destination).expect("Failed to copy");
+        let birds = Species::load(destination).unwrap();
+        let sbirds = make_sname_btree(&birds);
+        remove_file(destination).expect("Cleanup test failed");
+        
+        let small: Vec<Sightings> = sightings.iter().take(20).cloned().collect();
+        let csv = "./test/sights_ids.csv";
+        Sightings::export_csv(csv, &small).unwrap();
+        let back = Sightings::import_csv(csv, &sbirds).unwrap();
+        remove_file(csv).expect("Cleanup test failed");
+        
+        let id = sightings.get(908).unwrap().id.clone();
+        let wn = what_number(&id, &sbirds, &sightings);
+        let unique: HashSet<&String> = sightings.iter().map(|s| &s.id).collect();
+        
+        assert_eq!(is_sighting_id(&id), true);
+        assert_eq!(unique.len(), sightings.len());
+        assert_eq!(back.get(5).unwrap().id, small.get(5).unwrap().id);
+        assert_eq!(wn.number, Some(908));
+        assert_eq!(find_index_of_id("nope", &sightings), None);
+    }
+
+    
+    #[ignore]
+    #[test]
+    fn t022_subspecies() {
+        let mut duck = Species::new();
+        duck.code = "duye".to_string();
+        duck.sname = "Anas undulata".to_string();
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        birds.insert(duck.code.clone(), duck.clone());
+        let free = make_sname_btree(&birds);
+        duck.subspecies = vec!["undulata".to_string(), "ruppelli".to_string()];
+        birds.insert(duck.code.clone(), duck);
+        let listed = make_sname_btree(&birds);
+        
+        let arg = "s#c=duye#d=2022.03.29#a=Delta Park#w=Joburg#p=Gauteng#t=South Africa#v=Anas undulata Ruppelli";
+        let known = Sightings::new().do_bool_places_val(arg, false, &birds, &listed);
+        let unknown = Sightings::new().do_bool_places_val(&arg.replace("Ruppelli", "sp"), false, &birds, &listed);
+        let anything = Sightings::new().do_bool_places_val(&arg.replace("Ruppelli", "sp"), false, &birds, &free);
+        
+        assert_eq!(known.clone().unwrap().subspecies, "ruppelli");
+        assert_eq!(known.unwrap().trinomial(), "Anas undulata ruppelli");
+        assert_eq!(unknown.is_err(), true);
+        assert_eq!(anything.unwrap().subspecies, "sp");
+    }
+
+
+
+    #[ignore]
+    #[test]
+    fn t023_counts() {
+        let mut gannet = Species::new();
+        gannet.code = "gaca".to_string();
+        gannet.sname = "Morus capensis".to_string();
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        birds.insert(gannet.code.clone(), gannet);
+        let sbirds = make_sname_btree(&birds);
+        
+        let arg = "s#c=gaca#d=2022.03.29#a=Harbour#w=Hermanus#p=Western Cape#t=South Africa#n=42 3m 1f";
+        let sighting = Sightings::new().do_bool_places_val(arg, false, &birds, &sbirds).unwrap();
+        let no_total = Counts::from_text("3m, 1f 2a").unwrap();
+        
+        assert_eq!(sighting.count.total, 42);
+        assert_eq!(sighting.male && sighting.female && !sighting.adult, true);
+        assert_eq!(sighting.count.text(), "42 3m 1f");
+        assert_eq!(sighting.count.describe(), "42 (3 male, 1 female)");
+        assert_eq!(no_total.total, 4);
+        assert_eq!(Counts::from_text("").unwrap(), Counts::default());
+        assert_eq!(Counts::from_text("2 3m").is_err(), true);
+        assert_eq!(Counts::from_text("3x").is_err(), true);
+        assert_eq!(Counts::from_text("many").is_err(), true);
+        assert_eq!(Counts::from_text("4000000000m 4000000000f").is_err(), true);
+    }
+
+
+
+    #[ignore]
+    #[test]
+    fn t024_time_and_zone() {
+        let (date, _, time, zone) = convert_when("2022.03.29 06:45 UTC+2").unwrap();
+        let mut early = Sightings::new();
+        early.sname = "Morus capensis".to_string();
+        early.date = date;
+        early.time = time;
+        early.zone = zone;
+        let mut late = early.clone();
+        late.sname = "Anas undulata".to_string();
+        late.time = Some(convert_time_text("17:05").unwrap());
+        let mut sightings = vec![late.clone(), early.clone()];
+        sightings.sort();
+        
+        assert_eq!(early.display_when(), "2022.03.29 06:45");
+        assert_eq!(early.display_zone(), "UTC+02:00");
+        assert_eq!(sightings[0].sname, "Morus capensis");
+        assert_eq!(convert_when("2022.03.29").unwrap(), (date, Precision::Day, None, None));
+        assert_eq!(convert_zone_text("-05:30").unwrap(), -330);
+        assert_eq!(convert_zone_text("Z").unwrap(), 0);
+        assert_eq!(convert_zone_text("+15").is_err(), true);
+        assert_eq!(convert_time_text("24:00").is_err(), true);
+        assert_eq!(convert_time_text("6:5").is_err(), true);
+    }
+
+
+
+    #[ignore]
+    #[test]
+    fn t025_partial_dates() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        let mut gannet = Species::new();
+        gannet.code = "gaca".to_string();
+        gannet.sname = "Morus capensis".to_string();
+        birds.insert(gannet.code.clone(), gannet);
+        let sbirds = make_sname_btree(&birds);
+        let arg = "s#c=gaca#d=1987.05#a=Harbour#w=Hermanus#p=Western Cape#t=South Africa";
+        
+        let may = Sightings::new().do_bool_places_val(arg, false, &birds, &sbirds).unwrap();
+        let old = Sightings::new().do_bool_places_val(&arg.replace("1987.05", "1962"), false, &birds, &sbirds).unwrap();
+        let with_time = Sightings::new().do_bool_places_val(&arg.replace("1987.05", "1987.05 06:45"), false, &birds, &sbirds);
+        let line = |date: &str| format!("Morus capensis\t{}\tHarbour\tHermanus\tWestern Cape\tSouth Africa\ttrue{}\t\t\t\t\t06:45",
+                                        date, "\tfalse".repeat(13));
+        let imported = get_sighting_from_line(&line("1987.05.29"), &sbirds).unwrap();
+        
+        assert_eq!(may.precision, Precision::Month);
+        assert_eq!(may.display_date(), "1987.05");
+        assert_eq!(may.period_end(), convert_date_text("1987.05.31").unwrap());
+        assert_eq!(old.display_date(), "1962");
+        assert_eq!(old.period_end(), convert_date_text("1962.12.31").unwrap());
+        assert_eq!(with_time.is_err(), true);
+        assert_eq!(imported.precision, Precision::Time);
+        assert_eq!(get_sighting_from_line(&line("1987.05"), &sbirds).is_err(), true);
+        assert_eq!(period_end(convert_date_text("1988.02.01").unwrap(), Precision::Month), convert_date_text("1988.02.29").unwrap());
+        assert_eq!(convert_period_text("2022.03.29").unwrap().1, Precision::Day);
+        assert_eq!(convert_year_month("1987.13"), None);
+    }
+
+
+
+    #[ignore]
+    #[test]
+    fn t026_coordinates() {
+        let delta_park = Coordinates::from_text("-26.1234, 28.0").unwrap();
+        let nearby = Coordinates::from_text("-26.13,28.01,15m").unwrap();
+        let johannesburg_to_cape_town = Coordinates::from_text("-26.2041,28.0473").unwrap()
+                                            .distance_to(&Coordinates::from_text("-33.9249,18.4241").unwrap());
+        
+        assert_eq!(delta_park.latitude, -26_123_400);
+        assert_eq!(delta_park.accuracy, 0);
+        assert_eq!(nearby.accuracy, 15);
+        assert_eq!(nearby.describe(), "-26.130000, 28.010000   (15 m)");
+        assert_eq!(delta_park.distance_to(&nearby) < 2000.0, true);
+        assert_eq!((johannesburg_to_cape_town / 1000.0).round(), 1262.0);
+        assert_eq!(Coordinates::from_text("-95,28").is_err(), true);
+        assert_eq!(Coordinates::from_text("-26.1").is_err(), true);
+        assert_eq!(convert_distance_text("5km", 1.0).unwrap(), 5000.0);
+        assert_eq!(convert_distance_text("5", 1000.0).unwrap(), 5000.0);
+        assert_eq!(convert_distance_text("250m", 1000.0).unwrap(), 250.0);
+    }
+
 
 
 
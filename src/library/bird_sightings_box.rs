@@ -81,7 +81,7 @@ pub fn show_sighting(options: &mut SettingsText, rec_num: usize, species: Specie
 
     
     // Name - Observations
-    let name = justify(species.clone().name, NAME_LEN, Justify::Left);         
+    let name = justify(species.display_name(), NAME_LEN, Justify::Left);         
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let name_text = justify("Name:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
     // let observation_text = justify("Observations:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
@@ -210,7 +210,7 @@ pub fn show_sighting(options: &mut SettingsText, rec_num: usize, species: Specie
     
     
     // Status
-    let status = justify(species.clone().status, NAME_LEN, Justify::Left);         
+    let status = justify(species.status.describe(), NAME_LEN, Justify::Left);         
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let status_text = justify("Status:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
     print!("{}{}{}",color::Fg(my_blue), status_text, style::Reset);
@@ -290,7 +290,10 @@ pub fn show_sighting(options: &mut SettingsText, rec_num: usize, species: Specie
     
     
     // Date   & Country
-    let tempo = options.clone().get_date_string(sighting.date);
+    let mut tempo = sighting.display_date();
+    if sighting.time.is_some() {
+        tempo = format!("{} {}   {}", tempo, sighting.display_time(), sighting.display_zone());
+    }
     let date = justify(tempo, NAME_LEN, Justify::Left);         
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let date_text = justify("Date:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);   
@@ -356,6 +359,54 @@ pub fn show_sighting(options: &mut SettingsText, rec_num: usize, species: Specie
 
     // Lines below
     print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
+    
+    
+    // Count
+    let count_len: usize = BLOCK_LEN - PAD1_LEN - R_SIDE_TEXT_LEN;
+    let count = justify(sighting.count.describe(), count_len, Justify::Left);
+    print!("{}{}{}", MARGIN, v, PADDING1);
+    let count_text = justify("Count:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);
+    print!("{}", color::Fg(my_blue));
+    underline('n', &count_text);
+    print!("{}", style::Reset);
+    print!("{}{}", color::Fg(my_normal), count);
+    print!("{}{}\n", color::Fg(my_blue), v);
+    
+    print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
+
+
+    // Coordinates
+    let position = match sighting.coordinates {
+        Some(coordinates) => { coordinates.describe() }
+        None              => { "".to_string() }
+    };
+    let position = justify(position, count_len, Justify::Left);
+    print!("{}{}{}", MARGIN, v, PADDING1);
+    let position_text = justify("Coordinates:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);
+    print!("{}", color::Fg(my_blue));
+    underline('g', &position_text);
+    print!("{}", style::Reset);
+    print!("{}{}", color::Fg(my_normal), position);
+    print!("{}{}\n", color::Fg(my_blue), v);
+    
+    print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
+
+
+    // Id
+    let id = justify(sighting.clone().id, NAME_LEN, Justify::Left);         
+    print!("{}{}{}", MARGIN, v, PADDING1); 
+    let id_text = justify("Id:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);   
+    print!("{}{}{}",color::Fg(my_blue), id_text, style::Reset);
+    print!("{}{}{}", color::Fg(my_dark_gray), id, style::Reset); 
+    let subspecies_text = justify("Subspecies:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);    
+    let subspecies = justify(sighting.clone().subspecies, NAME_LEN, Justify::Left);  
+    print!("{}", color::Fg(my_blue)); 
+    underline('v', &subspecies_text);
+    print!("{}", style::Reset); 
+    print!("{}{}{}{}", color::Fg(my_normal), style::Italic, subspecies, style::Reset); 
+    let id_pad_len: usize = BLOCK_LEN - PAD1_LEN - ( 2 * R_SIDE_TEXT_LEN ) - ( 2 * NAME_LEN);
+    print!("{}{}{}\n", color::Fg(my_blue), &repeat_char(SPACE.to_string(), id_pad_len), v); 
+    
     print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
 
 
@@ -0,0 +1,199 @@
+/*
+        Merging sightings from someone else into our own, instead of replacing them all.
+
+        A sighting from the other file is
+            a duplicate         when there is one with the same sname, date, location and booleans already
+            a near-duplicate    when there is one with the same id, or the same sname, date and location
+                                but other booleans. Either mine is kept (the default) or theirs.
+            new                 otherwise, it is added
+
+        2026.09.30   Sven Ponelat
+*/
+
+use crate::library::bird_sightings::*;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::HashMap;
+
+
+// Which one stays when there is a near-duplicate
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Keep {
+    Mine,
+    Theirs,
+}
+
+
+impl Keep {
+    // Function to read mine or theirs from the command line
+    pub fn from_arg(arg: Option<String>) -> Result<Keep, String> {
+        match arg {
+            None => { Ok(Keep::Mine) }
+            Some(text) => {
+                match text.to_lowercase().trim() {
+                    "mine"   => { Ok(Keep::Mine) }
+                    "theirs" => { Ok(Keep::Theirs) }
+                    _        => { Err(format!("Not a valid choice for near-duplicates -> {}   (either mine or theirs)", text)) }
+                }
+            }
+        }
+    }
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct MergeReport {
+    pub added: usize,
+    pub duplicates: usize,
+    pub replaced: usize,
+    // (mine, theirs)
+    pub conflicts: Vec<(Sightings, Sightings)>,
+}
+
+
+// What makes two sightings the same record, apart from the booleans
+fn record_key(sighting: &Sightings) -> (String, i64, String) {
+    (sighting.sname.clone(), sighting.date, sighting.location.to_lowercase().trim().to_string())
+}
+
+
+// Function to merge their sightings into mine
+pub fn merge_sightings(mine: &mut Vec<Sightings>, theirs: Vec<Sightings>, keep: Keep) -> MergeReport {
+    let mut report = MergeReport::default();
+
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+    let mut by_key: HashMap<(String, i64, String), Vec<usize>> = HashMap::new();
+    for (index, sighting) in mine.iter().enumerate() {
+        by_id.insert(sighting.id.clone(), index);
+        by_key.entry(record_key(sighting)).or_default().push(index);
+    }
+
+    for mut their in theirs {
+        let key = record_key(&their);
+        let same_place: Vec<usize> = match by_key.get(&key) {
+            Some(indices) => { indices.iter().cloned().filter(|i| record_key(&mine[*i]) == key).collect() }
+            None          => { Vec::new() }
+        };
+
+        // Already there
+        if same_place.iter().any(|i| mine[*i].boolean_codes() == their.boolean_codes()) {
+            report.duplicates += 1;
+            continue;
+        }
+
+        // The same sighting changed in two places, or the same bird seen differently
+        let near = match by_id.get(&their.id) {
+            Some(index) => { Some(*index) }
+            None        => { same_place.first().cloned() }
+        };
+        if let Some(index) = near {
+            report.conflicts.push((mine[index].clone(), their.clone()));
+            if keep == Keep::Theirs {
+                their.id = mine[index].id.clone();
+                by_key.entry(key).or_default().push(index);
+                mine[index] = their;
+                report.replaced += 1;
+            }
+            continue;
+        }
+
+        // New, but it needs an id of its own
+        if !is_sighting_id(&their.id) || by_id.contains_key(&their.id) {
+            their.id = new_sighting_id();
+        }
+        by_id.insert(their.id.clone(), mine.len());
+        by_key.entry(key).or_default().push(mine.len());
+        mine.push(their);
+        report.added += 1;
+    }
+
+    mine.sort();
+    report
+}
+
+
+// Function to tell the user what the merge did, and show the near-duplicates
+pub fn display_merge_report(options: &mut SettingsText, report: &MergeReport, keep: Keep) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    if report.conflicts.len() > 0 {
+        let kept = if keep == Keep::Mine { "mine" } else { "theirs" };
+        println!("{}Near-duplicates (kept {}){}", color::Fg(my_light_blue), kept, style::Reset);
+        for (mine, theirs) in &report.conflicts {
+            let line = format!("{}  {:<30}  {:<25}  mine: {:<8} theirs: {:<8} {}", mine.display_date(), mine.sname,
+                                limit_length(mine.location.clone(), 25), mine.boolean_codes(), theirs.boolean_codes(),
+                                if mine.comments != theirs.comments { "(comments differ)" } else { "" });
+            println!("{}{}{}", color::Fg(my_normal_gray), line, style::Reset);
+        }
+        println!();
+    }
+
+    let message = format!("{} new sightings added, {} were already there, {} near-duplicates ({} replaced with theirs).",
+                            report.added.separate_with_spaces(), report.duplicates.separate_with_spaces(),
+                            report.conflicts.len().separate_with_spaces(), report.replaced.separate_with_spaces());
+    feedback(Feedback::Info, message);
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn sighting(sname: &str, date: i64, location: &str, seen: bool) -> Sightings {
+        let mut sighting = Sightings::new();
+        sighting.sname = sname.to_string();
+        sighting.date = date;
+        sighting.location = location.to_string();
+        sighting.seen = seen;
+        sighting.heard = !seen;
+        sighting
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_merge_sightings() {
+        let mut mine = vec![sighting("Anas undulata", 1, "Delta Park", true), sighting("Euplectes orix", 2, "Home", true)];
+        let theirs = vec![
+            sighting("Anas undulata", 1, "delta park ", true),        // duplicate
+            sighting("Euplectes orix", 2, "Home", false),             // near-duplicate
+            sighting("Bostrychia hagedash", 3, "Home", true),         // new
+        ];
+
+        let mut mine2 = mine.clone();
+        let report = merge_sightings(&mut mine, theirs.clone(), Keep::Mine);
+        let report2 = merge_sightings(&mut mine2, theirs, Keep::Theirs);
+        let orix = mine2.iter().find(|s| s.sname == "Euplectes orix").unwrap();
+
+        assert_eq!(report.added, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.replaced, 0);
+        assert_eq!(mine.len(), 3);
+        assert_eq!(report2.replaced, 1);
+        assert_eq!(orix.heard, true);
+        assert_eq!(orix.id, report2.conflicts[0].0.id);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_keep_from_arg() {
+        assert_eq!(Keep::from_arg(None).unwrap(), Keep::Mine);
+        assert_eq!(Keep::from_arg(Some("Theirs".to_string())).unwrap(), Keep::Theirs);
+        assert_eq!(Keep::from_arg(Some("ours".to_string())).is_err(), true);
+    }
+}
@@ -13,8 +13,8 @@ use super::settings::SettingsText;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 use std::fmt::{Debug};
-use std::cmp::Ordering;
 use crate::library::bird_sightings::*;
+use crate::library::bird_locations::*;
 
 
 
@@ -46,7 +46,9 @@ pub struct SearchFields{
     ch: char,
     value: String,
     d1: i64,
-    d2: i64
+    d2: i64,
+    // The centre and the radius in metres of a g= search
+    near: Option<(Coordinates, f64)>,
 }
 
 impl SearchFields{
@@ -56,57 +58,34 @@ impl SearchFields{
             value: value,
             d1: 0,
             d2: 0,
+            near: None,
         }
     }
 }
 
 
-#[derive(Clone, Debug, PartialOrd, Eq )]
+// Where a sighting was, the id in the location registry keeps "Around" in two towns apart
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub struct Location {
+    location_id: u32,
     location: String,
     town: String,
     province: String,
-    // date: String
 }
 
 impl Location{
     pub fn new(sighting: &Sightings) -> Location {
-        // let display = sighting.display_date();
-        let s = sighting.clone();
-        Location { location: s.location, 
-                    town: s.town, 
-                    province: s.province, 
-                    // date: display 
+        Location { location_id: sighting.location_id,
+                    location: sighting.location.clone(), 
+                    town: sighting.town.clone(), 
+                    province: sighting.province.clone(), 
         }
     }
 }
 
-impl PartialEq for Location {
-    fn eq(&self, other: &Self) -> bool {
-        if self.location == other.location &&
-            self.town == other.town &&
-            self.province == other.province {
-            return true
-        }
-        else {
-            return false
-        }
-    }
-}
-
-impl Ord for Location {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.location.cmp(&other.location)
-        // if self.date == other.date {
-        //     return self.location.cmp(&other.location)
-        // }
-        // return self.date.cmp(&other.date)
-    }
-}
-
 
 
-// Error check the number from arguments
+// Error check the number (or the id) from arguments
 pub fn what_number(sub: &str, sbirds: &BTreeMap<String,Species> , sightings: &Vec<Sightings>) -> WhatNumber {
     let mut answer = WhatNumber::new();
     let lower = sub.to_lowercase();
@@ -152,12 +131,25 @@ pub fn what_number(sub: &str, sbirds: &BTreeMap<String,Species> , sightings: &Ve
         }
     }
     
-    // It is not a number 
+    // It is not a number, but it could be the id of a sighting
     if res2.is_err(){
         answer.is_number = false;
         answer.sighting = None;
         answer.number = None;
         answer.species = None;
+        
+        if is_sighting_id(trim) {
+            let r_index = find_index_of_id(trim, sightings);
+            if r_index.is_some(){
+                let index = r_index.unwrap();
+                let sight = sightings[index].clone();
+                let species = sbirds.get(&sight.sname).unwrap().clone();
+                answer.number = Some(index);
+                answer.sighting = Some(sight);
+                answer.is_number = true;
+                answer.species = Some(species);
+            }
+        }
     }
 
     return answer;
@@ -166,8 +158,9 @@ pub fn what_number(sub: &str, sbirds: &BTreeMap<String,Species> , sightings: &Ve
 
 
 // Functions to get results of search terms from sightings database
+// The locations registry is asked for the other names of a site and the coordinates of the place
 pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<String,Species>, 
-                    sightings: &'a Vec<Sightings> ) -> Result<(Vec<usize>,Vec<Sightings>), String> { 
+                    sightings: &'a Vec<Sightings>, places: &BTreeMap<u32, Place> ) -> Result<(Vec<usize>,Vec<Sightings>), String> { 
     
     let capacity: usize = 500;
 
@@ -196,7 +189,8 @@ pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<Strin
         for st in s_array{
             match st.ch {
                 'a' => {
-                    if sight.location.to_lowercase().contains(&st.value){
+                    let place = places.get(&sight.location_id);
+                    if sight.location.to_lowercase().contains(&st.value) || place.is_some_and(|p| p.site_has(&st.value)) {
                         and_counter += 1; 
                     }
                     else {
@@ -212,21 +206,27 @@ pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<Strin
                     }
                 }
                 'd' => {
-                    if st.d2 == 0 {
-                        if sight.date == st.d1 {
-                            and_counter += 1; 
-                        }
-                        else {
-                            break;
-                        }
+                    // The whole year or month of a sighting with only that known has to be in the range
+                    if sight.date >= st.d1 && sight.period_end() <= st.d2 {
+                        and_counter += 1; 
                     }
                     else {
-                        if sight.date >= st.d1 && sight.date <= st.d2 {
-                            and_counter += 1; 
-                        }
-                        else {
-                            break;
-                        }
+                        break;
+                    }
+                }
+                'g' => {
+                    // Without its own coordinates a sighting is where its location is, without both it is never near
+                    let (centre, radius) = st.near.unwrap();
+                    let coordinates = sight.coordinates.or_else(|| places.get(&sight.location_id).and_then(|p| p.coordinates));
+                    let near = match coordinates {
+                        Some(coordinates) => { coordinates.distance_to(&centre) <= radius }
+                        None              => { false }
+                    };
+                    if near {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
                     }
                 }
                 'e' => {
@@ -237,8 +237,24 @@ pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<Strin
                         break;
                     }
                 }
+                'i' => {
+                    if species.notes.to_lowercase().contains(&st.value) {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
+                    }
+                }
+                'k' => {
+                    if Category::validate_category(&st.value).ok() == Some(species.category.clone()) {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
+                    }
+                }
                 'l' => {
-                    if species.list.to_lowercase().contains(&st.value) {
+                    if species.lists.iter().any(|l| l.to_lowercase().contains(&st.value)) {
                         and_counter += 1; 
                     }
                     else {
@@ -254,7 +270,7 @@ pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<Strin
                     }
                 }
                 'n' => {
-                    if species.name.to_lowercase().contains(&st.value) {
+                    if species.any_name_contains(&st.value) {
                         and_counter += 1; 
                     }
                     else {
@@ -293,6 +309,30 @@ pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<Strin
                         break;
                     }
                 }
+                't' => {
+                    if sight.country.to_lowercase().contains(&st.value) {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
+                    }
+                }
+                'u' => {
+                    if species.status.matches(&st.value) {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
+                    }
+                }
+                'v' => {
+                    if sight.subspecies.contains(&st.value) {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
+                    }
+                }
                 'w' => {
                     if sight.town.to_lowercase().contains(&st.value) {
                         and_counter += 1; 
@@ -301,6 +341,14 @@ pub fn get_searched_slice_of_sightings<'a>(arg: &str, sbirds: &'a BTreeMap<Strin
                         break;
                     }
                 }
+                'x' => {
+                    if species.confusable.contains(&st.value) {
+                        and_counter += 1; 
+                    }
+                    else {
+                        break;
+                    }
+                }
                 'A' => {
                     if sight.adult {
                         and_counter += 1; 
@@ -480,29 +528,20 @@ pub fn make_search_vector(terms_str: &mut String) -> Result<Vec<SearchFields>, S
                         continue;
                     }
                     "d" => { //date
+                        // From the start of the first date (or year or month) to the end of the last
                         let range = indi[1].split("-");
                         let dd: Vec<&str> = range.collect(); 
-                        if dd.len() == 2 {
-                            let d1 = convert_date_text(dd[0]);
+                        if dd.len() == 1 || dd.len() == 2 {
+                            let d1 = convert_period_text(dd[0]);
                             if d1.is_err(){
                                 return Err("Problem parsing date text".to_string())
                             }
-                            let d2  = convert_date_text(dd[1]);
+                            let d2  = convert_period_text(dd[dd.len() - 1]);
                             if d2.is_err(){
                                 return Err("Problem parsing date text".to_string())
                             }
-                            // let arr = SearchFields::date {ch: 'd', start: d1.unwrap(), end: d2.unwrap()};
-                            let arr = SearchFields {ch: 'd', value: "".to_string(), d1: d1.unwrap(), d2: d2.unwrap() };
-                            ret.push(arr);
-                            continue;
-                        }
-                        else if dd.len() == 1 {
-                            let d1 = convert_date_text(dd[0]);
-                            if d1.is_err(){
-                                return Err("Problem parsing date text".to_string())
-                            }
-                            // let arr = SearchFields::date {ch: 'd', start: d1.unwrap(), end: 0};
-                            let arr = SearchFields {ch: 'd', value: "".to_string(), d1: d1.unwrap(), d2: 0 };
+                            let (end, precision) = d2.unwrap();
+                            let arr = SearchFields {ch: 'd', value: "".to_string(), d1: d1.unwrap().0, d2: period_end(end, precision), near: None };
                             ret.push(arr);
                             continue;
                         }
@@ -510,11 +549,39 @@ pub fn make_search_vector(terms_str: &mut String) -> Result<Vec<SearchFields>, S
                             return Err("Wrong number of search terms in date string".to_string())
                         }
                     }
+                    "g" => { //within a distance of a position, like -26.1,28.0,5km
+                        let parts: Vec<&str> = indi[1].split(',').collect();
+                        if parts.len() != 3 {
+                            return Err("A search near a position needs the latitude, longitude and distance  (like g=-26.1,28.0,5km)".to_string())
+                        }
+                        let centre = Coordinates::from_text(&parts[..2].join(","));
+                        if centre.is_err(){
+                            return Err(centre.err().unwrap())
+                        }
+                        let radius = convert_distance_text(parts[2], 1000.0);
+                        if radius.is_err(){
+                            return Err(radius.err().unwrap())
+                        }
+                        let mut arr = SearchFields::new('g', "".to_string());
+                        arr.near = Some((centre.unwrap(), radius.unwrap()));
+                        ret.push(arr);
+                        continue;
+                    }
                     "e" => { //aname
                         let arr = SearchFields::new('e',indi[1].to_string());
                         ret.push(arr);
                         continue;
                     }
+                    "i" => { //species notes
+                        let arr = SearchFields::new('i',indi[1].to_string());
+                        ret.push(arr);
+                        continue;
+                    }
+                    "k" => { //category
+                        let arr = SearchFields::new('k',indi[1].to_string());
+                        ret.push(arr);
+                        continue;
+                    }
                     "l" => { //list
                         let arr = SearchFields::new('l',indi[1].to_string());
                         ret.push(arr);
@@ -560,11 +627,21 @@ pub fn make_search_vector(terms_str: &mut String) -> Result<Vec<SearchFields>, S
                         ret.push(arr);
                         continue;
                     }
+                    "v" => { //subspecies
+                        let arr = SearchFields::new('v',indi[1].trim().to_string());
+                        ret.push(arr);
+                        continue;
+                    }
                     "w" => { //town
                         let arr = SearchFields::new('w',indi[1].to_string());
                         ret.push(arr);
                         continue;
                     }
+                    "x" => { //confusable with this code
+                        let arr = SearchFields::new('x',indi[1].trim().to_string());
+                        ret.push(arr);
+                        continue;
+                    }
 
 
                     _ => {
@@ -596,8 +673,12 @@ pub fn display_search_results( options: &SettingsText, sbirds: &BTreeMap<String,
         let pos = positions[i];      
 
         let pos_text = justify(pos.to_string(), P_LEN, Justify::Right); 
-        let name_text = justify(species.name, NAME_39, Justify::Left); 
-        let date_text = justify(results[i].display_date(), DATE_LEN, Justify::Center); 
+        let name = match results[i].subspecies.len() {
+            0 => { species.display_name() }
+            _ => { limit_length(format!("{} ({})", species.display_name(), results[i].subspecies), NAME_39) }
+        };
+        let name_text = justify(name, NAME_39, Justify::Left); 
+        let date_text = justify(results[i].display_when(), DATE_LEN, Justify::Center); 
         let location_text = justify(results[i].location.to_string(), NAME_39, Justify::Left); 
         let comment_text = justify(results[i].comments.to_string(), FAMILY_59, Justify::Left);  
 
@@ -751,6 +832,14 @@ pub fn get_shortcut(last: Vec<String>, arg: &str, sightings: &Vec<Sightings>) ->
                     to_be_added.province = tsight.province;
                     to_be_added.country = tsight.country;
                     to_be_added.date = tsight.date;
+                    // A year or a month stays one, the time is not copied so it is a day at most
+                    to_be_added.precision = match tsight.precision {
+                        Precision::Time => { Precision::Day }
+                        precision       => { precision }
+                    };
+                    // The same place is in the same time zone, the time of day is not the same
+                    to_be_added.zone = tsight.zone;
+                    to_be_added.coordinates = tsight.coordinates;
 
                     only_one_shortcut = false;
                     has_values = true;
@@ -831,7 +920,7 @@ mod tests {
         remove_file(destination).expect("Cleanup test failed");
 
         let arg = "a=eagle".to_string();
-        let res = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        let res = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
 
         assert_eq!(res.unwrap().1.len(),392);
     }
@@ -925,37 +1014,37 @@ mod tests {
         remove_file(destination).expect("Cleanup test failed");
         
         let mut arg = "d=2021.06.26#o=lifer".to_string();
-        let mut slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        let mut slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
         if slice.is_ok(){
             assert_eq!(slice.unwrap().1.len(),1);
         }
         
         arg = "H#d=2010.06.01".to_string();
-        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
         if slice.is_ok(){
             assert_eq!(slice.unwrap().1.len(),3);
         }
         
         arg = "MFA#d=2014.02.16".to_string();
-        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
         if slice.is_ok(){
             assert_eq!(slice.unwrap().1.len(),2);
         }
         
         arg = "E".to_string();
-        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
         if slice.is_ok(){
             assert_eq!(slice.unwrap().1.len(),71);
         }
         
         arg = "a=delta park#d=2001.09.01".to_string();
-        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
         if slice.is_ok(){
             assert_eq!(slice.unwrap().1.len(),7);
         }
         
         arg = "w=kgomo".to_string();
-        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings);
+        slice = get_searched_slice_of_sightings(&arg, &sbirds, &sightings, &BTreeMap::new());
         if slice.is_ok(){
             assert_eq!(slice.unwrap().1.len(),94);
         }
@@ -996,6 +1085,37 @@ mod tests {
 
 
 
+    #[ignore]
+    #[test]
+    fn t008_search_the_registry() {
+        let mut duck = Species::new();
+        duck.code = "duye".to_string();
+        duck.sname = "Anas undulata".to_string();
+        let mut sbirds: BTreeMap<String, Species> = BTreeMap::new();
+        sbirds.insert(duck.sname.clone(), duck);
+        let mut sighting = Sightings::new();
+        sighting.sname = "Anas undulata".to_string();
+        sighting.location = "Delta".to_string();
+        sighting.town = "Johannesburg".to_string();
+        sighting.country = "South Africa".to_string();
+        let mut sightings = vec![sighting];
+        let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+        register_sightings(&mut places, &mut sightings);
+        edit_place(sightings[0].location_id, "a=Bird Sanctuary#k=Delta Park#g=-26.13,28.01", &mut places, &mut sightings).unwrap();
+
+        let by_alias = get_searched_slice_of_sightings("a=delta park#c=duye", &sbirds, &sightings, &places).unwrap().1.len();
+        let by_old_name = get_searched_slice_of_sightings("a=delta", &sbirds, &sightings, &places).unwrap().1.len();
+        let near = get_searched_slice_of_sightings("g=-26.13,28.0,5km", &sbirds, &sightings, &places).unwrap().1.len();
+        let without = get_searched_slice_of_sightings("g=-26.13,28.0,5km", &sbirds, &sightings, &BTreeMap::new()).unwrap().1.len();
+
+        assert_eq!(by_alias, 1);
+        assert_eq!(by_old_name, 1);
+        assert_eq!(near, 1);
+        assert_eq!(without, 0);
+    }
+
+
+
 
 
 
@@ -11,6 +11,9 @@ use crate::library::bird_species_support::*;
 use crate::library::bird_species_support::CodeOrNumber;
 use crate::library::bird_species_box::*;
 use crate::library::bird_sightings::*;
+use crate::library::data_dir::*;
+use crate::library::backups::*;
+use crate::library::bin_format::*;
 use std::path::Path;
 use std::fs::{ OpenOptions };
 use serde::{Serialize, Deserialize};
@@ -28,12 +31,16 @@ use std::io::{BufReader, BufWriter, Write};
 
 
 
-pub const SPECIES_BIN_FILENAME: &str = "./species.bin";
+pub const SPECIES_BIN_FILENAME: &str = "species.bin";
 pub const CODE_LEN:  usize   = 10;
 pub const POS_LEN:   usize   =  9;
+// The language of the name, the other languages are in names
+pub const ENGLISH:   &str    = "en";
+// How long the notes on a species may be
+pub const NOTES_LEN: usize   = 2000;
 
 #[allow(non_snake_case)]
-#[derive(Clone, Debug,  Serialize, Deserialize, derivative::Derivative)]
+#[derive(Clone, Debug, PartialEq,  Serialize, Deserialize, derivative::Derivative)]
 #[derivative(Default)]
 pub struct Species {
     pub sname: String,
@@ -42,11 +49,298 @@ pub struct Species {
     pub code: String,
     pub order: String,
     pub family: String,
-    pub status: String,
+    pub status: Status,
     pub aname: String,
     pub afname: String,
     pub acode: String,
-    pub list: String,
+    // The named lists the species is on (Southern Africa, Garden, UK BOU, ...)
+    pub lists: Vec<String>,
+    // Where the species comes in a field guide (from a checklist), 0 if it is not known
+    #[serde(default)]
+    pub seq: u32,
+    // The subspecies (just the third name) a sighting may be given, any is fine when empty
+    #[serde(default)]
+    pub subspecies: Vec<String>,
+    // The common names in other languages, by language code (af, de, nl, ...)
+    #[serde(default)]
+    pub names: BTreeMap<String, String>,
+    // Our own notes on identification, voice and habitat, line breaks are kept
+    #[serde(default)]
+    pub notes: String,
+    // The codes of the species it is easily mistaken for
+    #[serde(default)]
+    pub confusable: Vec<String>,
+    #[serde(default)]
+    pub category: Category,
+}
+
+
+// The species as a map from code, in the order they are walked in (for the json export)
+struct OrderedSpecies<'a>(Vec<&'a Species>);
+
+impl<'a> Serialize for OrderedSpecies<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|s| (&s.code, *s)))
+    }
+}
+
+
+// The conservation status, either an IUCN Red List category or a regional one in free text.
+// It is kept as its text (LC, EN, Rare, ...), so the files did not have to change for it.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Status {
+    #[default]
+    NotSet,
+    LeastConcern,
+    NearThreatened,
+    Vulnerable,
+    Endangered,
+    CriticallyEndangered,
+    ExtinctInTheWild,
+    Extinct,
+    DataDeficient,
+    NotEvaluated,
+    Regional(String),
+}
+
+
+// The IUCN categories, in the order of the Red List
+const IUCN_CATEGORIES: [Status; 9] = [Status::LeastConcern, Status::NearThreatened, Status::Vulnerable, Status::Endangered,
+                                      Status::CriticallyEndangered, Status::ExtinctInTheWild, Status::Extinct,
+                                      Status::DataDeficient, Status::NotEvaluated];
+
+
+impl Status {
+
+    // Function to read a status as it is typed, an IUCN code or name (any case) or else a regional one
+    pub fn validate_status(input: &str) -> Result<Status, String> {
+        let text = line_to_words(input).join(" ");
+        if text.len() == 0 {
+            return Ok(Status::NotSet)
+        }
+        for category in IUCN_CATEGORIES {
+            if text.to_uppercase() == category.code() || text.to_lowercase() == category.name().to_lowercase() {
+                return Ok(category)
+            }
+        }
+        // Two letters is surely a mistyped code, not a regional status
+        if text.chars().count() == 2 {
+            let codes: Vec<&str> = IUCN_CATEGORIES.iter().map(|c| c.code()).collect();
+            return Err(format!("Not an IUCN category -> {}   (either {} or a regional status)", text, codes.join(", ")))
+        }
+        Ok(Status::Regional(limit_length(text, NAME_39)))
+    }
+
+
+    pub fn code(&self) -> &str {
+        match self {
+            Status::NotSet               => { "" }
+            Status::LeastConcern         => { "LC" }
+            Status::NearThreatened       => { "NT" }
+            Status::Vulnerable           => { "VU" }
+            Status::Endangered           => { "EN" }
+            Status::CriticallyEndangered => { "CR" }
+            Status::ExtinctInTheWild     => { "EW" }
+            Status::Extinct              => { "EX" }
+            Status::DataDeficient        => { "DD" }
+            Status::NotEvaluated         => { "NE" }
+            Status::Regional(text)       => { text }
+        }
+    }
+
+
+    pub fn name(&self) -> &str {
+        match self {
+            Status::LeastConcern         => { "Least Concern" }
+            Status::NearThreatened       => { "Near Threatened" }
+            Status::Vulnerable           => { "Vulnerable" }
+            Status::Endangered           => { "Endangered" }
+            Status::CriticallyEndangered => { "Critically Endangered" }
+            Status::ExtinctInTheWild     => { "Extinct in the Wild" }
+            Status::Extinct              => { "Extinct" }
+            Status::DataDeficient        => { "Data Deficient" }
+            Status::NotEvaluated         => { "Not Evaluated" }
+            _                            => { self.code() }
+        }
+    }
+
+
+    pub fn is_threatened(&self) -> bool {
+        matches!(self, Status::Vulnerable | Status::Endangered | Status::CriticallyEndangered)
+    }
+
+
+    // Function to show the status, as in "Vulnerable (VU)"
+    pub fn describe(&self) -> String {
+        match self {
+            Status::NotSet | Status::Regional(_) => { self.code().to_string() }
+            _                                    => { format!("{} ({})", self.name(), self.code()) }
+        }
+    }
+
+
+    // The colour (from the options) the status is shown in
+    pub fn color_key(&self) -> &str {
+        match self {
+            Status::LeastConcern                                       => { "myGreen" }
+            Status::NearThreatened | Status::Vulnerable                => { "myOlive" }
+            Status::Endangered | Status::CriticallyEndangered          => { "myRed" }
+            Status::ExtinctInTheWild | Status::Extinct                 => { "myPurple" }
+            _                                                          => { "myNormalGray" }
+        }
+    }
+
+
+    // Function to see if the status is what was searched for: a code, "threatened", or part of a regional one
+    pub fn matches(&self, term: &str) -> bool {
+        let term = term.trim().to_lowercase();
+        if term == "threatened" {
+            return self.is_threatened()
+        }
+        match self {
+            Status::NotSet         => { false }
+            Status::Regional(text) => { text.to_lowercase().contains(&term) }
+            _                      => { term == self.code().to_lowercase() || term == self.name().to_lowercase() }
+        }
+    }
+}
+
+
+// What was in the files is kept, even when it would not pass validation now
+impl From<String> for Status {
+    fn from(text: String) -> Status {
+        match Status::validate_status(&text) {
+            Ok(status) => { status }
+            Err(_)     => { Status::Regional(text.trim().to_string()) }
+        }
+    }
+}
+
+
+impl From<Status> for String {
+    fn from(status: Status) -> String {
+        status.code().to_string()
+    }
+}
+
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+
+// What sort of taxon (or bird) the record is, the counting rules in the options say which ones count.
+// It is kept as its text (species, hybrid, escapee, ...), like the status.
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum Category {
+    #[default]
+    Species,
+    SubspeciesGroup,
+    Hybrid,
+    Spuh,
+    Slash,
+    Domestic,
+    Escapee,
+    Introduced,
+}
+
+
+pub const CATEGORIES: [Category; 8] = [Category::Species, Category::SubspeciesGroup, Category::Hybrid, Category::Spuh,
+                                       Category::Slash, Category::Domestic, Category::Escapee, Category::Introduced];
+
+
+impl Category {
+
+    // Function to read a category as it is typed, the eBird names (issf, sp.) are taken as well
+    pub fn validate_category(input: &str) -> Result<Category, String> {
+        let text = line_to_words(input).join(" ").to_lowercase();
+        match text.as_str() {
+            ""                                                     => { return Ok(Category::Species) }
+            "subspecies group" | "issf" | "form"                   => { return Ok(Category::SubspeciesGroup) }
+            "sp" | "sp."                                           => { return Ok(Category::Spuh) }
+            "escaped" | "escape"                                   => { return Ok(Category::Escapee) }
+            "introduced-established" | "introduced established"
+                | "established"                                    => { return Ok(Category::Introduced) }
+            _                                                      => {}
+        }
+        for category in CATEGORIES {
+            if text == category.code() {
+                return Ok(category)
+            }
+        }
+        let codes: Vec<&str> = CATEGORIES.iter().map(|c| c.code()).collect();
+        Err(format!("Not a valid category -> {}   (one of {})", input.trim(), codes.join(", ")))
+    }
+
+
+    pub fn code(&self) -> &str {
+        match self {
+            Category::Species         => { "species" }
+            Category::SubspeciesGroup => { "group" }
+            Category::Hybrid          => { "hybrid" }
+            Category::Spuh            => { "spuh" }
+            Category::Slash           => { "slash" }
+            Category::Domestic        => { "domestic" }
+            Category::Escapee         => { "escapee" }
+            Category::Introduced      => { "introduced" }
+        }
+    }
+
+
+    // Function to show the category, as in "Subspecies group"
+    pub fn describe(&self) -> String {
+        match self {
+            Category::SubspeciesGroup => { "Subspecies group".to_string() }
+            Category::Introduced      => { "Introduced (established)".to_string() }
+            _                         => {
+                let code = self.code();
+                format!("{}{}", code[..1].to_uppercase(), &code[1..])
+            }
+        }
+    }
+
+
+    // Function to read the categories that count, like "species, introduced"
+    pub fn validate_categories(input: &str) -> Result<Vec<Category>, String> {
+        let mut ret: Vec<Category> = Vec::new();
+        for part in input.split(',').filter(|p| p.trim().len() > 0) {
+            let category = Category::validate_category(part);
+            if category.is_err(){
+                return Err(category.err().unwrap());
+            }
+            let category = category.unwrap();
+            if !ret.contains(&category) {
+                ret.push(category);
+            }
+        }
+        Ok(ret)
+    }
+}
+
+
+// What was in the files is kept as a species when it is not known
+impl From<String> for Category {
+    fn from(text: String) -> Category {
+        Category::validate_category(&text).unwrap_or_default()
+    }
+}
+
+
+impl From<Category> for String {
+    fn from(category: Category) -> String {
+        category.code().to_string()
+    }
+}
+
+
+impl std::fmt::Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
 }
 
 
@@ -60,11 +354,17 @@ impl Species {
                 code: "".to_string(),  
                 order: "".to_string(),  
                 family: "".to_string(),  
-                status: "".to_string(),  
+                status: Status::NotSet,  
                 aname: "".to_string(),  
                 afname: "".to_string(),  
                 acode: "".to_string(),  
-                list: "".to_string(),  
+                lists: Vec::new(),  
+                seq: 0,
+                subspecies: Vec::new(),
+                names: BTreeMap::new(),
+                notes: String::new(),
+                confusable: Vec::new(),
+                category: Category::Species,
         }
     }
 
@@ -167,29 +467,37 @@ impl Species {
     }
 
 
+    // The full path of the species bin file in the data directory
+    pub fn bin_path() -> String {
+        data_file(SPECIES_BIN_FILENAME)
+    }
+
+
     // Load all the bird species from binary file into map
     #[warn(unused_must_use)]
-    pub fn load(bin_file: &str) -> Result<BTreeMap<String,Species>, String> {
+    pub fn load(bin_file: &str) -> Result<BTreeMap<String,Species>, LoadError> {
         
         let decoded:  BTreeMap<String,Species>;
                 
-        // Lets open the bin file
-        let mut file = match OpenOptions::new()
-            .read(true)
-            .write(false)
-            .create(false)
-            .open(bin_file){
-                Ok(content) => content,
-                Err(_) => { return Err("Problem opening species bin file".to_string()); }
-        };
+        // Lets read the bin file, a missing file is not the same as a broken one
+        let file = read_bin_file(bin_file, FileKind::Species);
+        if file.is_err(){
+            return Err(file.err().unwrap())
+        }
+        let buffer = file.unwrap();
         
-        let mut buffer = Vec::<u8>::new();
-        match file.read_to_end(&mut buffer) {
-            Ok(content)  => { content }
-            Err(_) => { return Err("Problem reading species bin file".to_string()); }
-        };
+        // Older files are brought up to the current layout
+        let header = read_header(&buffer[..], FileKind::Species, SPECIES_FORMAT_VERSION);
+        if header.is_err(){
+            return Err(header.err().unwrap())
+        }
+        let (version, payload) = header.unwrap();
         
-        decoded = bincode::deserialize(&buffer[..]).unwrap();
+        let result = migrate_species(version, payload);
+        if result.is_err(){
+            return Err(result.err().unwrap())
+        }
+        decoded = result.unwrap();
         
         Ok(decoded)
     }
@@ -198,22 +506,13 @@ impl Species {
     pub fn save(bin_file: &str, birds: &BTreeMap<String,Species>) -> Result<(), String> {
         
         // let encoded:  BTreeMap<String,Species>;
-        let encoded: Vec<u8> = bincode::serialize(birds).unwrap();
+        let payload: Vec<u8> = bincode::serialize(birds).unwrap();
+        let encoded = add_header(FileKind::Species, SPECIES_FORMAT_VERSION, &payload);
                 
-        // Lets open the bin file
-        let mut file = match OpenOptions::new()
-            .read(false)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(bin_file){
-                Ok(content) => content,
-                Err(_) => { return Err("Problem saving species bin file".to_string()); }
-        };
-        
-        match file.write_all(&encoded) {
-            Ok(file) => file,
-            Err(_) => { return Err("Problem writing to species bin file".to_string()); }
+        // Write next to the old file and swap it in, so a crash never leaves half a file
+        let res = atomic_write(bin_file, &encoded);
+        if res.is_err(){
+            return Err(res.err().unwrap())
         }
 
         Ok(())
@@ -273,7 +572,9 @@ impl Species {
         
         let path = Path::new(json_file);
         
-        let serialized = serde_json::to_string_pretty(&birds);
+        // Still a map from code to species, but the keys come in the order that was set
+        let ordered = OrderedSpecies(ordered_species(birds));
+        let serialized = serde_json::to_string_pretty(&ordered);
         let mut file = match OpenOptions::new()
                                 .read(false)
                                 .write(true)
@@ -302,7 +603,13 @@ impl Species {
             Err(_) => { return Err("Problem importing species json file".to_string()); }
         };
         
-        let map: BTreeMap<String,Species> = match serde_json::from_str(&content){
+        // An export from before the lists has a single list
+        let mut values: BTreeMap<String,serde_json::Value> = match serde_json::from_str(&content){
+            Ok(values) => values,
+            Err(_) => { return Err("Problem converting species json file".to_string()); }
+        };
+        values.values_mut().for_each(Species::upgrade_json);
+        let map: BTreeMap<String,Species> = match serde_json::from_value(serde_json::json!(values)){
             Ok(map) => map,
             Err(_) => { return Err("Problem converting species json file".to_string()); }
         };
@@ -350,7 +657,7 @@ impl Species {
         let mut file = BufWriter::new(f);
         
         // Do Header
-        let header = "sname\tname\tfname\tcode\torder\tfamily\tstatus\taname\tafname\tacode\tlist\n";
+        let header = "sname\tname\tfname\tcode\torder\tfamily\tstatus\taname\tafname\tacode\tlists\tseq\tsubspecies\tnames\tnotes\tconfusable\tcategory\n";
         match file.write_all(header.as_bytes()) {
             Err(_) => { return Err("Problem writing species csv file".to_string()); } 
             _      => { } 
@@ -358,7 +665,7 @@ impl Species {
         
         let mut counter = 0;
 
-        for (_k,v) in birds {
+        for v in ordered_species(birds) {
             let mut assembly = String::new();
 
             assembly.push_str(&v.sname);
@@ -373,7 +680,7 @@ impl Species {
             assembly.push('\t');
             assembly.push_str(&v.family);
             assembly.push('\t');
-            assembly.push_str(&v.status);
+            assembly.push_str(&v.status.to_string());
             assembly.push('\t');
             assembly.push_str(&v.aname);
             assembly.push('\t');
@@ -381,7 +688,19 @@ impl Species {
             assembly.push('\t');
             assembly.push_str(&v.acode);
             assembly.push('\t');
-            assembly.push_str(&v.list);
+            assembly.push_str(&v.lists.join(","));
+            assembly.push('\t');
+            assembly.push_str(&v.seq.to_string());
+            assembly.push('\t');
+            assembly.push_str(&v.subspecies.join(","));
+            assembly.push('\t');
+            assembly.push_str(&v.names_text());
+            assembly.push('\t');
+            assembly.push_str(&v.notes_text());
+            assembly.push('\t');
+            assembly.push_str(&v.confusable.join(","));
+            assembly.push('\t');
+            assembly.push_str(v.category.code());
             assembly.push('\n');
             
             match file.write_all(assembly.as_bytes()) {
@@ -397,11 +716,11 @@ impl Species {
     
 
     pub fn export_path(extension: &str, options: &mut SettingsText ) -> String {
-        let mut assembly = "./".to_string();
+        let mut assembly = String::new();
         assembly.push_str(&options.date_time_str());
         assembly.push_str("species.");
         assembly.push_str(extension);
-        assembly
+        data_file(&assembly)
     }
  
     
@@ -490,22 +809,19 @@ impl Species {
 
 
         // Validation time and some processing                                       -- status
-        let mut i_status: String = "".to_string();
-        if status.len() > 0 {
-            let temp = status.trim().to_string();
-            // let j_status = temp.to_uppercase();
-            i_status = limit_length(temp, NAME_39);
-
-        } 
+        let r_status = Status::validate_status(&status);
+        if r_status.is_err(){
+            return Err(r_status.err().unwrap());
+        }
+        let i_status = r_status.unwrap();
 
 
-        // Validation time and some processing                                       -- list
-        let mut i_list: String = "".to_string();
-        if list.len() > 0 {
-            // i_list = list.trim().to_title_case().to_string();
-            let j_list = list.trim().to_title_case().to_string();
-            i_list = limit_length(j_list, NAME_39);
-        } 
+        // Validation time and some processing                                       -- lists
+        let r_lists = Species::validate_lists(&list);
+        if r_lists.is_err(){
+            return Err(r_lists.err().unwrap());
+        }
+        let i_lists = r_lists.unwrap();
 
         Ok(Species {
             sname:  i_sname,
@@ -518,7 +834,13 @@ impl Species {
             aname:  i_aname,
             afname: i_afname,
             acode:  i_acode,
-            list:   i_list
+            lists:  i_lists,
+            seq:    0,
+            subspecies: Vec::new(),
+            names:  BTreeMap::new(),
+            notes:  String::new(),
+            confusable: Vec::new(),
+            category: Category::Species,
         })
             
     }   // end of build_species
@@ -539,6 +861,12 @@ impl Species {
         acode:  String,
         list:   String)     -> Result<Species, String> {
 
+        // Status, so that a wrong one says so
+        let r_status = Status::validate_status(&status);
+        if r_status.is_err(){
+            return Err(r_status.err().unwrap());
+        }
+
         let mut species: Species;
         let r_species = Species::build_species(birds, sname.clone(), name.clone(), order.clone(), 
                                             family.clone(), status.clone(), aname.clone(), list.clone());
@@ -608,6 +936,201 @@ impl Species {
     }
 
 
+    // Function to read a list of subspecies like "capensis, ruppelli". A full trinomial is fine too,
+    // only the third name is kept.
+    pub fn validate_subspecies(input: &str) -> Result<Vec<String>, String> {
+        let mut ret: Vec<String> = Vec::new();
+        for part in input.split(',') {
+            let words = line_to_words(part);
+            if words.len() == 0 {
+                continue;
+            }
+            if words.len() != 1 && words.len() != 3 {
+                return Err(format!("Not a valid subspecies -> {}   (either the third name or the trinomial)", part.trim()));
+            }
+            let epithet = words[words.len() - 1].trim().to_lowercase();
+            if !epithet.chars().all(|c| c.is_alphabetic() || c == '-') {
+                return Err(format!("Not a valid subspecies -> {}", part.trim()));
+            }
+            if !ret.contains(&epithet) {
+                ret.push(limit_length(epithet, NAME_39));
+            }
+        }
+        Ok(ret)
+    }
+
+
+    // Function to read the lists a species is on, like "Southern Africa, Garden". The case is kept (UK BOU).
+    pub fn validate_lists(input: &str) -> Result<Vec<String>, String> {
+        let mut ret: Vec<String> = Vec::new();
+        for part in input.split(',') {
+            let name = line_to_words(part).join(" ");
+            if name.len() == 0 {
+                continue;
+            }
+            if name.len() < 2 {
+                return Err(format!("Not a valid list name -> {}", name));
+            }
+            let name = limit_length(name, NAME_39);
+            if !ret.iter().any(|l| l.to_lowercase() == name.to_lowercase()) {
+                ret.push(name);
+            }
+        }
+        Ok(ret)
+    }
+
+
+    // Function to read a language code like "af" or "deu"
+    pub fn validate_language(input: &str) -> Result<String, String> {
+        let language = input.trim().to_lowercase();
+        if language.len() < 2 || language.len() > 3 || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format!("Not a valid language code -> {}   (like af, de or nl)", input.trim()));
+        }
+        Ok(language)
+    }
+
+
+    // Function to read names in other languages, like "af:Geelbekeend, de:Gelbschnabelente".
+    // A language without a name (de:) means that name goes. The case of the names is kept.
+    pub fn validate_names(input: &str) -> Result<Vec<(String, String)>, String> {
+        let mut ret: Vec<(String, String)> = Vec::new();
+        for part in input.split(',') {
+            if part.trim().len() == 0 {
+                continue;
+            }
+            let pair: Vec<&str> = part.splitn(2, ':').collect();
+            if pair.len() != 2 {
+                return Err(format!("Not a valid name in another language -> {}   (like af:Geelbekeend)", part.trim()));
+            }
+            let language = Species::validate_language(pair[0]);
+            if language.is_err(){
+                return Err(language.err().unwrap());
+            }
+            let language = language.unwrap();
+            if language == ENGLISH {
+                return Err("The English name is the name (n=)".to_string());
+            }
+            let name = limit_length(line_to_words(pair[1]).join(" "), NAME_39);
+            ret.retain(|(l, _)| *l != language);
+            ret.push((language, name));
+        }
+        Ok(ret)
+    }
+
+
+    // Function to put names in other languages on the species, an empty name takes it off
+    pub fn set_names(&mut self, names: Vec<(String, String)>) {
+        for (language, name) in names {
+            match name.len() {
+                0 => { self.names.remove(&language); }
+                _ => { self.names.insert(language, name); }
+            }
+        }
+    }
+
+
+    // The names as they are typed (and in the csv), "af:Geelbekeend,de:Gelbschnabelente"
+    pub fn names_text(&self) -> String {
+        self.names.iter().map(|(l, n)| format!("{}:{}", l, n)).collect::<Vec<String>>().join(",")
+    }
+
+
+    // Function to get the name in the preferred language, the English one if there is none
+    pub fn display_name(&self) -> String {
+        match self.names.get(get_language()) {
+            Some(name) => { name.clone() }
+            None       => { self.name.clone() }
+        }
+    }
+
+
+    // Function to see if any of the names (in any language) contains the text
+    pub fn any_name_contains(&self, text: &str) -> bool {
+        self.name.to_lowercase().contains(text) || self.names.values().any(|n| n.to_lowercase().contains(text))
+    }
+
+
+    // Function to read the notes. A typed \n is a line break as well (that is how they are in the csv),
+    // the spaces in a line are tidied and empty lines at the start and end go.
+    pub fn validate_notes(input: &str) -> Result<String, String> {
+        let text = input.replace("\\n", "\n");
+        let lines: Vec<String> = text.lines().map(|l| line_to_words(l).join(" ")).collect();
+        let notes = lines.join("\n").trim_matches('\n').to_string();
+        if notes.chars().count() > NOTES_LEN {
+            return Err(format!("The notes are {} characters long, they can be at most {}", notes.chars().count(), NOTES_LEN));
+        }
+        Ok(notes)
+    }
+
+
+    // The notes on one line, as they are in the csv
+    pub fn notes_text(&self) -> String {
+        self.notes.replace('\n', "\\n")
+    }
+
+
+    // Function to wrap the notes for the screen, every line of the notes is wrapped on its own
+    pub fn notes_lines(&self, width: usize) -> Vec<String> {
+        let mut ret: Vec<String> = Vec::new();
+        for line in self.notes.lines() {
+            match line.len() {
+                0 => { ret.push(String::new()); }
+                _ => { ret.extend(textwrap::wrap(line, width).iter().map(|w| w.to_string())); }
+            }
+        }
+        ret
+    }
+
+
+    // Function to read a list of codes like "duye, mallar", without looking them up
+    pub fn codes_from_text(input: &str) -> Vec<String> {
+        let mut ret: Vec<String> = Vec::new();
+        for part in input.split(',') {
+            let code = part.trim().to_lowercase();
+            if code.len() > 0 && !ret.contains(&code) {
+                ret.push(code);
+            }
+        }
+        ret
+    }
+
+
+    // Function to read the species this one is confused with, every code has to be a species we have
+    pub fn validate_confusable(input: &str, own_code: &str, birds: &BTreeMap<String,Species>) -> Result<Vec<String>, String> {
+        let codes = Species::codes_from_text(input);
+        for code in &codes {
+            if code == own_code {
+                return Err(format!("A species cannot be confused with itself -> {}", code));
+            }
+            if !birds.contains_key(code) {
+                return Err(format!("There is no species with the code -> {}", code));
+            }
+        }
+        Ok(codes)
+    }
+
+
+    // Function to see if the species is on a list, case does not matter
+    pub fn is_on_list(&self, list: &str) -> bool {
+        self.lists.iter().any(|l| l.to_lowercase() == list.to_lowercase())
+    }
+
+
+    // Function to bring species json from before the lists up to date: the one list becomes the first of the lists
+    pub fn upgrade_json(value: &mut serde_json::Value) {
+        if let Some(map) = value.as_object_mut() {
+            if !map.contains_key("lists") {
+                let list = map.remove("list").and_then(|l| l.as_str().map(|l| l.to_string())).unwrap_or_default();
+                let lists: Vec<String> = match list.trim().len() {
+                    0 => { Vec::new() }
+                    _ => { vec![list.trim().to_string()] }
+                };
+                map.insert("lists".to_string(), serde_json::json!(lists));
+            }
+        }
+    }
+
+
     // This function can probably be used for validating the alternative name as well.
     pub fn validate_name(input: &str) -> Result<String, String> {
         let interim = line_to_words(input);
@@ -724,6 +1247,7 @@ pub fn show_bird_number<'a>(z_index: usize, options: &'a mut SettingsText, birds
                               z_index,
                                          &options, 
                                     &k_species.1,
+                                         birds,
                                          );
     
     options.set_value_for_key("lastSpeciesViewed", z_index.to_string()).expect("Option File Problems");  
@@ -848,7 +1372,7 @@ pub fn prepare_species_adding(arg: String, sbirds: &BTreeMap<String,Species>,
     
     let r_list = deconstruct_bird_str('l', &arg);
     if r_list.is_ok() {
-        list = limit_length(r_list.unwrap(),NAME_39);
+        list = r_list.unwrap();
     } else {
         list = "".to_string();
     }
@@ -876,8 +1400,54 @@ pub fn prepare_species_adding(arg: String, sbirds: &BTreeMap<String,Species>,
     if result.is_err(){
         return Err(result.err().unwrap());
     }
+    let mut species = result.unwrap();
+
+    let r_subspecies = deconstruct_bird_str('v', &arg);
+    if r_subspecies.is_ok() {
+        let result = Species::validate_subspecies(&r_subspecies.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.subspecies = result.unwrap();
+    }
+
+    let r_names = deconstruct_bird_str('t', &arg);
+    if r_names.is_ok() {
+        let result = Species::validate_names(&r_names.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.set_names(result.unwrap());
+    }
+
+    let r_notes = deconstruct_bird_str('i', &arg);
+    if r_notes.is_ok() {
+        let result = Species::validate_notes(&r_notes.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.notes = result.unwrap();
+    }
+
+    let r_confusable = deconstruct_bird_str('x', &arg);
+    if r_confusable.is_ok() {
+        let result = Species::validate_confusable(&r_confusable.unwrap(), &species.code, birds);
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.confusable = result.unwrap();
+    }
+
+    let r_category = deconstruct_bird_str('k', &arg);
+    if r_category.is_ok() {
+        let result = Category::validate_category(&r_category.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.category = result.unwrap();
+    }
 
-    Ok(result.unwrap())
+    Ok(species)
 }
 
 
@@ -958,6 +1528,7 @@ pub fn delete_bird<'a>(con: CodeOrNumber , options: &'a mut SettingsText, birds:
     
     let result = birds.remove(&species.code);
     if result.is_some(){
+        rename_confusable(birds, &[(&species.code, None)]);
         let bird_number = index + 1;
         let message = format!("The bird species:  {}{}{} {} with the code:  {}{}{} {} in position  {}{}{} {} was deleted.\n",
                         color::Fg(color::Yellow), style::Italic ,&species.name, style::Reset,
@@ -1017,8 +1588,15 @@ pub fn show_code_range<'a>(code: &str, options: &'a mut SettingsText, birds: &'a
         return Err(r_map.err().unwrap());
     }
 
+    // Listed in the order the species are walked in, so in taxonomic order the numbers go up
+    let mut listed: Vec<(usize, String, Species)> = Vec::new();
     for (k,v) in r_map.unwrap() {
         let r_index = get_index_from_code(&k, birds).unwrap() + 1;                            //Add one
+        listed.push((r_index, k, v));
+    }
+    listed.sort_by_key(|l| l.0);
+
+    for (r_index, k, v) in listed {
         let pos_text = justify(r_index.to_string(), POS_LEN, Justify::Left); 
         let code_text = justify(k.clone(), CODE_LEN, Justify::Left); 
         let fname_text = justify(v.clone().fname, NAME_39, Justify::Left); 
@@ -1050,6 +1628,7 @@ pub fn edit_bird<'a>(arg: String, con: CodeOrNumber, options: &'a mut SettingsTe
             &'a mut Vec<Sightings>), String>{
 
     let mut shuffled_species: Option<Species> = None;
+    let mut shuffled_codes: Option<(String, String)> = None;
     let code_change: bool;
 
     // Let oldest records show first
@@ -1112,9 +1691,17 @@ pub fn edit_bird<'a>(arg: String, con: CodeOrNumber, options: &'a mut SettingsTe
         
         let s = shuffled_species.unwrap();
         let result = Species::build_species(&birds, s.sname, s.name, s.order, 
-            s.family, s.status, s.aname, s.list); 
+            s.family, s.status.to_string(), s.aname, s.lists.join(",")); 
             if result.is_ok(){
-                let insertion_shuffle = birds.insert(result.clone().unwrap().code, result.unwrap());
+                let mut shuffled = result.unwrap();
+                shuffled.seq = s.seq;
+                shuffled.subspecies = s.subspecies;
+                shuffled.names = s.names;
+                shuffled.notes = s.notes;
+                shuffled.confusable = s.confusable;
+                shuffled.category = s.category;
+                shuffled_codes = Some((s.code.clone(), shuffled.code.clone()));
+                let insertion_shuffle = birds.insert(shuffled.code.clone(), shuffled);
                 
                 if insertion_shuffle.is_none(){
                     // let tuple = (options, birds, sightings);
@@ -1126,6 +1713,15 @@ pub fn edit_bird<'a>(arg: String, con: CodeOrNumber, options: &'a mut SettingsTe
             }
         } 
 
+    // The other species follow the codes that changed
+    if code_change {
+        let mut renames: Vec<(&str, Option<&str>)> = vec![(&old_key, Some(&improved_species.code))];
+        if shuffled_codes.is_some() {
+            let (from, to) = shuffled_codes.as_ref().unwrap();
+            renames.push((from, Some(to)));
+        }
+        rename_confusable(birds, &renames);
+    }
         
     // Make a new CodeOrNumber
     let con_new = what_code(&improved_species.code, birds);
@@ -1169,6 +1765,8 @@ pub fn prepare_species_edit(arg: String, old_species: Species, birds: &BTreeMap<
     let list: String;
     let aname: String;
     let mut code: String =String::new();
+    let seq = old_species.seq;
+    let mut subspecies = old_species.subspecies.clone();
 
     let r_name = deconstruct_bird_str('n', &arg);
     if r_name.is_ok() {
@@ -1204,14 +1802,14 @@ pub fn prepare_species_edit(arg: String, old_species: Species, birds: &BTreeMap<
     if r_status.is_ok() {
         status = r_status.unwrap();
     } else {
-        status = old_species.status;
+        status = old_species.status.to_string();
     }
     
     let r_list = deconstruct_bird_str('l', &arg);
     if r_list.is_ok() {
         list = r_list.unwrap();
     } else {
-        list = old_species.list;
+        list = old_species.lists.join(",");
     }
     
     let r_aname = deconstruct_bird_str('e', &arg);
@@ -1226,6 +1824,59 @@ pub fn prepare_species_edit(arg: String, old_species: Species, birds: &BTreeMap<
         return Err(result.err().unwrap());
     }
     let mut species = result.unwrap();
+    // Not something that is edited, it comes with a checklist
+    species.seq = seq;
+
+    let r_subspecies = deconstruct_bird_str('v', &arg);
+    if r_subspecies.is_ok() {
+        let result = Species::validate_subspecies(&r_subspecies.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        subspecies = result.unwrap();
+    }
+    species.subspecies = subspecies;
+
+    // Only the languages that are given change
+    species.names = old_species.names.clone();
+    let r_names = deconstruct_bird_str('t', &arg);
+    if r_names.is_ok() {
+        let result = Species::validate_names(&r_names.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.set_names(result.unwrap());
+    }
+
+    species.notes = old_species.notes.clone();
+    let r_notes = deconstruct_bird_str('i', &arg);
+    if r_notes.is_ok() {
+        let result = Species::validate_notes(&r_notes.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.notes = result.unwrap();
+    }
+
+    species.confusable = old_species.confusable.clone();
+    let r_confusable = deconstruct_bird_str('x', &arg);
+    if r_confusable.is_ok() {
+        let result = Species::validate_confusable(&r_confusable.unwrap(), &old_species.code, birds);
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.confusable = result.unwrap();
+    }
+
+    species.category = old_species.category.clone();
+    let r_category = deconstruct_bird_str('k', &arg);
+    if r_category.is_ok() {
+        let result = Category::validate_category(&r_category.unwrap());
+        if result.is_err(){
+            return Err(result.err().unwrap());
+        }
+        species.category = result.unwrap();
+    }
 
 
     let r_code = deconstruct_bird_str('c', &arg);
@@ -1262,6 +1913,28 @@ pub fn prepare_species_edit(arg: String, old_species: Species, birds: &BTreeMap<
 }
 
 
+// Function to follow species codes that changed (or went, when the new one is None) in what the other
+// species are confused with. All the changes are made at once, since two species can swap codes.
+pub fn rename_confusable(birds: &mut BTreeMap<String,Species>, renames: &[(&str, Option<&str>)]) {
+    for (code, species) in birds.iter_mut() {
+        if !species.confusable.iter().any(|c| renames.iter().any(|(old, _)| c == old)) {
+            continue;
+        }
+        let mut confusable: Vec<String> = Vec::new();
+        for c in &species.confusable {
+            let c = match renames.iter().find(|(old, _)| c == old) {
+                Some((_, new)) => { new.unwrap_or("").to_string() }
+                None           => { c.clone() }
+            };
+            if c.len() > 0 && c != *code && !confusable.contains(&c) {
+                confusable.push(c);
+            }
+        }
+        species.confusable = confusable;
+    }
+}
+
+
 // To check if the desired code meets all the rules for a code change
 pub fn is_code_valid(code: String, species: &Species ) -> bool {
     let nope = false;
@@ -1325,8 +1998,9 @@ pub fn is_code_valid(code: String, species: &Species ) -> bool {
 pub fn get_species_from_line(line: &str, birds: &BTreeMap<String,Species> ) -> Result<Species,String> {
     let temp = line.split("\t");
     let vec: Vec<&str> = temp.collect();
-    if vec.len() != 11 {
-        return Err("Line does not have 11 fields".to_string());
+    // Older exports do not have the seq, subspecies, names, notes, confusable or category columns
+    if vec.len() < 11 || vec.len() > 17 {
+        return Err("Line does not have 11 to 17 fields".to_string());
     }
 
     let ret = Species::validate_species(birds, vec[0].to_string(),
@@ -1337,8 +2011,54 @@ pub fn get_species_from_line(line: &str, birds: &BTreeMap<String,Species> ) -> R
     if ret.is_err(){
         return Err(ret.err().unwrap());
     }
+    let mut species = ret.unwrap();
 
-    return Ok(ret.unwrap())
+    if vec.len() >= 12 && vec[11].trim().len() > 0 {
+        let seq = vec[11].trim().parse::<u32>();
+        if seq.is_err(){
+            return Err(format!("Not a valid taxonomic sequence number -> {}", vec[11]));
+        }
+        species.seq = seq.unwrap();
+    }
+
+    if vec.len() >= 13 {
+        let subspecies = Species::validate_subspecies(vec[12]);
+        if subspecies.is_err(){
+            return Err(subspecies.err().unwrap());
+        }
+        species.subspecies = subspecies.unwrap();
+    }
+
+    if vec.len() >= 14 {
+        let names = Species::validate_names(vec[13]);
+        if names.is_err(){
+            return Err(names.err().unwrap());
+        }
+        species.set_names(names.unwrap());
+    }
+
+    if vec.len() >= 15 {
+        let notes = Species::validate_notes(vec[14]);
+        if notes.is_err(){
+            return Err(notes.err().unwrap());
+        }
+        species.notes = notes.unwrap();
+    }
+
+    // The species it is confused with may come further down the file, so they are not looked up
+    if vec.len() >= 16 {
+        species.confusable = Species::codes_from_text(vec[15]);
+    }
+
+    if vec.len() == 17 {
+        let category = Category::validate_category(vec[16]);
+        if category.is_err(){
+            return Err(category.err().unwrap());
+        }
+        species.category = category.unwrap();
+    }
+
+    return Ok(species)
 }
 
 
@@ -1555,7 +2275,7 @@ mod tests {
 
         let result = Species::build_species(&birds, sname, name, order, family, status, aname, list).unwrap();
 
-        assert_eq!(result.status, "".to_string());
+        assert_eq!(result.status, Status::NotSet);
     }
     
 
@@ -2521,8 +3241,10 @@ mod tests {
 
         let mut s1 = SettingsText::new("./test/options_005.json");
         let ans = Species::export_path("cvs",&mut s1);
+        let name = Path::new(&ans).file_name().unwrap().to_string_lossy().to_string();
         
-        assert_eq!(ans.substring(22, 33),"species.cvs");
+        assert_eq!(name.substring(20, 31),"species.cvs");
+        assert_eq!(Path::new(&ans).parent().unwrap(), get_data_dir());
     }
 
 
@@ -2625,6 +3347,103 @@ mod tests {
 
     }
 
+    
+    #[ignore]
+    #[test]
+    fn t045_validate_subspecies() {
+        let good = Species::validate_subspecies("Undulata, anas undulata ruppelli,, undulata");
+        let bad = Species::validate_subspecies("anas ruppelli");
+        
+        assert_eq!(good.unwrap(), vec!["undulata".to_string(), "ruppelli".to_string()]);
+        assert_eq!(bad.is_err(), true);
+        assert_eq!(Species::validate_subspecies("").unwrap().len(), 0);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t046_validate_status() {
+        assert_eq!(Status::validate_status(" vu ").unwrap(), Status::Vulnerable);
+        assert_eq!(Status::validate_status("Extinct in the wild").unwrap(), Status::ExtinctInTheWild);
+        assert_eq!(Status::validate_status("Rare   in Europe").unwrap(), Status::Regional("Rare in Europe".to_string()));
+        assert_eq!(Status::validate_status("").unwrap(), Status::NotSet);
+        assert_eq!(Status::validate_status("XY").is_err(), true);
+        assert_eq!(Status::from("XY".to_string()), Status::Regional("XY".to_string()));
+        assert_eq!(Status::Endangered.matches("threatened"), true);
+        assert_eq!(Status::Regional("Rare in Europe".to_string()).matches("rare"), true);
+        assert_eq!(Status::CriticallyEndangered.describe(), "Critically Endangered (CR)");
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t047_validate_names() {
+        let mut species = Species::new();
+        species.name = "Yellow-billed Duck".to_string();
+        species.set_names(Species::validate_names("AF:Geelbekeend, de: Gelbschnabelente ,,af:Geelbek eend").unwrap());
+
+        assert_eq!(species.names_text(), "af:Geelbek eend,de:Gelbschnabelente");
+        assert_eq!(species.any_name_contains("gelbschnabel"), true);
+        assert_eq!(Species::validate_names("en:Duck").is_err(), true);
+        assert_eq!(Species::validate_names("Geelbekeend").is_err(), true);
+
+        species.set_names(Species::validate_names("de:").unwrap());
+        assert_eq!(species.names_text(), "af:Geelbek eend");
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t048_validate_notes() {
+        let mut species = Species::new();
+        species.notes = Species::validate_notes("\n  Yellow  bill with a black saddle\\nOn dams,   pans and slow rivers \n").unwrap();
+        let too_long = "a".repeat(NOTES_LEN + 1);
+
+        assert_eq!(species.notes, "Yellow bill with a black saddle\nOn dams, pans and slow rivers");
+        assert_eq!(species.notes_text(), "Yellow bill with a black saddle\\nOn dams, pans and slow rivers");
+        assert_eq!(species.notes_lines(20), ["Yellow bill with a", "black saddle", "On dams, pans and", "slow rivers"]);
+        assert_eq!(Species::validate_notes(&too_long).is_err(), true);
+        assert_eq!(Species::codes_from_text(" DUYE, spho,,duye"), ["duye", "spho"]);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t049_rename_confusable() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        for code in ["duye", "mall", "duaf"] {
+            let mut species = Species::new();
+            species.code = code.to_string();
+            birds.insert(code.to_string(), species);
+        }
+        birds.get_mut("duye").unwrap().confusable = vec!["mall".to_string(), "duaf".to_string()];
+
+        assert_eq!(Species::validate_confusable("mall, duaf", "duye", &birds).unwrap(), ["mall", "duaf"]);
+        assert_eq!(Species::validate_confusable("duye", "duye", &birds).is_err(), true);
+        assert_eq!(Species::validate_confusable("nope", "duye", &birds).is_err(), true);
+
+        // Two codes swap, and one goes into the species itself
+        rename_confusable(&mut birds, &[("mall", Some("duaf")), ("duaf", Some("mall"))]);
+        assert_eq!(birds["duye"].confusable, ["duaf", "mall"]);
+        rename_confusable(&mut birds, &[("mall", Some("duye"))]);
+        assert_eq!(birds["duye"].confusable, ["duaf"]);
+        rename_confusable(&mut birds, &[("duaf", None)]);
+        assert_eq!(birds["duye"].confusable.len(), 0);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t050_validate_category() {
+        assert_eq!(Category::validate_category(" Introduced-Established ").unwrap(), Category::Introduced);
+        assert_eq!(Category::validate_category("issf").unwrap(), Category::SubspeciesGroup);
+        assert_eq!(Category::validate_category("").unwrap(), Category::Species);
+        assert_eq!(Category::validate_category("feral").is_err(), true);
+        assert_eq!(Category::from("feral".to_string()), Category::Species);
+        assert_eq!(Category::Escapee.describe(), "Escapee");
+        assert_eq!(String::from(Category::SubspeciesGroup), "group");
+    }
+
 
 
 
@@ -13,7 +13,7 @@ use draw_box::{Char};
 use termion::{color, style};
 use thousands::{Separable};
 use std::collections::BTreeMap;
-use super::bird_species_support::CodeOrNumber;
+use super::bird_species_support::{CodeOrNumber, get_language};
 
 
 const SPACE:    &str       =   " ";
@@ -33,7 +33,8 @@ const INDEX_HALF_LEN: usize     =   4;
 
 
 // A function to show the data of the species in a box
-pub fn show_species(observations: usize, bird_number: usize, options: &SettingsText, bird: &Species) -> SettingsText {
+pub fn show_species(observations: usize, bird_number: usize, options: &SettingsText, bird: &Species,
+                        birds: &BTreeMap<String,Species>) -> SettingsText {
     let mut tempo = options.clone();
     let my_green: color::Rgb = options.clone().get_color("myGreen");
     let my_normal_gray: color::Rgb = options.clone().get_color("myNormalGray");
@@ -62,7 +63,7 @@ pub fn show_species(observations: usize, bird_number: usize, options: &SettingsT
     print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
     
     // Name - Observations
-    let name = justify(bird.name.clone(), NAME_LEN, Justify::Left);         
+    let name = justify(bird.display_name(), NAME_LEN, Justify::Left);         
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let name_text = justify("Name:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
     let observation_text = justify("Observations:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
@@ -82,10 +83,17 @@ pub fn show_species(observations: usize, bird_number: usize, options: &SettingsT
     let sname = justify(bird.sname.clone(), NAME_LEN, Justify::Left);         
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let sname_text = justify("Scientific Name:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
+    let category_text = justify("Category:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
+    let category = justify(bird.category.describe(), NAME_LEN, Justify::Left);
     underline('S', &sname_text);
     print!("{}{}{}{}", color::Fg(my_normal_gray), style::Italic, sname, style::Reset); 
-    let sname_pad_len: usize = BLOCK_LEN - PAD1_LEN - R_SIDE_TEXT_LEN - NAME_LEN;
-    print!("{}{}{}\n", color::Fg(my_green), &repeat_char(SPACE.to_string(), sname_pad_len), v); 
+    print!("{}", color::Fg(my_green)); 
+    underline('k', &category_text);
+    print!("{}", style::Reset); 
+    print!("{}{}{}", color::Fg(my_normal_gray), category, style::Reset); 
+    let sname_pad_len: usize = BLOCK_LEN - PAD1_LEN - PAD2_LEN - (2 * R_SIDE_TEXT_LEN) - NAME_LEN - NAME_LEN;
+    let add_space = &repeat_char(SPACE.to_string(), sname_pad_len);
+    print!("{}{}{}{}\n", color::Fg(my_green), PADDING2, add_space,  v); 
     
     print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
     
@@ -106,8 +114,8 @@ pub fn show_species(observations: usize, bird_number: usize, options: &SettingsT
     let order = justify(bird.order.clone(), NAME_LEN, Justify::Left);         
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let order_text = justify("Order:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
-    let list_text = justify("List:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);  
-    let list = justify(bird.list.clone(), NAME_LEN, Justify::Left);   
+    let list_text = justify("Lists:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);  
+    let list = justify(limit_length(bird.lists.join(", "), NAME_LEN), NAME_LEN, Justify::Left);   
     underline('r', &order_text);
     print!("{}{}{}", color::Fg(my_normal_gray), order, style::Reset); 
     print!("{}", color::Fg(my_green));
@@ -122,14 +130,15 @@ pub fn show_species(observations: usize, bird_number: usize, options: &SettingsT
     
 
     // Status
-    let status = justify(bird.status.clone(), NAME_LEN, Justify::Left);
+    let my_status_color: color::Rgb = options.clone().get_color(bird.status.color_key());
+    let status = justify(bird.status.describe(), NAME_LEN, Justify::Left);
     let aname = justify(bird.aname.clone(), NAME_LEN, Justify::Left);  
     print!("{}{}{}", MARGIN, v, PADDING1); 
     let status_text = justify("Status:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
     let aname_text = justify("Alt. Name:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
     underline('u', &status_text);
     print!("{}", style::Reset); 
-    print!("{}{}{}", color::Fg(my_normal_gray), status, style::Reset); 
+    print!("{}{}{}", color::Fg(my_status_color), status, style::Reset); 
     print!("{}", color::Fg(my_green)); 
     let status_pad_len: usize = BLOCK_LEN - PAD1_LEN - PAD2_LEN - (2 * R_SIDE_TEXT_LEN) - NAME_LEN - NAME_LEN;
     let add_space = &repeat_char(SPACE.to_string(), status_pad_len);
@@ -177,6 +186,85 @@ pub fn show_species(observations: usize, bird_number: usize, options: &SettingsT
     print!("{}{}{}{}\n", color::Fg(my_green), PADDING2, add_space,  v);
     
     print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
+    
+    
+    // Subspecies, only when the species has a list of them
+    if bird.subspecies.len() > 0 {
+        let subspecies_len: usize = BLOCK_LEN - PAD1_LEN - PAD2_LEN - R_SIDE_TEXT_LEN;
+        let subspecies = justify(limit_length(bird.subspecies.join(", "), subspecies_len), subspecies_len, Justify::Left);
+        print!("{}{}{}", MARGIN, v, PADDING1); 
+        let subspecies_text = justify("Subspecies:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
+        print!("{}", color::Fg(my_green)); 
+        underline('v', &subspecies_text);
+        print!("{}", style::Reset); 
+        print!("{}{}{}", color::Fg(my_normal_gray), subspecies, style::Reset); 
+        print!("{}{}{}\n", color::Fg(my_green), PADDING2, v);
+        
+        print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);              // empty body line
+    }
+    
+    
+    // Names in other languages, with the English one when it is not the name shown above
+    let mut other_names: Vec<String> = bird.names.iter().filter(|(l, _)| l.as_str() != get_language())
+                                            .map(|(l, n)| format!("{}: {}", l, n)).collect();
+    if other_names.len() > 0 && bird.display_name() != bird.name {
+        other_names.insert(0, format!("{}: {}", ENGLISH, bird.name));
+    }
+    if other_names.len() > 0 {
+        let names_len: usize = BLOCK_LEN - PAD1_LEN - PAD2_LEN - R_SIDE_TEXT_LEN;
+        let names = justify(limit_length(other_names.join(", "), names_len), names_len, Justify::Left);
+        print!("{}{}{}", MARGIN, v, PADDING1); 
+        let names_text = justify("Names:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
+        print!("{}", color::Fg(my_green)); 
+        underline('t', &names_text);
+        print!("{}", style::Reset); 
+        print!("{}{}{}", color::Fg(my_normal_gray), names, style::Reset); 
+        print!("{}{}{}\n", color::Fg(my_green), PADDING2, v);
+        
+        print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);              // empty body line
+    }
+    
+    
+    // The species it is easily mistaken for, by name when we still have them
+    if bird.confusable.len() > 0 {
+        let confusable_len: usize = BLOCK_LEN - PAD1_LEN - PAD2_LEN - R_SIDE_TEXT_LEN;
+        let confusable: Vec<String> = bird.confusable.iter().map(|c| match birds.get(c) {
+                                            Some(other) => { format!("{} ({})", other.display_name(), c) }
+                                            None        => { c.clone() }
+                                        }).collect();
+        let confusable = justify(limit_length(confusable.join(", "), confusable_len), confusable_len, Justify::Left);
+        print!("{}{}{}", MARGIN, v, PADDING1); 
+        let confusable_text = justify("Confused with:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
+        print!("{}", color::Fg(my_green)); 
+        underline('x', &confusable_text);
+        print!("{}", style::Reset); 
+        print!("{}{}{}", color::Fg(my_normal_gray), confusable, style::Reset); 
+        print!("{}{}{}\n", color::Fg(my_green), PADDING2, v);
+        
+        print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);              // empty body line
+    }
+    
+    
+    // Our notes, wrapped to the box. The heading is only on the first line.
+    if bird.notes.len() > 0 {
+        let notes_len: usize = BLOCK_LEN - PAD1_LEN - PAD2_LEN - R_SIDE_TEXT_LEN;
+        for (i, line) in bird.notes_lines(notes_len).iter().enumerate() {
+            let note = justify(line.clone(), notes_len, Justify::Left);
+            print!("{}{}{}", MARGIN, v, PADDING1); 
+            if i == 0 {
+                let notes_text = justify("Notes:".to_string(), R_SIDE_TEXT_LEN, Justify::Left);         
+                print!("{}", color::Fg(my_green)); 
+                underline('i', &notes_text);
+                print!("{}", style::Reset); 
+            } else {
+                print!("{}", &repeat_char(SPACE.to_string(), R_SIDE_TEXT_LEN)); 
+            }
+            print!("{}{}{}", color::Fg(my_normal_gray), note, style::Reset); 
+            print!("{}{}{}\n", color::Fg(my_green), PADDING2, v);
+        }
+        
+        print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);              // empty body line
+    }
     print!("{}{}{}{}\n", MARGIN, v, &repeat_char(SPACE.to_string(), BLOCK_LEN), v);                  // empty body line
     
     
@@ -216,6 +304,7 @@ pub fn show_bird<'a>(con: CodeOrNumber, options: &'a mut SettingsText, birds: &B
                               con.clone().number.unwrap(),
                                          &options, 
                                     &species.unwrap().1,
+                                         birds,
                                          );
 
     options.set_value_for_key("lastSpeciesViewed", con.clone().number.unwrap().to_string()).expect("Option File Problems");
@@ -242,6 +331,7 @@ pub fn show_template_bird<'a>(options: &'a mut SettingsText, birds: &'a BTreeMap
                               0,
                                          &options, 
                                     &result,
+                                         &birds,
                                          );
 
     return options;
@@ -0,0 +1,358 @@
+/*
+        Merging an updated species list into our own, instead of replacing it.
+
+        Species are matched on their sname, since that is what the sightings point to.
+            matched     the fields that changed are taken over, but the code (and acode) stays the same.
+                        Empty fields in the new list (a checklist has no status or list) leave ours alone.
+            new         added with a code from Species::make_code, so it cannot clash with one we have
+            missing     ours but not in the new list, they are kept. Those that have sightings are listed.
+
+        And merging two of our own species that turn out to be the same bird (bm): the sightings move
+        over, the lists, subspecies and notes of the one that goes are added to the other one, and
+        then it is deleted. Its names and codes are not taken over, they belong to another species.
+
+        2026.10.02   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::*;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+
+// What bm did
+#[derive(Clone, Debug, Default)]
+pub struct SpeciesMoveReport {
+    pub from: Species,
+    pub into: Species,
+    pub sightings_moved: usize,
+    // The lists of into that the other one's entries were added to
+    pub added_to: Vec<String>,
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct SpeciesMergeReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: usize,
+    // Species that are not in the new list, with their number of sightings
+    pub missing: Vec<(Species, usize)>,
+}
+
+
+// The sname as it is compared
+fn sname_key(sname: &str) -> String {
+    sname.trim().to_lowercase()
+}
+
+
+// Function to keep our value of the fields the new list leaves empty
+fn keep_where_empty(updated: &mut Species, mine: &Species) {
+    for (theirs, ours) in [(&mut updated.name, &mine.name), (&mut updated.fname, &mine.fname),
+                           (&mut updated.order, &mine.order), (&mut updated.family, &mine.family),
+                           (&mut updated.aname, &mine.aname), (&mut updated.afname, &mine.afname)] {
+        if theirs.trim().len() == 0 {
+            *theirs = ours.clone();
+        }
+    }
+    for (language, name) in &mine.names {
+        updated.names.entry(language.clone()).or_insert(name.clone());
+    }
+    if updated.lists.len() == 0 {
+        updated.lists = mine.lists.clone();
+    }
+    if updated.notes.len() == 0 {
+        updated.notes = mine.notes.clone();
+    }
+    if updated.status == Status::NotSet {
+        updated.status = mine.status.clone();
+    }
+    if updated.seq == 0 {
+        updated.seq = mine.seq;
+    }
+}
+
+
+// Function to merge their species into mine, matched on sname
+pub fn merge_species(birds: &mut BTreeMap<String, Species>, theirs: BTreeMap<String, Species>,
+                        sightings: &Vec<Sightings>) -> SpeciesMergeReport {
+    let mut report = SpeciesMergeReport::default();
+
+    let mut by_sname: HashMap<String, String> = HashMap::new();
+    for (code, species) in birds.iter() {
+        by_sname.insert(sname_key(&species.sname), code.clone());
+    }
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for (_, their) in theirs {
+        let key = sname_key(&their.sname);
+        seen.insert(key.clone());
+
+        match by_sname.get(&key) {
+            // Ours, take over what changed but never the codes
+            Some(code) => {
+                let code = code.clone();
+                let mine = birds.get(&code).unwrap().clone();
+                let mut updated = their.clone();
+                updated.sname = mine.sname.clone();
+                updated.code = mine.code.clone();
+                updated.acode = mine.acode.clone();
+                // These are codes as well
+                updated.confusable = mine.confusable.clone();
+                // A checklist does not know what was introduced or escaped here
+                if updated.category == Category::Species {
+                    updated.category = mine.category.clone();
+                }
+                keep_where_empty(&mut updated, &mine);
+
+                if mine == updated {
+                    report.unchanged += 1;
+                } else {
+                    birds.insert(code.clone(), updated);
+                    report.updated.push(code);
+                }
+            }
+
+            // New to us, it gets codes that do not clash with ours
+            None => {
+                let mut new = their.clone();
+                new.confusable = Vec::new();
+                new.code = Species::make_code(&new.fname, birds);
+                new.acode = match new.afname.len() {
+                    0 => { String::new() }
+                    _ => { Species::make_acode(&new.afname, birds) }
+                };
+                by_sname.insert(key, new.code.clone());
+                report.added.push(new.code.clone());
+                birds.insert(new.code.clone(), new);
+            }
+        }
+    }
+
+    // What the new list does not have any more
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for sighting in sightings {
+        *counts.entry(sname_key(&sighting.sname)).or_default() += 1;
+    }
+    for species in birds.values() {
+        let key = sname_key(&species.sname);
+        if !seen.contains(&key) {
+            let number = counts.get(&key).cloned().unwrap_or(0);
+            report.missing.push((species.clone(), number));
+        }
+    }
+
+    report
+}
+
+
+// Function to merge one of our species into another one: its sightings move over, its lists,
+// subspecies and notes are added, and it is deleted
+pub fn merge_two_species(from_code: &str, into_code: &str, birds: &mut BTreeMap<String, Species>,
+                            sightings: &mut Vec<Sightings>) -> Result<SpeciesMoveReport, String> {
+    let from_sname = match birds.get(from_code) {
+        Some(species) => { species.sname.clone() }
+        None          => { return Err(format!("There is no species with the code -> {}", from_code)) }
+    };
+    let used: Vec<String> = sightings.iter().filter(|s| s.sname == from_sname).map(|s| s.subspecies.clone()).collect();
+    let result = merge_species_record(from_code, into_code, birds, &used);
+    if result.is_err(){
+        return Err(result.err().unwrap())
+    }
+    let mut report = result.unwrap();
+
+    for sighting in sightings.iter_mut().filter(|s| s.sname == from_sname) {
+        sighting.sname = report.into.sname.clone();
+        report.sightings_moved += 1;
+    }
+    sightings.sort();
+    Ok(report)
+}
+
+
+// Function to merge only the species, for when its sightings have been moved some other way.
+// The subspecies those sightings use are kept on the one it goes into.
+pub fn merge_species_record(from_code: &str, into_code: &str, birds: &mut BTreeMap<String, Species>,
+                                subspecies_used: &[String]) -> Result<SpeciesMoveReport, String> {
+    if from_code == into_code {
+        return Err("A species cannot be merged into itself.".to_string())
+    }
+    let from = match birds.get(from_code) {
+        Some(species) => { species.clone() }
+        None          => { return Err(format!("There is no species with the code -> {}", from_code)) }
+    };
+    let mut into = match birds.get(into_code) {
+        Some(species) => { species.clone() }
+        None          => { return Err(format!("There is no species with the code -> {}", into_code)) }
+    };
+    let before = into.clone();
+
+    // Only what can be added to is taken over, the names and codes are those of another species
+    // A list of subspecies means only those are allowed, so keep all that are in use
+    if into.subspecies.len() > 0 || from.subspecies.len() > 0 {
+        for subspecies in from.subspecies.iter().chain(subspecies_used.iter()).cloned() {
+            if subspecies.len() > 0 && !into.subspecies.contains(&subspecies) {
+                into.subspecies.push(subspecies);
+            }
+        }
+    }
+
+    // Both sets of notes are kept
+    if before.notes.len() == 0 {
+        into.notes = from.notes.clone();
+    } else if from.notes.len() > 0 && before.notes != from.notes {
+        into.notes = format!("{}\n{}", into.notes, from.notes);
+    }
+    for code in &from.confusable {
+        if code != into_code && !into.confusable.contains(code) {
+            into.confusable.push(code.clone());
+        }
+    }
+
+    // It stays on the lists of both
+    for list in &from.lists {
+        if !into.is_on_list(list) {
+            into.lists.push(list.clone());
+        }
+    }
+
+    let mut report = SpeciesMoveReport::default();
+    for (name, changed) in [("lists", before.lists != into.lists), ("subspecies", before.subspecies != into.subspecies),
+                            ("notes", before.notes != into.notes), ("confusable", before.confusable != into.confusable)] {
+        if changed {
+            report.added_to.push(name.to_string());
+        }
+    }
+
+    birds.remove(from_code);
+    birds.insert(into_code.to_string(), into.clone());
+    rename_confusable(birds, &[(from_code, Some(into_code))]);
+    report.from = from;
+    report.into = into;
+    Ok(report)
+}
+
+
+// Function to tell the user what bm did
+pub fn display_species_move_report(report: &SpeciesMoveReport) {
+    let mut message = format!("{} ({}) was merged into {} ({}), {} {} moved over.",
+                                report.from.name, report.from.code, report.into.name, report.into.code,
+                                report.sightings_moved.separate_with_spaces(), plural("sighting", report.sightings_moved));
+    if report.added_to.len() > 0 {
+        message.push_str(&format!(" Added to its {}.", report.added_to.join(", ")));
+    }
+    feedback(Feedback::Info, message);
+}
+
+
+// Function to tell the user what the merge did, and which missing species still have sightings
+pub fn display_species_merge_report(options: &mut SettingsText, report: &SpeciesMergeReport) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    let with_sightings: Vec<&(Species, usize)> = report.missing.iter().filter(|(_, n)| *n > 0).collect();
+    if with_sightings.len() > 0 {
+        println!("{}Not in the new list, but they have sightings (they were kept){}", color::Fg(my_light_blue), style::Reset);
+        for (species, number) in &with_sightings {
+            println!("{}{:<10}  {:<40}  {:<39}  {:>6} {}{}", color::Fg(my_normal_gray), species.code, species.name, species.sname,
+                        number.separate_with_spaces(), plural("sighting", *number), style::Reset);
+        }
+        println!();
+    }
+
+    let message = format!("{} species added, {} updated, {} unchanged, {} not in the new list ({} with sightings).",
+                            report.added.len().separate_with_spaces(), report.updated.len().separate_with_spaces(),
+                            report.unchanged.separate_with_spaces(), report.missing.len().separate_with_spaces(),
+                            with_sightings.len().separate_with_spaces());
+    feedback(Feedback::Info, message);
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn species(code: &str, sname: &str, name: &str, family: &str) -> Species {
+        let mut species = Species::new();
+        species.code = code.to_string();
+        species.sname = sname.to_string();
+        species.name = name.to_string();
+        species.fname = name.to_string();
+        species.family = family.to_string();
+        species
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_merge_species() {
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        birds.insert("bisor".to_string(), species("bisor", "Euplectes orix", "Southern Red Bishop", "Ploceidae"));
+        birds.insert("duye".to_string(), species("duye", "Anas undulata", "Yellow-billed Duck", "Anatidae"));
+        let mut sighting = Sightings::new();
+        sighting.sname = "Anas undulata".to_string();
+        let sightings = vec![sighting];
+
+        let mut theirs: BTreeMap<String, Species> = BTreeMap::new();
+        theirs.insert("x".to_string(), species("x", "euplectes orix", "Southern Red Bishop", "Weavers"));
+        theirs.insert("y".to_string(), species("y", "Upupa africana", "African Hoopoe", "Upupidae"));
+
+        let report = merge_species(&mut birds, theirs, &sightings);
+
+        assert_eq!(report.updated, vec!["bisor".to_string()]);
+        assert_eq!(birds.get("bisor").unwrap().family, "Weavers");
+        assert_eq!(birds.get("bisor").unwrap().sname, "Euplectes orix");
+        assert_eq!(report.added, vec!["afho".to_string()]);
+        assert_eq!(birds.len(), 3);
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].1, 1);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_merge_two_species() {
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        let mut from = species("duye1", "Anas undulatus", "Yellow-billed Duck", "Anatidae");
+        from.lists = vec!["Garden".to_string()];
+        from.status = Status::LeastConcern;
+        from.aname = "Geelbekeend".to_string();
+        from.acode = "geel".to_string();
+        birds.insert("duye1".to_string(), from);
+        let mut into = species("duye", "Anas undulata", "Yellow-billed Duck", "Anatidae");
+        into.status = Status::NearThreatened;
+        birds.insert("duye".to_string(), into);
+        let mut sightings: Vec<Sightings> = Vec::new();
+        for sname in ["Anas undulatus", "Anas undulata", "Anas undulatus", "Euplectes orix"] {
+            let mut sighting = Sightings::new();
+            sighting.sname = sname.to_string();
+            sightings.push(sighting);
+        }
+
+        let report = merge_two_species("duye1", "duye", &mut birds, &mut sightings).unwrap();
+        let duck = birds.get("duye").unwrap();
+
+        assert_eq!(report.sightings_moved, 2);
+        assert_eq!(report.added_to, vec!["lists".to_string()]);
+        assert_eq!(birds.len(), 1);
+        assert_eq!(duck.lists, vec!["Garden".to_string()]);
+        assert_eq!(duck.status, Status::NearThreatened);
+        assert_eq!((duck.aname.as_str(), duck.acode.as_str()), ("", ""));
+        assert_eq!(sightings.iter().filter(|s| s.sname == "Anas undulata").count(), 3);
+        assert_eq!(merge_two_species("duye", "duye", &mut birds, &mut sightings).is_err(), true);
+    }
+}
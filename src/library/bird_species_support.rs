@@ -12,12 +12,40 @@ use crate::library::bird_species::*;
 use crate::library::my_file_funcs::*;
 use crate::library::settings::*;
 use std::{collections::BTreeMap};
+use std::str::FromStr;
+use std::sync::OnceLock;
 use super::my_file_funcs::Feedback;
 
 
 
 
 
+// The order the species are walked in (b, bb, bf, the index numbers and the exports)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpeciesOrder {
+    Code,
+    Taxonomic,
+}
+
+impl FromStr for SpeciesOrder {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<SpeciesOrder, String> {
+        match text.to_lowercase().trim() {
+            "code"                   => { Ok(SpeciesOrder::Code) }
+            "taxonomic"|"taxonomy"   => { Ok(SpeciesOrder::Taxonomic) }
+            _                        => { Err(format!("Not a valid speciesSortOrder -> {}   (either code or taxonomic)", text)) }
+        }
+    }
+}
+
+// Set once from the options at the start, code order until then
+static SPECIES_ORDER: OnceLock<SpeciesOrder> = OnceLock::new();
+
+// The language the names are shown in, set once from the options at the start
+static LANGUAGE: OnceLock<String> = OnceLock::new();
+
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Code{
     Neither,
@@ -147,12 +175,105 @@ pub fn what_code(sub: &str, birds: &mut BTreeMap<String,Species>) -> CodeOrNumbe
 } // end of what_code
 
 
+// Function to set the order the species are walked in, for the rest of the run
+pub fn set_species_order(order: SpeciesOrder) {
+    let _ = SPECIES_ORDER.set(order);
+}
+
+
+// Function to get the order the species are walked in
+pub fn get_species_order() -> SpeciesOrder {
+    match SPECIES_ORDER.get() {
+        Some(order) => { *order }
+        None        => { SpeciesOrder::Code }
+    }
+}
+
+
+// Function to set the language the names are shown in, only the first call counts
+pub fn set_language(language: String) {
+    let _ = LANGUAGE.set(language);
+}
+
+
+// Function to get the language the names are shown in
+pub fn get_language() -> &'static str {
+    match LANGUAGE.get() {
+        Some(language) => { language }
+        None           => { ENGLISH }
+    }
+}
+
+
+// Function to get the species in the order that was set (the index numbers follow this)
+pub fn ordered_species(birds: &BTreeMap<String,Species>) -> Vec<&Species> {
+    ordered_species_by(birds, get_species_order())
+}
+
+
+// Function to get the species in the given order. In taxonomic order the ones without
+// a sequence number go at the end, in code order.
+pub fn ordered_species_by(birds: &BTreeMap<String,Species>, order: SpeciesOrder) -> Vec<&Species> {
+    let mut ret: Vec<&Species> = birds.values().collect();
+    if order == SpeciesOrder::Taxonomic {
+        ret.sort_by_key(|s| (s.seq == 0, s.seq, s.code.clone()));
+    }
+    ret
+}
+
+
+// Function to get the index of the first species of the next family (forward) or the previous one,
+// the way one pages through a field guide. Stays put at either end.
+pub fn get_family_step(z_index: usize, birds: &BTreeMap<String,Species>, forward: bool) -> usize {
+    let list = ordered_species(birds);
+    if z_index >= list.len() {
+        return 0
+    }
+    let family = &list[z_index].family;
+
+    if forward {
+        return match (z_index..list.len()).find(|i| &list[*i].family != family) {
+            Some(i) => { i }
+            None    => { z_index }
+        }
+    }
+
+    // Back to the start of this family, and then to the start of the one before it
+    let mut start = z_index;
+    while start > 0 && &list[start - 1].family == family {
+        start -= 1;
+    }
+    if start == 0 {
+        return 0
+    }
+    let previous = &list[start - 1].family;
+    start -= 1;
+    while start > 0 && &list[start - 1].family == previous {
+        start -= 1;
+    }
+    start
+}
+
+
+// Function to tell the user when stepping to another species went into another family
+pub fn announce_family_change(from: usize, to: usize, birds: &BTreeMap<String,Species>) {
+    let list = ordered_species(birds);
+    if from >= list.len() || to >= list.len() || list[from].family == list[to].family {
+        return
+    }
+    let family = &list[to].family;
+    let count = list.iter().filter(|s| &s.family == family).count();
+    let message = format!("Family: {}{}{}   ({} species)", color::Fg(color::Yellow), family, style::Reset, count);
+    feedback(Feedback::Info, message);
+}
+
+
 // Find the index of the sname in the birds database
 pub fn get_index_from_sname(sname: &str, birds: &mut BTreeMap<String,Species>)  -> Result<usize, String> {
     let mut index: usize = 0;
     let mut found: bool = false;
 
-    for (_k,v) in birds {
+    for v in ordered_species(birds) {
         if sname == v.sname {
             found = true;
             break;
@@ -171,7 +292,7 @@ pub fn get_index_from_sname(sname: &str, birds: &mut BTreeMap<String,Species>)
 pub fn get_index_from_code(code: &str, birds: &BTreeMap<String,Species>) -> Result<usize, String> {
     let mut counter = 0;
     let mut found: bool = false; 
-    for (_k, v) in birds {
+    for v in ordered_species(birds) {
         if code == v.code {
             found = true;
             break;
@@ -196,10 +317,10 @@ pub fn get_species_from_index<'a>(z_index: usize, birds: &'a mut BTreeMap<String
     let mut key = String::new();
     let mut species = Species::new(); 
 
-    for (k,v) in birds {
+    for v in ordered_species(birds) {
         if counter == z_index {
             found = true;
-            key = k.to_string();
+            key = v.code.to_string();
             species = v.clone();
             break;
         }
@@ -418,6 +539,49 @@ mod tests {
     }
 
 
+    #[ignore]
+    #[test]
+    fn t005_taxonomic_order() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        for (code, family, seq) in [("aaa", "Anatidae", 0), ("bbb", "Ploceidae", 20), ("ccc", "Anatidae", 2),
+                                     ("ddd", "Ploceidae", 21), ("eee", "Anatidae", 1)] {
+            let mut species = Species::new();
+            species.code = code.to_string();
+            species.family = family.to_string();
+            species.seq = seq;
+            birds.insert(code.to_string(), species);
+        }
+
+        let by_code: Vec<&str> = ordered_species_by(&birds, SpeciesOrder::Code).iter().map(|s| s.code.as_str()).collect();
+        let by_seq: Vec<&str> = ordered_species_by(&birds, SpeciesOrder::Taxonomic).iter().map(|s| s.code.as_str()).collect();
+
+        assert_eq!(by_code, ["aaa", "bbb", "ccc", "ddd", "eee"]);
+        assert_eq!(by_seq, ["eee", "ccc", "bbb", "ddd", "aaa"]);
+        assert_eq!("Taxonomic".parse::<SpeciesOrder>().unwrap(), SpeciesOrder::Taxonomic);
+        assert_eq!("field guide".parse::<SpeciesOrder>().is_err(), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t006_family_step() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        for (code, family) in [("a1", "A"), ("a2", "A"), ("b1", "B"), ("b2", "B"), ("b3", "B"), ("c1", "C")] {
+            let mut species = Species::new();
+            species.code = code.to_string();
+            species.family = family.to_string();
+            birds.insert(code.to_string(), species);
+        }
+
+        assert_eq!(get_family_step(0, &birds, true), 2);
+        assert_eq!(get_family_step(3, &birds, true), 5);
+        assert_eq!(get_family_step(5, &birds, true), 5);
+        assert_eq!(get_family_step(3, &birds, false), 0);
+        assert_eq!(get_family_step(5, &birds, false), 2);
+        assert_eq!(get_family_step(1, &birds, false), 0);
+    }
+
+
     // #[ignore]
     // #[test]
     // fn t004_make1() {
@@ -0,0 +1,407 @@
+/*
+        Taxonomy updates: when a checklist renames, splits or lumps species, the sightings follow.
+
+        The mapping file is a csv (comma or tab) with a header line and two columns, the old and
+        the new scientific name. An old name that appears on more than one line is a split,
+        more than one old name going to the same new name is a lump.
+
+            rename / lump   all sightings of the old name get the new name. The old species is renamed
+                            when the new name is free, otherwise it is merged into the species that has it.
+                            Names are worked out before anything moves, so chains and swaps are fine.
+            split           nothing is changed, the sightings are listed so they can be sorted out by hand
+
+        Everything that was done (or flagged) is added to taxonomy_audit.tsv in the data directory:
+            when    mapping file    what    code or sighting id    old    new
+
+        2026.10.07   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::*;
+use crate::library::bird_species_support::*;
+use crate::library::bird_species_merge::*;
+use crate::library::data_dir::*;
+use crate::library::settings::*;
+use crate::library::my_file_funcs::*;
+use termion::{color, style};
+use thousands::Separable;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+
+
+pub const TAXONOMY_AUDIT_FILENAME: &str = "taxonomy_audit.tsv";
+
+
+// One old name and what it becomes, more than one new name is a split
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaxonomyChange {
+    pub old: String,
+    pub new: Vec<String>,
+}
+
+
+#[derive(Clone, Debug, Default)]
+pub struct TaxonomyReport {
+    // (sighting id, old, new)
+    pub sightings_moved: Vec<(String, String, String)>,
+    // (code, old, new)
+    pub species_renamed: Vec<(String, String, String)>,
+    // (code, old, new), merged into the species that already had the new name
+    pub species_lumped: Vec<(String, String, String)>,
+    // (code, old, new, why), the sightings moved but the species could not be merged
+    pub not_lumped: Vec<(String, String, String, String)>,
+    // (old, the new names, the sightings that need to be sorted out)
+    pub splits: Vec<(String, Vec<String>, Vec<Sightings>)>,
+    // Old names that are neither a species nor in any sighting
+    pub not_found: Vec<String>,
+}
+
+
+// Function to read the mapping file, old names in the order they first appear
+pub fn read_taxonomy_map(csv_file: &str) -> Result<Vec<TaxonomyChange>, String> {
+    let text = std::fs::read_to_string(csv_file);
+    if text.is_err(){
+        return Err(format!("Cannot open the mapping file for reading -> {}", csv_file));
+    }
+    let text = text.unwrap();
+    let first_line = text.lines().next().unwrap_or("");
+    let delimiter = if first_line.contains('\t') { b'\t' } else { b',' };
+
+    let mut reader = csv::ReaderBuilder::new()
+                        .delimiter(delimiter)
+                        .flexible(true)
+                        .from_reader(text.trim_start_matches('\u{feff}').as_bytes());
+
+    let mut changes: Vec<TaxonomyChange> = Vec::new();
+    for (line, record) in reader.records().enumerate() {
+        let record = match record {
+            Ok(record) => { record }
+            Err(_)     => { return Err(format!("Problem reading the mapping file, around line {}", line + 2)); }
+        };
+        let old = record.get(0).unwrap_or("").trim();
+        let new = record.get(1).unwrap_or("").trim();
+        if old.len() == 0 && new.len() == 0 {
+            continue;
+        }
+
+        let old = Species::validate_sname(old);
+        let new = Species::validate_sname(new);
+        if old.is_err() || new.is_err() {
+            return Err(format!("Line {} of the mapping file needs an old and a new scientific name", line + 2));
+        }
+        let (old, new) = (old.unwrap(), new.unwrap());
+
+        match changes.iter_mut().find(|c| c.old == old) {
+            Some(change) => {
+                if !change.new.contains(&new) {
+                    change.new.push(new);
+                }
+            }
+            None => { changes.push(TaxonomyChange { old: old, new: vec![new] }); }
+        }
+    }
+
+    // A name that stays the same is only of interest as part of a split
+    changes.retain(|c| c.new != vec![c.old.clone()]);
+    if changes.len() == 0 {
+        return Err(format!("There are no name changes in the mapping file -> {}", csv_file));
+    }
+    Ok(changes)
+}
+
+
+// Function to apply the name changes to the species and sightings. Everything is looked up with
+// the name it had before, so a -> b and b -> c in one file does not make a -> c.
+pub fn apply_taxonomy_changes(changes: &Vec<TaxonomyChange>, birds: &mut BTreeMap<String,Species>,
+                                sightings: &mut Vec<Sightings>) -> TaxonomyReport {
+    let mut report = TaxonomyReport::default();
+    let by_old: HashMap<&String, &TaxonomyChange> = changes.iter().map(|c| (&c.old, c)).collect();
+    let snames_before = make_sname_btree(birds);
+
+    // The sightings
+    let mut split_sightings: HashMap<String, Vec<Sightings>> = HashMap::new();
+    for sighting in sightings.iter_mut() {
+        let change = match by_old.get(&sighting.sname) {
+            Some(change) => { change }
+            None         => { continue; }
+        };
+        if change.new.len() > 1 {
+            split_sightings.entry(change.old.clone()).or_default().push(sighting.clone());
+            continue;
+        }
+        report.sightings_moved.push((sighting.id.clone(), sighting.sname.clone(), change.new[0].clone()));
+        sighting.sname = change.new[0].clone();
+    }
+    sightings.sort();
+
+    // Where each species ends up is worked out before anything is moved: a species keeps its name
+    // unless it is renamed itself, the others take a free name in the order of the mapping file,
+    // and a name that is already held makes it a lump
+    let moving: HashSet<&String> = changes.iter().filter(|c| c.new.len() == 1 && snames_before.contains_key(&c.old))
+                                        .map(|c| &c.old).collect();
+    let mut held: HashMap<String, String> = snames_before.values().filter(|s| !moving.contains(&s.sname))
+                                        .map(|s| (s.sname.clone(), s.code.clone())).collect();
+    let mut renames: Vec<(&TaxonomyChange, String)> = Vec::new();
+    let mut lumps: Vec<(&TaxonomyChange, String, String)> = Vec::new();
+    for change in changes {
+        if change.new.len() > 1 {
+            let flagged = split_sightings.remove(&change.old).unwrap_or_default();
+            if flagged.len() == 0 && !snames_before.contains_key(&change.old) {
+                report.not_found.push(change.old.clone());
+                continue;
+            }
+            report.splits.push((change.old.clone(), change.new.clone(), flagged));
+            continue;
+        }
+
+        let new = &change.new[0];
+        let old_species = match snames_before.get(&change.old) {
+            Some(species) => { species }
+            None          => {
+                if !report.sightings_moved.iter().any(|m| &m.1 == &change.old) {
+                    report.not_found.push(change.old.clone());
+                }
+                continue;
+            }
+        };
+        match held.get(new) {
+            Some(into_code) => { lumps.push((change, old_species.code.clone(), into_code.clone())); }
+            None            => {
+                held.insert(new.clone(), old_species.code.clone());
+                renames.push((change, old_species.code.clone()));
+            }
+        }
+    }
+
+    // The species
+    for (change, code) in renames {
+        let species = birds.get_mut(&code).unwrap();
+        species.sname = change.new[0].clone();
+        report.species_renamed.push((code, change.old.clone(), change.new[0].clone()));
+    }
+    // Their sightings have already moved, and the old name may now belong to another species
+    for (change, from_code, into_code) in lumps {
+        let moved: HashSet<&String> = report.sightings_moved.iter().filter(|m| m.1 == change.old).map(|m| &m.0).collect();
+        let used: Vec<String> = sightings.iter().filter(|s| moved.contains(&s.id)).map(|s| s.subspecies.clone()).collect();
+        match merge_species_record(&from_code, &into_code, birds, &used) {
+            Ok(_)        => { report.species_lumped.push((from_code, change.old.clone(), change.new[0].clone())); }
+            Err(message) => { report.not_lumped.push((from_code, change.old.clone(), change.new[0].clone(), message)); }
+        }
+    }
+
+    report
+}
+
+
+// Function to add what was done to the audit file, it is only ever added to
+pub fn write_taxonomy_audit(audit_file: &str, mapping_file: &str, report: &TaxonomyReport) -> Result<(), String> {
+    let when = chrono::offset::Local::now().format("%Y.%m.%d %H:%M:%S").to_string();
+    let mut text = String::new();
+    let mut add = |what: &str, key: &str, old: &str, new: &str| {
+        text.push_str(&format!("{}\t{}\t{}\t{}\t{}\t{}\n", when, mapping_file, what, key, old, new));
+    };
+
+    for (code, old, new) in &report.species_renamed {
+        add("species renamed", code, old, new);
+    }
+    for (code, old, new) in &report.species_lumped {
+        add("species lumped", code, old, new);
+    }
+    for (code, old, new, why) in &report.not_lumped {
+        add(&format!("lump not done: {}", why), code, old, new);
+    }
+    for (id, old, new) in &report.sightings_moved {
+        add("sighting moved", id, old, new);
+    }
+    for (old, new, flagged) in &report.splits {
+        for sighting in flagged {
+            add("split to resolve", &sighting.id, old, &new.join(" / "));
+        }
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(audit_file);
+    if file.is_err(){
+        return Err(format!("Problem opening the taxonomy audit file -> {}", audit_file));
+    }
+    match file.unwrap().write_all(text.as_bytes()) {
+        Ok(_)  => { Ok(()) }
+        Err(_) => { Err(format!("Problem writing the taxonomy audit file -> {}", audit_file)) }
+    }
+}
+
+
+// Function to get the path of the audit file
+pub fn taxonomy_audit_path() -> String {
+    data_file(TAXONOMY_AUDIT_FILENAME)
+}
+
+
+// Function to tell the user what changed, and list the splits that need sorting out
+pub fn display_taxonomy_report(options: &mut SettingsText, report: &TaxonomyReport) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    let renames = report.species_renamed.iter().map(|r| ("renamed", r)).chain(report.species_lumped.iter().map(|r| ("lumped", r)));
+    let mut shown = false;
+    for (what, (code, old, new)) in renames {
+        if !shown {
+            println!("{}Species{}", color::Fg(my_light_blue), style::Reset);
+            shown = true;
+        }
+        println!("{}{:<10}  {:<8}  {:<39}  ->  {}{}", color::Fg(my_normal_gray), code, what, old, new, style::Reset);
+    }
+    if shown {
+        println!();
+    }
+
+    for (old, new, flagged) in &report.splits {
+        println!("{}Split, sort these out by hand:  {}  ->  {}{}", color::Fg(my_light_blue), old, new.join(" / "), style::Reset);
+        for sighting in flagged {
+            println!("{}{}  {}  {}{}", color::Fg(my_normal_gray), sighting.id, sighting.display_date(),
+                        limit_length(sighting.location.clone(), 40), style::Reset);
+        }
+        println!();
+    }
+
+    for (code, old, new, why) in &report.not_lumped {
+        let message = format!("{} ({}) could not be lumped into {}, its sightings did move: {}", old, code, new, why);
+        feedback(Feedback::Warning, message);
+    }
+    if report.not_found.len() > 0 {
+        let message = format!("Not a species or in any sighting: {}", report.not_found.join(", "));
+        feedback(Feedback::Warning, message);
+    }
+
+    let flagged: usize = report.splits.iter().map(|s| s.2.len()).sum();
+    let message = format!("{} {} moved, {} species renamed, {} lumped, {} {} in {} {} to sort out by hand.",
+                            report.sightings_moved.len().separate_with_spaces(), plural("sighting", report.sightings_moved.len()),
+                            report.species_renamed.len().separate_with_spaces(), report.species_lumped.len().separate_with_spaces(),
+                            flagged.separate_with_spaces(), plural("sighting", flagged),
+                            report.splits.len().separate_with_spaces(), plural("split", report.splits.len()));
+    feedback(Feedback::Info, message);
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn species(code: &str, sname: &str) -> Species {
+        let mut species = Species::new();
+        species.code = code.to_string();
+        species.sname = sname.to_string();
+        species
+    }
+
+
+    fn sighting(sname: &str, date: i64) -> Sightings {
+        let mut sighting = Sightings::new();
+        sighting.sname = sname.to_string();
+        sighting.date = date;
+        sighting
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_read_taxonomy_map() {
+        let changes = read_taxonomy_map("./test/store/species/taxonomy_map.csv").unwrap();
+
+        assert_eq!(changes.len(), 4);
+        assert_eq!(changes[0], TaxonomyChange { old: "Anas undulata".to_string(), new: vec!["Anas ruppelli".to_string()] });
+        assert_eq!(changes[1].new.len(), 2);
+        assert_eq!(read_taxonomy_map("./test/store/species/bad1.csv").is_err(), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_apply_taxonomy_changes() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        for (code, sname) in [("duye", "Anas undulata"), ("sugr", "Sula capensis"), ("spmo", "Morus capensis"),
+                              ("libl", "Buteo buteo")] {
+            birds.insert(code.to_string(), species(code, sname));
+        }
+        let mut sightings = vec![sighting("Anas undulata", 1), sighting("Sula capensis", 2), sighting("Buteo buteo", 3),
+                                 sighting("Buteo buteo", 4), sighting("Upupa africana", 5)];
+        let changes = vec![
+            TaxonomyChange { old: "Anas undulata".to_string(), new: vec!["Anas ruppelli".to_string()] },
+            TaxonomyChange { old: "Sula capensis".to_string(), new: vec!["Morus capensis".to_string()] },
+            TaxonomyChange { old: "Buteo buteo".to_string(), new: vec!["Buteo vulpinus".to_string(), "Buteo buteo".to_string()] },
+            TaxonomyChange { old: "Dodo dodo".to_string(), new: vec!["Raphus cucullatus".to_string()] },
+        ];
+
+        let report = apply_taxonomy_changes(&changes, &mut birds, &mut sightings);
+
+        assert_eq!(report.sightings_moved.len(), 2);
+        assert_eq!(birds.get("duye").unwrap().sname, "Anas ruppelli");
+        assert_eq!(birds.contains_key("sugr"), false);
+        assert_eq!(report.species_lumped[0].0, "sugr");
+        assert_eq!(sightings.iter().filter(|s| s.sname == "Morus capensis").count(), 1);
+        assert_eq!(report.splits[0].2.len(), 2);
+        assert_eq!(sightings.iter().filter(|s| s.sname == "Buteo buteo").count(), 2);
+        assert_eq!(report.not_found, vec!["Dodo dodo".to_string()]);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_chains_and_swaps() {
+        let mut birds: BTreeMap<String,Species> = BTreeMap::new();
+        for (code, sname) in [("aaaa", "Anas a"), ("bbbb", "Anas b"), ("cccc", "Anas c"), ("dddd", "Anas d"),
+                              ("eeee", "Anas e")] {
+            birds.insert(code.to_string(), species(code, sname));
+        }
+        birds.get_mut("eeee").unwrap().notes = "Only in e".to_string();
+        let mut sightings = vec![sighting("Anas a", 1), sighting("Anas b", 2), sighting("Anas c", 3),
+                                 sighting("Anas d", 4), sighting("Anas e", 5)];
+        let changes = vec![
+            TaxonomyChange { old: "Anas a".to_string(), new: vec!["Anas b".to_string()] },
+            TaxonomyChange { old: "Anas b".to_string(), new: vec!["Anas x".to_string()] },
+            TaxonomyChange { old: "Anas c".to_string(), new: vec!["Anas d".to_string()] },
+            TaxonomyChange { old: "Anas d".to_string(), new: vec!["Anas c".to_string()] },
+            TaxonomyChange { old: "Anas e".to_string(), new: vec!["Anas a".to_string()] },
+        ];
+
+        let report = apply_taxonomy_changes(&changes, &mut birds, &mut sightings);
+
+        // A chain, each species takes the name the one before it gave up
+        assert_eq!(birds.get("aaaa").unwrap().sname, "Anas b");
+        assert_eq!(birds.get("bbbb").unwrap().sname, "Anas x");
+        assert_eq!(birds.get("eeee").unwrap().sname, "Anas a");
+        // A swap
+        assert_eq!(birds.get("cccc").unwrap().sname, "Anas d");
+        assert_eq!(birds.get("dddd").unwrap().sname, "Anas c");
+        assert_eq!(report.species_renamed.len(), 5);
+        assert_eq!(report.species_lumped.len(), 0);
+        let snames: Vec<&str> = sightings.iter().map(|s| s.sname.as_str()).collect();
+        for (date, sname) in [(1, "Anas b"), (2, "Anas x"), (3, "Anas d"), (4, "Anas c"), (5, "Anas a")] {
+            assert_eq!(sightings.iter().find(|s| s.date == date).unwrap().sname, sname, "{:?}", snames);
+        }
+
+        // A lump keeps what only the old species had, the sightings of the other one are left alone
+        birds.get_mut("bbbb").unwrap().subspecies = vec!["x".to_string()];
+        for sighting in sightings.iter_mut() {
+            sighting.subspecies = if sighting.date == 5 { "a".to_string() } else { "b".to_string() };
+        }
+        let changes = vec![TaxonomyChange { old: "Anas a".to_string(), new: vec!["Anas x".to_string()] }];
+        let report = apply_taxonomy_changes(&changes, &mut birds, &mut sightings);
+        assert_eq!(report.species_lumped, vec![("eeee".to_string(), "Anas a".to_string(), "Anas x".to_string())]);
+        assert_eq!(birds.contains_key("eeee"), false);
+        assert_eq!(birds.get("bbbb").unwrap().notes, "Only in e");
+        assert_eq!(birds.get("bbbb").unwrap().subspecies, vec!["x".to_string(), "a".to_string()]);
+        assert_eq!(report.not_lumped.len(), 0);
+        assert_eq!(sightings.iter().filter(|s| s.sname == "Anas x").count(), 2);
+    }
+}
@@ -0,0 +1,216 @@
+/*
+        Asking before anything is thrown away, and the flags that go with it.
+
+            --yes (-y)      answer yes to every question, for scripts
+            --dry-run       do the command, show what would change (the first records, each with
+                            what it was and what it would become), but save nothing
+            --              the end of the flags, what comes after it is never taken as one
+                            (a comment of "-y" in  oa ... -- "-y")
+
+        2026.09.28   Sven Ponelat
+*/
+
+use crate::library::my_file_funcs::*;
+use crate::library::journal::*;
+use serde::Serialize;
+use termion::{color, style};
+use std::collections::BTreeMap;
+use std::io::{stdin, stdout, Write};
+
+
+pub const YES_FLAGS:     [&str; 2] = ["--yes", "-y"];
+pub const DRY_RUN_FLAG:  &str      = "--dry-run";
+pub const END_OF_FLAGS:  &str      = "--";
+pub const DRY_RUN_SHOWN: usize     = 10;
+
+
+// Function to find where the flags stop, at the first -- or else at the end of the arguments
+pub fn flags_end(arguments: &Vec<String>) -> usize {
+    match arguments.iter().position(|arg| arg.trim() == END_OF_FLAGS) {
+        Some(index) => { index }
+        None        => { arguments.len() }
+    }
+}
+
+
+// Function to take a flag out of the arguments anywhere before the first --, returns true if it was there
+pub fn take_flag(arguments: &mut Vec<String>, flags: &[&str]) -> bool {
+    let end = flags_end(arguments);
+    let before = arguments.len();
+    let mut index = 0;
+    arguments.retain(|arg| {
+        index += 1;
+        index > end || !flags.contains(&arg.trim())
+    });
+    arguments.len() != before
+}
+
+
+// Function to take the -- out of the arguments, once all the flags have been taken
+pub fn take_end_of_flags(arguments: &mut Vec<String>) {
+    let end = flags_end(arguments);
+    if end < arguments.len() {
+        arguments.remove(end);
+    }
+}
+
+
+// Function to ask a yes/no question, anything but y or yes (or no input at all) is a no
+pub fn confirm(question: &str, assume_yes: bool) -> bool {
+    if assume_yes {
+        return true
+    }
+    print!("{}{}{} [y/N] ", color::Fg(color::Yellow), question, style::Reset);
+    let _ = stdout().flush();
+
+    let mut answer = String::new();
+    if stdin().read_line(&mut answer).is_err() {
+        return false
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+
+// Function to stop when the user said no
+pub fn cancelled() -> ! {
+    let message = format!("Nothing was changed.");
+    feedback(Feedback::Info, message);
+    std::process::exit(17);
+}
+
+
+// Function to tell the user what a dry run would have changed, the first records one by one
+pub fn show_dry_run(entry: Option<JournalEntry>) {
+    match entry {
+        Some(entry) => {
+            let message = format!("Dry run, nothing was saved. This would have changed: {}", entry.summary());
+            feedback(Feedback::Info, message);
+
+            let mut lines = describe_changes(&entry.species_before, &entry.species_after,
+                                                |s| (s.code.clone(), format!("species {} ({})", s.code, s.name)));
+            lines.extend(describe_changes(&entry.sightings_before, &entry.sightings_after,
+                                                |s| (s.id.clone(), format!("sighting {} ({})", s.id, s.sname))));
+            lines.extend(describe_changes(&entry.locations_before, &entry.locations_after,
+                                                |p| (p.id.to_string(), format!("location {} ({})", p.id, p.describe()))));
+            if entry.changes_lists() {
+                lines.push(format!("~ lists   \"{}\" -> \"{}\"", entry.lists_before, entry.lists_after));
+            }
+            for line in lines.iter().take(DRY_RUN_SHOWN) {
+                println!("   {}", line);
+            }
+            if lines.len() > DRY_RUN_SHOWN {
+                println!("   ... and {} more, {} in all", lines.len() - DRY_RUN_SHOWN, lines.len());
+            }
+        }
+        None => {
+            let message = format!("Dry run, nothing was saved. Nothing would have changed.");
+            feedback(Feedback::Info, message);
+        }
+    }
+}
+
+
+// Function to describe what happened to each record, "+" added, "-" deleted and "~" changed with
+// every field that changed as "field: before -> after". The key says which record is which.
+fn describe_changes<T: Serialize>(before: &Vec<T>, after: &Vec<T>, key: impl Fn(&T) -> (String, String)) -> Vec<String> {
+    let mut ret: Vec<String> = Vec::new();
+    let old: BTreeMap<String, &T> = before.iter().map(|record| (key(record).0, record)).collect();
+    let new: BTreeMap<String, &T> = after.iter().map(|record| (key(record).0, record)).collect();
+
+    for record in before {
+        let (id, label) = key(record);
+        match new.get(&id) {
+            Some(changed) => { ret.push(format!("~ {}   {}", label, changed_fields(record, changed).join(", "))) }
+            None          => { ret.push(format!("- {}", label)) }
+        }
+    }
+    for record in after {
+        let (id, label) = key(record);
+        if !old.contains_key(&id) {
+            ret.push(format!("+ {}", label));
+        }
+    }
+    ret
+}
+
+
+// Function to list the fields that differ between two versions of a record
+fn changed_fields<T: Serialize>(before: &T, after: &T) -> Vec<String> {
+    let old = serde_json::to_value(before).unwrap_or_default();
+    let new = serde_json::to_value(after).unwrap_or_default();
+    let mut ret: Vec<String> = Vec::new();
+    if let (Some(old), Some(new)) = (old.as_object(), new.as_object()) {
+        for (field, value) in new {
+            let was = old.get(field).cloned().unwrap_or_default();
+            if &was != value {
+                ret.push(format!("{}: {} -> {}", field, was, value));
+            }
+        }
+    }
+    ret
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[ignore]
+    #[test]
+    fn t001_take_flag() {
+        let mut arguments: Vec<String> = ["mybirding", "bd", "-y", "bisor", "--dry-run"].iter().map(|s| s.to_string()).collect();
+        let yes = take_flag(&mut arguments, &YES_FLAGS);
+        let dry = take_flag(&mut arguments, &[DRY_RUN_FLAG]);
+        let again = take_flag(&mut arguments, &YES_FLAGS);
+
+        assert_eq!(yes, true);
+        assert_eq!(dry, true);
+        assert_eq!(again, false);
+        assert_eq!(arguments, ["mybirding", "bd", "bisor"]);
+        assert_eq!(confirm("Sure?", true), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_end_of_flags() {
+        let mut arguments: Vec<String> = ["mybirding", "-y", "oa", "--", "-y", "--"].iter().map(|s| s.to_string()).collect();
+        let yes = take_flag(&mut arguments, &YES_FLAGS);
+        let again = take_flag(&mut arguments, &YES_FLAGS);
+        take_end_of_flags(&mut arguments);
+
+        assert_eq!(yes, true);
+        assert_eq!(again, false);
+        assert_eq!(arguments, ["mybirding", "oa", "-y", "--"]);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_describe_changes() {
+        use crate::library::bird_species::*;
+        let mut kept = Species::new();
+        kept.code = "bisor".to_string();
+        kept.name = "Southern Red Bishop".to_string();
+        let mut gone = kept.clone();
+        gone.code = "spho".to_string();
+        let mut edited = kept.clone();
+        edited.name = "Red Bishop".to_string();
+        let mut added = kept.clone();
+        added.code = "new".to_string();
+
+        let lines = describe_changes(&vec![kept, gone], &vec![edited, added], |s| (s.code.clone(), s.code.clone()));
+
+        assert_eq!(lines, ["~ bisor   name: \"Southern Red Bishop\" -> \"Red Bishop\"", "- spho", "+ new"]);
+    }
+}
@@ -0,0 +1,285 @@
+/*
+        This is the module that decides where all the data files live.
+
+        The data directory (species, sightings, exports) is found in this order:
+            1.  --data-dir <path> given on the command line
+            2.  the MYBIRDING_DATA_DIR environment variable
+            3.  $XDG_DATA_HOME/mybirding      or   ~/.local/share/mybirding
+
+        The options file lives in $XDG_CONFIG_HOME/mybirding (or ~/.config/mybirding),
+        unless the data directory was overridden, then everything lives together.
+
+        2026.09.14   Sven Ponelat
+*/
+
+use crate::library::my_file_funcs::*;
+use crate::library::confirm::*;
+use std::env;
+use std::fs::{copy, create_dir_all, remove_file, rename};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+
+pub const DATA_DIR_ENV:   &str = "MYBIRDING_DATA_DIR";
+pub const DATA_DIR_FLAG:  &str = "--data-dir";
+pub const APP_DIR_NAME:   &str = "mybirding";
+
+// Files that used to be kept in the current working directory
+pub const OLD_CWD_FILES: [&str; 3] = ["species.bin", "sightings.bin", "options.json"];
+
+// Set once from the command line flag, it beats everything else
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+
+// Function to set the data directory given with the --data-dir flag
+pub fn set_data_dir_override(dir: &str) -> Result<(), String> {
+    let trimmed = dir.trim();
+    if trimmed.len() == 0 {
+        return Err("The data directory given is empty".to_string());
+    }
+    if DATA_DIR_OVERRIDE.set(PathBuf::from(trimmed)).is_err() {
+        return Err("The data directory has already been set".to_string());
+    }
+    Ok(())
+}
+
+
+// Function to return a directory from an environment variable, if it is set and not empty
+fn dir_from_env(key: &str) -> Option<PathBuf> {
+    match env::var(key) {
+        Ok(value) => {
+            if value.trim().len() == 0 {
+                return None
+            }
+            Some(PathBuf::from(value.trim()))
+        }
+        Err(_) => { None }
+    }
+}
+
+
+// Function to return the home directory, or the current one if there is no home
+fn home_dir() -> PathBuf {
+    match dir_from_env("HOME") {
+        Some(home) => { home }
+        None       => { PathBuf::from(".") }
+    }
+}
+
+
+// Function to see if the user has overridden the data directory (flag first, then environment)
+fn overridden_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Some(dir.clone())
+    }
+    dir_from_env(DATA_DIR_ENV)
+}
+
+
+// Function to get the directory where the species, sightings and exports are kept
+pub fn get_data_dir() -> PathBuf {
+    if let Some(dir) = overridden_dir() {
+        return dir
+    }
+    if let Some(dir) = dir_from_env("XDG_DATA_HOME") {
+        return dir.join(APP_DIR_NAME)
+    }
+    home_dir().join(".local").join("share").join(APP_DIR_NAME)
+}
+
+
+// Function to get the directory where the options file is kept
+pub fn get_config_dir() -> PathBuf {
+    if let Some(dir) = overridden_dir() {
+        return dir
+    }
+    if let Some(dir) = dir_from_env("XDG_CONFIG_HOME") {
+        return dir.join(APP_DIR_NAME)
+    }
+    home_dir().join(".config").join(APP_DIR_NAME)
+}
+
+
+// Function to return the full path of a file in the data directory
+pub fn data_file(file_name: &str) -> String {
+    get_data_dir().join(file_name).to_string_lossy().to_string()
+}
+
+
+// Function to return the full path of a file in the config directory
+pub fn config_file(file_name: &str) -> String {
+    get_config_dir().join(file_name).to_string_lossy().to_string()
+}
+
+
+// Function to make sure the data and config directories are there
+pub fn make_data_dirs() -> Result<(), String> {
+    let data = get_data_dir();
+    if create_dir_all(&data).is_err() {
+        let message = format!("Cannot create the data directory -> {}", data.display());
+        return Err(message);
+    }
+
+    let config = get_config_dir();
+    if create_dir_all(&config).is_err() {
+        let message = format!("Cannot create the config directory -> {}", config.display());
+        return Err(message);
+    }
+    Ok(())
+}
+
+
+// Function that takes the --data-dir flag (and its value) out of the arguments.
+// Both "--data-dir /some/where" and "--data-dir=/some/where" are accepted, only before the first --.
+pub fn take_data_dir_flag(arguments: &mut Vec<String>) -> Result<Option<String>, String> {
+    let mut ret: Option<String> = None;
+    let mut index = 0;
+
+    while index < flags_end(arguments) {
+        let arg = arguments[index].trim().to_string();
+
+        if arg == DATA_DIR_FLAG {
+            if index + 1 >= flags_end(arguments) {
+                return Err("The --data-dir flag needs a directory after it".to_string());
+            }
+            ret = Some(arguments[index + 1].trim().to_string());
+            arguments.remove(index + 1);
+            arguments.remove(index);
+            continue;
+        }
+
+        let prefix = format!("{}=", DATA_DIR_FLAG);
+        if arg.starts_with(&prefix) {
+            ret = Some(arg[prefix.len()..].to_string());
+            arguments.remove(index);
+            continue;
+        }
+        index += 1;
+    }
+
+    Ok(ret)
+}
+
+
+// Function to move one old file, rename first and if that fails (other disk) copy and delete
+fn move_file(from: &Path, to: &Path) -> Result<(), String> {
+    if rename(from, to).is_ok() {
+        return Ok(())
+    }
+    if copy(from, to).is_err() {
+        let message = format!("Could not copy {} to {}", from.display(), to.display());
+        return Err(message);
+    }
+    if remove_file(from).is_err() {
+        let message = format!("Copied but could not remove the old file {}", from.display());
+        return Err(message);
+    }
+    Ok(())
+}
+
+
+// One-time migration of the files that used to live in the current working directory.
+// A file is only moved if it is not already in the data (or config) directory.
+pub fn migrate_cwd_files() {
+    let cwd = match env::current_dir() {
+        Ok(dir) => { dir }
+        Err(_)  => { return }
+    };
+
+    for name in OLD_CWD_FILES {
+        let from = cwd.join(name);
+        if !from.is_file() {
+            continue;
+        }
+
+        let to = match name {
+            "options.json" => { PathBuf::from(config_file(name)) }
+            _              => { PathBuf::from(data_file(name)) }
+        };
+
+        // Running from inside the data directory, or already migrated
+        if same_file(&from, &to) || to.exists() {
+            continue;
+        }
+
+        match move_file(&from, &to) {
+            Ok(_) => {
+                let message = format!("Moved {} into {}", from.display(), to.display());
+                feedback(Feedback::Info, message);
+            }
+            Err(e) => {
+                feedback(Feedback::Warning, e);
+            }
+        }
+    }
+}
+
+
+// Function to see if two paths point to the same file
+fn same_file(one: &Path, two: &Path) -> bool {
+    match (one.canonicalize(), two.canonicalize()) {
+        (Ok(a), Ok(b)) => { a == b }
+        _              => { false }
+    }
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[ignore]
+    #[test]
+    fn t001_take_data_dir_flag() {
+        let mut args: Vec<String> = vec!["mybirding", "--data-dir", "/tmp/birds", "b", "spho"]
+                                        .iter().map(|s| s.to_string()).collect();
+        let res = take_data_dir_flag(&mut args).unwrap();
+
+        assert_eq!(res.unwrap(), "/tmp/birds");
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[1], "b");
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_take_data_dir_flag2() {
+        let mut args: Vec<String> = vec!["mybirding", "o", "--data-dir=/tmp/birds"]
+                                        .iter().map(|s| s.to_string()).collect();
+        let res = take_data_dir_flag(&mut args).unwrap();
+        assert_eq!(res.unwrap(), "/tmp/birds");
+        assert_eq!(args.len(), 2);
+
+        let mut args: Vec<String> = vec!["mybirding", "o", "--data-dir"]
+                                        .iter().map(|s| s.to_string()).collect();
+        assert_eq!(take_data_dir_flag(&mut args).is_err(), true);
+
+        let mut args: Vec<String> = vec!["mybirding", "oa", "--", "--data-dir=/tmp/birds"]
+                                        .iter().map(|s| s.to_string()).collect();
+        assert_eq!(take_data_dir_flag(&mut args).unwrap().is_none(), true);
+        assert_eq!(args.len(), 4);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_env_data_dir() {
+        env::set_var(DATA_DIR_ENV, "./test/data_dir");
+        let dir = get_data_dir();
+        let options = config_file("options.json");
+        env::remove_var(DATA_DIR_ENV);
+
+        assert_eq!(dir, PathBuf::from("./test/data_dir"));
+        assert_eq!(options, "./test/data_dir/options.json");
+    }
+}
@@ -50,6 +50,11 @@ pub fn show_help(options: SettingsText){
     print!("go back one bird species from the last viewed species");
     print!("{}\n",style::Reset);
     
+    print!("bb\tf" );
+    print!("\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("go back to the previous family (set speciesSortOrder to taxonomic in options to walk as in a field guide)");
+    print!("{}\n",style::Reset);
+    
     print!("bd\tcode or index" );
     print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
     print!("delete a bird from species database, also deletes all sightings with that bird species (e.g.)");
@@ -60,9 +65,29 @@ pub fn show_help(options: SettingsText){
     print!("deletes the house sparrow");
     print!("{}\n",style::Reset);
     
-    print!("be\tspho\t\"u=Rare#l=Europe\"" );
+    print!("be\tspho\t\"u=LC#l=Europe\"" );
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("edits the house sparrow field of status (LC, NT, VU, EN, CR, EW, EX, DD, NE or a regional one) and list");
+    print!("{}\n",style::Reset);
+    
+    print!("be\tduye\t\"v=undulata,ruppelli\"" );
     print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
-    print!("edits the house sparrow field of status and list");
+    print!("the subspecies a sighting of the yellow-billed duck may have (v= clears the list)");
+    print!("{}\n",style::Reset);
+    
+    print!("be\tduye\t\"t=af:Geelbekeend,de:Gelbschnabelente\"" );
+    print!("\t\t\t\t{}",color::Fg(my_gray));
+    print!("names in other languages (de: takes one off); preferredLanguage in options picks the one shown");
+    print!("{}\n",style::Reset);
+    
+    print!("be\tduye\t\"i=Yellow bill, black saddle\\nOn dams and pans#x=ma,duafb\"" );
+    print!("\t\t{}",color::Fg(my_gray));
+    print!("own notes (\\n starts a new line) and the species it is confused with (x= clears them)");
+    print!("{}\n",style::Reset);
+    
+    print!("be\tspho\t\"k=introduced\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("the category: species, group, hybrid, spuh, slash, domestic, escapee or introduced");
     print!("{}\n",style::Reset);
     
     print!("bex" );
@@ -80,11 +105,46 @@ pub fn show_help(options: SettingsText){
     print!("go forward one bird species from the last viewed species");
     print!("{}\n",style::Reset);
     
+    print!("bf\tf" );
+    print!("\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("go forward to the next family");
+    print!("{}\n",style::Reset);
+    
     print!("bim\t/Home/mine/bird_file.csv" );
     print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
     print!("import a bird file either .json or .csv that will replace all birds");
     print!("{}\n",style::Reset);
     
+    print!("bim\t/Home/mine/bird_file.csv merge" );
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("update the birds from a newer list, matched on sname, keeping the codes and all sightings");
+    print!("{}\n",style::Reset);
+    
+    print!("btu\t/Home/mine/ioc_changes.csv" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("update scientific names from a file of old,new lines; splits are listed, all is kept in taxonomy_audit.tsv");
+    print!("{}\n",style::Reset);
+    
+    print!("bim\t/Home/mine/ioc_master.csv ioc" );
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("replace all birds with a downloaded checklist: ioc, ebird or clements (the species rows only)");
+    print!("{}\n",style::Reset);
+    
+    print!("bim\t/Home/mine/eBird_Taxonomy.csv ebird merge" );
+    print!("\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("update the birds from a checklist, which also sets the taxonomic sequence");
+    print!("{}\n",style::Reset);
+    
+    print!("bim\t/Home/mine/ioc_multilingual.csv names af,de" );
+    print!("\t\t\t\t{}",color::Fg(my_gray));
+    print!("put names in other languages from a multilingual list on our species (all languages if none given)");
+    print!("{}\n",style::Reset);
+    
+    print!("bm\tduye1 duye" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("merge the first species into the second (code or index): its sightings move over and it is deleted");
+    print!("{}\n",style::Reset);
+    
     print!("");
     print!("Help ================================================================================================================================================");
     print!("\n");
@@ -114,6 +174,11 @@ pub fn show_help(options: SettingsText){
     print!("view bird sighting at position 909");
     print!("{}\n",style::Reset);
     
+    print!("o\t01M577J1PSEWKNY1Q9D01YPXM4 ");
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("view bird sighting with that id (o, oe and od take an id wherever they take a position)");
+    print!("{}\n",style::Reset);
+    
     print!("oa\t\"sha#d=29#c=duye#a=Home#w=London#p=WA1#t=UK\"");
     print!("\t\t\t\t{}",color::Fg(my_gray));
     print!("adding a sighting ['sha' are the codes for seen heard adult] (another example is)");
@@ -124,11 +189,26 @@ pub fn show_help(options: SettingsText){
     print!("adding a sighting [0 is the shorcut for the last location]");
     print!("{}\n",style::Reset);
     
+    print!("oa\t\"s#c=gaca#n=42 3m 1f\"");
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("adding a sighting of 42 birds, 3 males and 1 female among them [also a for adults, i immatures, c chicks]");
+    print!("{}\n",style::Reset);
+    
     print!("ob");
     print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
     print!("go back one bird sighting from the last viewed sighting");
     print!("{}\n",style::Reset);
     
+    print!("oc");
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("add up the counts per species and site: the sightings counted, the maximum and the total");
+    print!("{}\n",style::Reset);
+    
+    print!("oc\t\"c=gaca#t=south africa\"");
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("the same, only for the sightings that match the search terms (as with so)");
+    print!("{}\n",style::Reset);
+    
     print!("od\t1");
     print!("\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
     print!("delete the first sighting from the database");
@@ -139,6 +219,26 @@ pub fn show_help(options: SettingsText){
     print!("change the date on the first sighting");
     print!("{}\n",style::Reset);
     
+    print!("oe\t1\t\"d=2019.01.25 06:45 +02:00\"");
+    print!("\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("the date with the local time and time zone (both optional, d= with only a day clears them)");
+    print!("{}\n",style::Reset);
+    
+    print!("oe\t1\t\"d=1987.05\"");
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("only the year and month are known (or only the year: d=1974), as with an old notebook");
+    print!("{}\n",style::Reset);
+    
+    print!("oe\t1\t\"g=-26.1234,28.0,10m\"");
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("the latitude and longitude where it was seen, with how accurate they are (optional, g= clears them)");
+    print!("{}\n",style::Reset);
+    
+    print!("oe\t1\t\"v=ruppelli\"");
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("give the first sighting a subspecies (it still counts for the species)");
+    print!("{}\n",style::Reset);
+    
     print!("oex\t/Home/mine/sightings_file.csv");
     print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
     print!("exports sightings data file to a csv format");
@@ -164,6 +264,16 @@ pub fn show_help(options: SettingsText){
     print!("import a bird sightings file that is .json formatted and that will replace all sightings");
     print!("{}\n",style::Reset);
     
+    print!("oim\t/Home/mine/their_file.csv merge" );
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("add their sightings (csv or json) to yours, skipping duplicates and keeping yours when they differ");
+    print!("{}\n",style::Reset);
+    
+    print!("oim\t/Home/mine/their_file.csv merge theirs" );
+    print!("\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("the same, but their sighting replaces yours when the same sighting differs");
+    print!("{}\n",style::Reset);
+    
     print!("oz" );
     print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
     print!("shows last 10 locations with the last sighting in the database");
@@ -179,9 +289,170 @@ pub fn show_help(options: SettingsText){
     print!("list all sightings of birds that were adults and seen and ringed");
     print!("{}\n",style::Reset);
     
+    print!("so\t\"d=1980-1990#c=duye\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the sightings between two dates (or years, months), a sighting of May 1987 needs all of May in the range");
+    print!("{}\n",style::Reset);
+    
+    print!("so\t\"g=-26.1,28.0,5km\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the sightings with coordinates within 5 km of that position (or 500m)");
+    print!("{}\n",style::Reset);
+    
+    print!("so\t\"c=duye#v=ruppelli\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the sightings of a subspecies");
+    print!("{}\n",style::Reset);
+    
+    print!("so\t\"u=threatened#t=kenya\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the sightings of species with a status of VU, EN or CR (or u=EN for one status)");
+    print!("{}\n",style::Reset);
+    
+    print!("so\t\"i=reeds#x=duye\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the sightings of species whose notes have reeds and that are confused with duye");
+    print!("{}\n",style::Reset);
     
+    print!("");
+    print!("{}",color::Fg(my_geen));
+    print!("Lists ===============================================================================================================================================");
+    print!("{}\n",style::Reset);
     
+    print!("l" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("shows all the lists, with how many of their species have been seen");
+    print!("{}\n",style::Reset);
     
+    print!("l\t\"UK BOU\"" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("shows which species of the list have been seen (what counts is countedCategories and deadBirdIsSighting in options, only for the lists)");
+    print!("{}\n",style::Reset);
+    
+    print!("lc\t\"UK BOU\"" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("make a new list");
+    print!("{}\n",style::Reset);
+    
+    print!("ld\t\"UK BOU\"" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("delete a list, it is taken off all its species (the species stay)");
+    print!("{}\n",style::Reset);
+    
+    print!("la\tGarden /Home/mine/garden.txt" );
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("put the species in a file on a list, one per line by code, scientific name or name");
+    print!("{}\n",style::Reset);
+    
+    print!("la\tKenya \"t=kenya\"" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("put the species of the sightings a search finds on a list");
+    print!("{}\n",style::Reset);
+    
+    print!("lr\tGarden \"m=anatidae\"" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("take species off a list, from a file or a search as with la");
+    print!("{}\n",style::Reset);
+    
+    print!("be\tspho\t\"l=Garden,UK BOU\"" );
+    print!("\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("set the lists of the house sparrow");
+    print!("{}\n",style::Reset);
+    
+    
+    print!("");
+    print!("{}",color::Fg(my_geen));
+    print!("Locations ===========================================================================================================================================");
+    print!("{}\n",style::Reset);
+    
+    print!("lo" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the locations by country, province and town, with their ids, sightings, coordinates and aliases");
+    print!("{}\n",style::Reset);
+    
+    print!("lo\tjohannesburg" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("only the locations with this in their names or aliases");
+    print!("{}\n",style::Reset);
+    
+    print!("loe\t12\t\"a=Delta Park#k=Delta,Delta park\"" );
+    print!("\t\t\t\t{}",color::Fg(my_gray));
+    print!("rename a location (by id or name), the old name is kept as an alias and its sightings follow");
+    print!("{}\n",style::Reset);
+    
+    print!("loe\t12\t\"w=Randburg#g=-26.13,28.01,50m\"" );
+    print!("\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("change the town (p= province, t= country) or the coordinates of a location");
+    print!("{}\n",style::Reset);
+    
+    print!("lom\t14\t12" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("merge location 14 into 12, its sightings move and its names become aliases");
+    print!("{}\n",style::Reset);
+    
+    
+    
+    
+    
+    
+    print!("");
+    print!("Data files ==========================================================================================================================================");
+    print!("\n");
+    
+    print!("undo" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("reverse the last change to the species or sightings (ba, be, bd, oa, oe, od, lc, ld ..., not an import of more than 2 000 records)");
+    print!("{}\n",style::Reset);
+    
+    print!("redo" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("do the last undone change again, until something else is changed");
+    print!("{}\n",style::Reset);
+    
+    print!("history" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the changes that can be undone (how many are kept is numberOfUndos in options)");
+    print!("{}\n",style::Reset);
+    
+    print!("restore" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("list the backups of the species and sightings files (how many are kept is numberOfBackups in options)");
+    print!("{}\n",style::Reset);
+    
+    print!("restore\tnumber" );
+    print!("\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("roll back to the backup with that number, the current file is backed up first");
+    print!("{}\n",style::Reset);
+    
+    print!("repair\tspecies or sightings" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("salvage what can be read from a damaged file into species_repaired.bin or sightings_repaired.bin");
+    print!("{}\n",style::Reset);
+    
+    print!("migrate-store\tsqlite or bincode" );
+    print!("\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("move everything into a sqlite database (mybirding.db) or back into the bincode files");
+    print!("{}\n",style::Reset);
+    
+    print!("--data-dir\tpath" );
+    print!("\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("use the data files in this directory (also MYBIRDING_DATA_DIR), default ~/.local/share/mybirding");
+    print!("{}\n",style::Reset);
+    
+    print!("--yes\tor -y" );
+    print!("\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("do not ask before bd, od, bim and oim throw data away (for scripts)");
+    print!("{}\n",style::Reset);
+    
+    print!("--dry-run" );
+    print!("\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("do the command and show what would change, record by record for the first ones, but save nothing");
+    print!("{}\n",style::Reset);
+    
+    print!("--" );
+    print!("\t\t\t\t\t\t\t\t\t\t{}",color::Fg(my_gray));
+    print!("the end of the flags, nothing after it is taken as one (e.g. a comment of -y)");
+    print!("{}\n",style::Reset);
     
     print!("");
     print!("Version =============================================================================================================================================");
@@ -0,0 +1,500 @@
+/*
+        The journal of everything that changed the species, the sightings or the locations, so it can be undone.
+
+        Every command that changes the data leaves an entry in journal.json in the data directory.
+        An entry only holds what changed: the species (by code), the sightings and the locations
+        (by id) as they were before and as they are after the command, and the names of the lists made
+        with lc (the speciesLists option) when lc or ld changed them. Undo puts the before back, redo the after.
+        A command that changes more than JOURNAL_MAX_RECORDS records (a big import) is not kept, it would
+        put the whole data set in the journal. The journal is started afresh then, a backup can be restored instead.
+        The position is the number of entries that are in effect, a new change throws away
+        everything that was undone. Only the newest "numberOfUndos" entries are kept.
+
+        2026.09.25   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::*;
+use crate::library::bird_locations::*;
+use crate::library::data_dir::*;
+use crate::library::backups::*;
+use crate::library::settings::*;
+use serde::{Serialize, Deserialize};
+use termion::{color, style};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+
+pub const JOURNAL_FILENAME: &str = "journal.json";
+pub const JOURNAL_MAX_RECORDS: usize = 2000;
+
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub when: String,
+    pub command: String,
+    pub species_before: Vec<Species>,
+    pub species_after: Vec<Species>,
+    pub sightings_before: Vec<Sightings>,
+    pub sightings_after: Vec<Sightings>,
+    #[serde(default)]
+    pub locations_before: Vec<Place>,
+    #[serde(default)]
+    pub locations_after: Vec<Place>,
+    #[serde(default)]
+    pub lists_before: String,
+    #[serde(default)]
+    pub lists_after: String,
+}
+
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    pub position: usize,
+    pub entries: Vec<JournalEntry>,
+}
+
+
+// Which way an entry is applied
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Undo,
+    Redo,
+}
+
+
+impl JournalEntry {
+
+    // Function to make an entry from the data before and after a command, None if nothing changed
+    pub fn from_diff(command: &str, birds_before: &BTreeMap<String, Species>, birds: &BTreeMap<String, Species>,
+                        sightings_before: &Vec<Sightings>, sightings: &Vec<Sightings>,
+                        places_before: &BTreeMap<u32, Place>, places: &BTreeMap<u32, Place>,
+                        lists_before: &str, lists: &str) -> Option<JournalEntry> {
+        let mut entry = JournalEntry::default();
+        entry.when = chrono::offset::Local::now().format("%Y.%m.%d %H:%M:%S").to_string();
+        entry.command = command.to_string();
+
+        // Species, by code
+        for (code, old) in birds_before {
+            if birds.get(code) != Some(old) {
+                entry.species_before.push(old.clone());
+            }
+        }
+        for (code, new) in birds {
+            if birds_before.get(code) != Some(new) {
+                entry.species_after.push(new.clone());
+            }
+        }
+
+        // Sightings, by id
+        let old_ids = index_by_id(sightings_before);
+        let new_ids = index_by_id(sightings);
+        for old in sightings_before {
+            if new_ids.get(&old.id) != Some(&old) {
+                entry.sightings_before.push(old.clone());
+            }
+        }
+        for new in sightings {
+            if old_ids.get(&new.id) != Some(&new) {
+                entry.sightings_after.push(new.clone());
+            }
+        }
+
+        // Locations, by id
+        for (id, old) in places_before {
+            if places.get(id) != Some(old) {
+                entry.locations_before.push(old.clone());
+            }
+        }
+        for (id, new) in places {
+            if places_before.get(id) != Some(new) {
+                entry.locations_after.push(new.clone());
+            }
+        }
+
+        // The names of the lists made with lc
+        entry.lists_before = lists_before.to_string();
+        entry.lists_after = lists.to_string();
+
+        if entry.is_empty() {
+            return None
+        }
+        Some(entry)
+    }
+
+
+    // Function to tell if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.species_before.is_empty() && self.species_after.is_empty()
+            && self.sightings_before.is_empty() && self.sightings_after.is_empty()
+            && self.locations_before.is_empty() && self.locations_after.is_empty()
+            && !self.changes_lists()
+    }
+
+
+    // Function to count the records the entry holds
+    pub fn records(&self) -> usize {
+        self.species_before.len() + self.species_after.len()
+            + self.sightings_before.len() + self.sightings_after.len()
+            + self.locations_before.len() + self.locations_after.len()
+    }
+
+
+    // Function to tell if the entry is too big to keep in the journal
+    pub fn is_too_big(&self) -> bool {
+        self.records() > JOURNAL_MAX_RECORDS
+    }
+
+
+    // Function to tell if the entry touches the species, the sightings or the locations
+    pub fn changes_species(&self) -> bool {
+        !self.species_before.is_empty() || !self.species_after.is_empty()
+    }
+
+    pub fn changes_sightings(&self) -> bool {
+        !self.sightings_before.is_empty() || !self.sightings_after.is_empty()
+    }
+
+    pub fn changes_locations(&self) -> bool {
+        !self.locations_before.is_empty() || !self.locations_after.is_empty()
+    }
+
+    pub fn changes_lists(&self) -> bool {
+        self.lists_before != self.lists_after
+    }
+
+
+    // Function to apply the entry to the data. What is taken away has to be there exactly as the
+    // journal remembers it, otherwise the data was changed some other way (restore, a copied file)
+    // and nothing is touched.
+    pub fn apply(&self, direction: Direction, birds: &mut BTreeMap<String, Species>,
+                    sightings: &mut Vec<Sightings>, places: &mut BTreeMap<u32, Place>, lists: &mut String) -> Result<(), String> {
+        let (species_out, species_in, sightings_out, sightings_in) = match direction {
+            Direction::Undo => { (&self.species_after, &self.species_before, &self.sightings_after, &self.sightings_before) }
+            Direction::Redo => { (&self.species_before, &self.species_after, &self.sightings_before, &self.sightings_after) }
+        };
+        let (locations_out, locations_in) = match direction {
+            Direction::Undo => { (&self.locations_after, &self.locations_before) }
+            Direction::Redo => { (&self.locations_before, &self.locations_after) }
+        };
+        let (lists_out, lists_in) = match direction {
+            Direction::Undo => { (&self.lists_after, &self.lists_before) }
+            Direction::Redo => { (&self.lists_before, &self.lists_after) }
+        };
+
+        for species in species_out {
+            if birds.get(&species.code) != Some(species) {
+                return Err(format!("The species {} ({}) is not as the journal remembers it, so \"{}\" cannot be reversed",
+                                    species.name, species.code, self.command));
+            }
+        }
+        let ids = index_by_id(sightings);
+        for sighting in sightings_out {
+            if ids.get(&sighting.id) != Some(&sighting) {
+                return Err(format!("The sighting {} is not as the journal remembers it, so \"{}\" cannot be reversed",
+                                    sighting.id, self.command));
+            }
+        }
+        for place in locations_out {
+            if places.get(&place.id) != Some(place) {
+                return Err(format!("The location {} ({}) is not as the journal remembers it, so \"{}\" cannot be reversed",
+                                    place.id, place.describe(), self.command));
+            }
+        }
+        if self.changes_lists() && lists != lists_out {
+            return Err(format!("The lists are not as the journal remembers them, so \"{}\" cannot be reversed", self.command));
+        }
+
+        for species in species_out {
+            birds.remove(&species.code);
+        }
+        for species in species_in {
+            birds.insert(species.code.clone(), species.clone());
+        }
+
+        let out: HashMap<&String, &Sightings> = sightings_out.iter().map(|s| (&s.id, s)).collect();
+        sightings.retain(|s| out.get(&s.id) != Some(&s));
+        sightings.extend(sightings_in.iter().cloned());
+        sightings.sort();
+
+        for place in locations_out {
+            places.remove(&place.id);
+        }
+        for place in locations_in {
+            places.insert(place.id, place.clone());
+        }
+
+        if self.changes_lists() {
+            *lists = lists_in.clone();
+        }
+
+        Ok(())
+    }
+
+
+    // Function to describe what the entry changed, e.g. "species +1 -0   sightings +0 -1"
+    // (and "   locations +1 -0" when it changed locations, "   lists" when it changed the lists made with lc)
+    pub fn summary(&self) -> String {
+        let mut ret = format!("species +{} -{}   sightings +{} -{}",
+                self.species_after.len(), self.species_before.len(),
+                self.sightings_after.len(), self.sightings_before.len());
+        if self.changes_locations() {
+            ret = format!("{}   locations +{} -{}", ret, self.locations_after.len(), self.locations_before.len());
+        }
+        if self.changes_lists() {
+            ret = format!("{}   lists", ret);
+        }
+        ret
+    }
+}
+
+
+impl Journal {
+
+    // Function to get the path of the journal file
+    pub fn path() -> String {
+        data_file(JOURNAL_FILENAME)
+    }
+
+
+    // Function to read the journal, a missing one is just empty
+    pub fn load(json_file: &str) -> Result<Journal, String> {
+        if !Path::new(json_file).exists() {
+            return Ok(Journal::default())
+        }
+        let text = match std::fs::read_to_string(json_file) {
+            Ok(text) => { text }
+            Err(_)   => { return Err(format!("Problem reading the journal -> {}", json_file)); }
+        };
+        let mut journal: Journal = match serde_json::from_str(&text) {
+            Ok(journal) => { journal }
+            Err(_)      => { return Err(format!("The journal is corrupt, move it out of the way to start a new one -> {}", json_file)); }
+        };
+        if journal.position > journal.entries.len() {
+            journal.position = journal.entries.len();
+        }
+        Ok(journal)
+    }
+
+
+    // Function to write the journal
+    pub fn save(&self, json_file: &str) -> Result<(), String> {
+        let text = match serde_json::to_string(self) {
+            Ok(text) => { text }
+            Err(_)   => { return Err("Problem writing the journal".to_string()); }
+        };
+        atomic_write(json_file, text.as_bytes())
+    }
+
+
+    // Function to add a new entry, whatever was undone cannot be redone after this
+    pub fn record(&mut self, entry: JournalEntry, keep: usize) {
+        self.entries.truncate(self.position);
+        self.entries.push(entry);
+        if self.entries.len() > keep {
+            let excess = self.entries.len() - keep;
+            self.entries.drain(..excess);
+        }
+        self.position = self.entries.len();
+    }
+
+
+    // Function to forget every entry, after a change that is not in the journal they cannot be trusted
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.position = 0;
+    }
+
+
+    // Function to reverse the last change that is in effect
+    pub fn undo(&mut self, birds: &mut BTreeMap<String, Species>, sightings: &mut Vec<Sightings>,
+                    places: &mut BTreeMap<u32, Place>, lists: &mut String) -> Result<JournalEntry, String> {
+        if self.position == 0 {
+            return Err("There is nothing to undo.".to_string())
+        }
+        let entry = self.entries[self.position - 1].clone();
+        let res = entry.apply(Direction::Undo, birds, sightings, places, lists);
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+        self.position -= 1;
+        Ok(entry)
+    }
+
+
+    // Function to do the last undone change again
+    pub fn redo(&mut self, birds: &mut BTreeMap<String, Species>, sightings: &mut Vec<Sightings>,
+                    places: &mut BTreeMap<u32, Place>, lists: &mut String) -> Result<JournalEntry, String> {
+        if self.position >= self.entries.len() {
+            return Err("There is nothing to redo.".to_string())
+        }
+        let entry = self.entries[self.position].clone();
+        let res = entry.apply(Direction::Redo, birds, sightings, places, lists);
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+        self.position += 1;
+        Ok(entry)
+    }
+}
+
+
+// Function to look up sightings by their id
+fn index_by_id(sightings: &Vec<Sightings>) -> HashMap<&String, &Sightings> {
+    sightings.iter().map(|s| (&s.id, s)).collect()
+}
+
+
+// Function to show the journal, oldest first. What was undone (and can be redone) is grayed out.
+pub fn display_history(options: &mut SettingsText, journal: &Journal) {
+    let my_normal_gray: color::Rgb = options.get_color("myNormalGray");
+    let my_dark_gray: color::Rgb = options.get_color("myDarkGray");
+    let my_light_blue: color::Rgb = options.get_color("myLightBlue");
+
+    println!("{}History (the grayed out ones were undone and can be redone){}", color::Fg(my_light_blue), style::Reset);
+    for (i, entry) in journal.entries.iter().enumerate() {
+        let colour = if i < journal.position { my_normal_gray } else { my_dark_gray };
+        println!("{:>4}  {}  {}{:<50}  {}{}", i + 1, entry.when, color::Fg(colour), entry.command, entry.summary(), style::Reset);
+    }
+    println!();
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn sighting(sname: &str, date: i64) -> Sightings {
+        let mut sighting = Sightings::new();
+        sighting.sname = sname.to_string();
+        sighting.date = date;
+        sighting
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t001_undo_redo() {
+        let mut bird = Species::new();
+        bird.code = "bisor".to_string();
+        bird.sname = "Euplectes orix".to_string();
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        birds.insert(bird.code.clone(), bird);
+        let mut sightings = vec![sighting("Euplectes orix", 1), sighting("Anas undulata", 2)];
+        let birds_before = birds.clone();
+        let sightings_before = sightings.clone();
+
+        // Delete the species and its sighting, edit the other one
+        birds.clear();
+        sightings.remove(0);
+        sightings[0].comments = "edited".to_string();
+
+        let places: BTreeMap<u32, Place> = BTreeMap::new();
+        let entry = JournalEntry::from_diff("bd bisor", &birds_before, &birds, &sightings_before, &sightings, &places, &places, "", "").unwrap();
+        let mut journal = Journal::default();
+        journal.record(entry, 5);
+        let after_birds = birds.clone();
+        let after_sightings = sightings.clone();
+
+        let mut p = places.clone();
+        let mut lists = String::new();
+        journal.undo(&mut birds, &mut sightings, &mut p, &mut lists).unwrap();
+        let undone = birds == birds_before && sightings == sightings_before;
+        let nothing = journal.undo(&mut birds, &mut sightings, &mut p, &mut lists).is_err();
+        journal.redo(&mut birds, &mut sightings, &mut p, &mut lists).unwrap();
+
+        assert_eq!(undone, true);
+        assert_eq!(nothing, true);
+        assert_eq!(birds, after_birds);
+        assert_eq!(sightings, after_sightings);
+        assert_eq!(journal.position, 1);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_refuses_changed_data() {
+        let birds: BTreeMap<String, Species> = BTreeMap::new();
+        let mut sightings = vec![sighting("Anas undulata", 2)];
+        let sightings_before = sightings.clone();
+        sightings.push(sighting("Euplectes orix", 3));
+
+        let places: BTreeMap<u32, Place> = BTreeMap::new();
+        let entry = JournalEntry::from_diff("oa", &birds, &birds, &sightings_before, &sightings, &places, &places, "", "").unwrap();
+        let mut journal = Journal::default();
+        journal.record(entry, 1);
+
+        // Somebody changed the new sighting behind the journal's back
+        sightings[1].comments = "changed".to_string();
+        let mut b = birds.clone();
+        let mut p = places.clone();
+        let mut lists = String::new();
+        let res = journal.undo(&mut b, &mut sightings, &mut p, &mut lists);
+
+        assert_eq!(res.is_err(), true);
+        assert_eq!(sightings.len(), 2);
+        assert_eq!(journal.position, 1);
+        assert_eq!(JournalEntry::from_diff("x", &birds, &birds, &sightings, &sightings, &places, &places, "", "").is_none(), true);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t003_undo_location_rename() {
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        let mut sightings = vec![sighting("Anas undulata", 2)];
+        sightings[0].location = "Delta".to_string();
+        sightings[0].town = "Johannesburg".to_string();
+        sightings[0].country = "South Africa".to_string();
+        let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+        register_sightings(&mut places, &mut sightings);
+        let sightings_before = sightings.clone();
+        let places_before = places.clone();
+
+        edit_place(sightings[0].location_id, "a=Delta Park", &mut places, &mut sightings).unwrap();
+        let entry = JournalEntry::from_diff("loe", &birds, &birds, &sightings_before, &sightings, &places_before, &places, "", "").unwrap();
+        let mut journal = Journal::default();
+        journal.record(entry.clone(), 5);
+        let mut lists = String::new();
+        journal.undo(&mut birds, &mut sightings, &mut places, &mut lists).unwrap();
+
+        assert_eq!(entry.changes_locations(), true);
+        assert_eq!(places, places_before);
+        assert_eq!(sightings, sightings_before);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t004_undo_list_delete() {
+        let mut birds: BTreeMap<String, Species> = BTreeMap::new();
+        let mut sightings: Vec<Sightings> = Vec::new();
+        let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+
+        // ld of a list no species is on only changes the names of the lists
+        let entry = JournalEntry::from_diff("ld Garden", &birds, &birds, &sightings, &sightings, &places, &places,
+                                            "Garden,UK BOU", "UK BOU").unwrap();
+        let mut journal = Journal::default();
+        journal.record(entry.clone(), 5);
+        let mut lists = "UK BOU".to_string();
+        journal.undo(&mut birds, &mut sightings, &mut places, &mut lists).unwrap();
+        let undone = lists.clone();
+        lists = "Other".to_string();
+        let refused = journal.redo(&mut birds, &mut sightings, &mut places, &mut lists).is_err();
+
+        assert_eq!(entry.changes_lists(), true);
+        assert_eq!(entry.records(), 0);
+        assert_eq!(undone, "Garden,UK BOU");
+        assert_eq!(refused, true);
+    }
+}
@@ -3,10 +3,24 @@ pub mod my_file_funcs;
 pub mod bird_species;
 pub mod bird_species_box;
 pub mod bird_species_support;
+pub mod bird_species_merge;
+pub mod bird_checklist;
+pub mod bird_taxonomy;
+pub mod bird_lists;
+pub mod bird_counts;
+pub mod bird_locations;
 pub mod bird_sightings;
 pub mod bird_sightings_box;
 pub mod bird_sightings_supp;
+pub mod bird_sightings_merge;
 pub mod help;
+pub mod data_dir;
+pub mod backups;
+pub mod bin_format;
+pub mod store;
+pub mod store_sqlite;
+pub mod journal;
+pub mod confirm;
 
 
 
@@ -17,14 +17,17 @@ use serde::{Serialize, Deserialize};
 use std::collections::BTreeMap;
 use draw_box::Style;
 use crate::library::my_file_funcs::*;
+use crate::library::data_dir::*;
+use crate::library::bird_species_support::SpeciesOrder;
+use crate::library::bird_species::{Species, ENGLISH};
 use std::time::{UNIX_EPOCH, Duration};
 
 
 
 
 #[allow(dead_code)]
-pub const OPTIONS_FILENAME: &str = "./options.json";
-pub const TEST_FILENAME: &str = "./test.txt";
+pub const OPTIONS_FILENAME: &str = "options.json";
+pub const TEST_FILENAME: &str = "test.txt";
 
 
 
@@ -90,11 +93,21 @@ impl   SettingsText {
     }
 
 
+    // The full path of the options file in the config directory
+    pub fn options_path() -> String {
+        config_file(OPTIONS_FILENAME)
+    }
+
+
     // implement a new BTreeMap
     pub fn new(options_json: &str) -> SettingsText {
 
-        // Check the file system can read and write
-        if SettingsText::file_system_ok(TEST_FILENAME).is_err(){
+        // Check the file system can read and write, where the options file is kept
+        let test_path = match Path::new(options_json).parent() {
+            Some(dir) => { dir.join(TEST_FILENAME) }
+            None      => { Path::new(TEST_FILENAME).to_path_buf() }
+        };
+        if SettingsText::file_system_ok(&test_path.to_string_lossy()).is_err(){
             let message = format!("Something wrong with writing files!");
             feedback(Feedback::Error, message);
             exit(1)
@@ -106,7 +119,9 @@ impl   SettingsText {
         // If it s not there, then make a default one
         let result = SettingsText::import(options_json);
         if result.is_ok() {
-            return result.unwrap()
+            let mut settings = result.unwrap();
+            settings.add_missing_keys();
+            return settings
         }
 
         else {
@@ -136,6 +151,12 @@ impl   SettingsText {
         // map.insert("replaceExistingSname".to_string(), Sdata { value: "true".to_string(), show: true });
         map.insert("showResponseTimes".to_string(), Sdata { value: "true".to_string(), show: true });
         map.insert("showSpeciesIndex".to_string(), Sdata { value: "true".to_string(), show: true });
+        map.insert("numberOfBackups".to_string(), Sdata { value: "5".to_string(), show: true });
+        map.insert("numberOfUndos".to_string(), Sdata { value: "20".to_string(), show: true });
+        map.insert("speciesSortOrder".to_string(), Sdata { value: "code".to_string(), show: true });
+        map.insert("speciesLists".to_string(), Sdata { value: "".to_string(), show: false });
+        map.insert("preferredLanguage".to_string(), Sdata { value: "en".to_string(), show: true });
+        map.insert("countedCategories".to_string(), Sdata { value: "species,introduced".to_string(), show: true });
         // map.insert("preferredDateSeparatorSymbol".to_string(), Sdata { value: ".".to_string(), show: true });
 
 
@@ -143,6 +164,19 @@ impl   SettingsText {
     }    
 
     
+    // Options files from older versions do not have the newer keys, give them the defaults
+    pub fn add_missing_keys(&mut self) {
+        let mut defaults = BTreeMap::new();
+        SettingsText::init_map(&mut defaults);
+
+        for (key, sdata) in defaults {
+            if !self.map.contains_key(&key) {
+                self.map.insert(key, sdata);
+            }
+        }
+    }
+
+
     // Reads the settings (options.json) file into a treemap, returning a result
     pub fn import(path: &str) -> Result<SettingsText, &str> {
         let str_file  = std::fs::read_to_string(path );
@@ -221,6 +255,24 @@ impl   SettingsText {
     }
 
 
+    // Get the order the species are walked in, if errored then code order
+    pub fn get_species_order(&self) -> Result<SpeciesOrder, String> {
+        match self.map.get("speciesSortOrder") {
+            Some(sdata) => { SpeciesOrder::from_str(&sdata.value) }
+            None        => { Ok(SpeciesOrder::Code) }
+        }
+    }
+
+
+    // Get the language the names are shown in, if errored then English
+    pub fn get_language(&self) -> Result<String, String> {
+        match self.map.get("preferredLanguage") {
+            Some(sdata) => { Species::validate_language(&sdata.value) }
+            None        => { Ok(ENGLISH.to_string()) }
+        }
+    }
+
+
     // Writes the settings to disk in local folder
     pub fn export( &self,  path: &str) -> Result<(), String> {
         let path = Path::new(path);
@@ -880,6 +932,21 @@ mod tests {                   //     DONT RUN THE TESTS ABOVE THIS LINE
     }
 
 
+    #[ignore]
+    #[test] 
+    fn t024_missing_keys_get_defaults() {
+
+        let source = "./test/store/settings/options_002.json";
+        let destination = "./test/options_005.json";
+        copy(source,destination).expect("Failed to copy");
+        let opt = SettingsText::new(destination);
+        remove_file(destination).expect("Cleanup test failed");
+
+        assert_eq!(opt.get_number("numberOfBackups"), 5);
+        assert_eq!(opt.map.get("myBlack").unwrap().value, "(0, 0, 0)");
+    }
+
+
 
 
 
@@ -0,0 +1,492 @@
+/*
+        Where the species, sightings and settings are kept.
+
+        The Store trait hides how the data is kept. There are two of them:
+            BinStore        species.bin, sightings.bin, locations.json and options.json (the original way)
+            SqliteStore     one mybirding.db SQLite file in the data directory
+
+        If there is a mybirding.db in the data directory it is used, otherwise the bin files.
+        Use the migrate-store command to go from one to the other.
+
+        What a command changed is saved one species or sighting at a time when the store can do that
+        (SQLite), the bin files are always written whole.
+
+        2026.09.21   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::*;
+use crate::library::bird_locations::*;
+use crate::library::bin_format::*;
+use crate::library::settings::*;
+use crate::library::store_sqlite::*;
+use crate::library::my_file_funcs::*;
+use crate::library::data_dir::*;
+use crate::library::journal::*;
+use crate::library::backups::*;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::rename;
+use std::path::Path;
+
+
+pub const MIGRATED_EXTENSION: &str = "migrated";
+
+
+pub trait Store {
+    // The name the user uses for this store (bincode or sqlite)
+    fn name(&self) -> &str;
+
+    // The files on disk that hold the species, the sightings and the locations (for backups)
+    fn species_file(&self) -> String;
+    fn sightings_file(&self) -> String;
+    fn locations_file(&self) -> String;
+
+    fn load_species(&self) -> Result<BTreeMap<String, Species>, LoadError>;
+    fn save_species(&mut self, birds: &BTreeMap<String, Species>) -> Result<(), String>;
+
+    fn load_sightings(&self) -> Result<Vec<Sightings>, LoadError>;
+    fn save_sightings(&mut self, sightings: &Vec<Sightings>) -> Result<(), String>;
+
+    fn load_locations(&self) -> Result<BTreeMap<u32, Place>, String>;
+    fn save_locations(&mut self, places: &BTreeMap<u32, Place>) -> Result<(), String>;
+
+    fn load_settings(&self) -> SettingsText;
+    fn save_settings(&mut self, options: &SettingsText) -> Result<(), String>;
+
+    // Single records, species by code and sightings by id. The changes only stick after commit.
+    fn upsert_species(&mut self, species: &Species) -> Result<(), String>;
+    fn delete_species(&mut self, code: &str) -> Result<(), String>;
+    fn upsert_sighting(&mut self, sighting: &Sightings) -> Result<(), String>;
+    fn delete_sighting(&mut self, id: &str) -> Result<(), String>;
+    fn commit(&mut self) -> Result<(), String>;
+
+    // True if a single record can be written without rewriting a whole file
+    fn saves_by_record(&self) -> bool;
+
+    // True if the files are in an older format, so they should be written back in the new one
+    fn is_outdated(&self) -> bool;
+
+    // Copy one of its files into the backups before it is written
+    fn back_up(&mut self, file: &str, keep: usize) -> Result<(), String>;
+
+    // Let go of the files, so a backup can be put in their place
+    fn release(&mut self) -> Result<(), String>;
+
+    // Rename the files of this store to *.migrated, once everything lives in another store
+    fn retire(&mut self) -> Result<(), String>;
+}
+
+
+// Function to decide which store is used, depending on what is in the data directory
+pub fn open_store() -> Result<Box<dyn Store>, String> {
+    if Path::new(&SqliteStore::db_path()).exists() {
+        return open_store_by_name("sqlite")
+    }
+    open_store_by_name("bincode")
+}
+
+
+// Function to open the store with the given name
+pub fn open_store_by_name(name: &str) -> Result<Box<dyn Store>, String> {
+    match name.to_lowercase().trim() {
+        "bincode"|"bin" => { Ok(Box::new(BinStore::new())) }
+        "sqlite"        => {
+            let store = SqliteStore::open(&SqliteStore::db_path());
+            if store.is_err(){
+                return Err(store.err().unwrap())
+            }
+            Ok(Box::new(store.unwrap()))
+        }
+        _               => { Err(format!("Not a valid store -> {}   (either sqlite or bincode)", name)) }
+    }
+}
+
+
+// Function to copy everything from one store into another, the old store's files are then renamed
+// to *.migrated. A new database that did not make it is removed again, so it is not picked up next time.
+pub fn migrate_store(from: &mut Box<dyn Store>, to_name: &str, birds: &BTreeMap<String, Species>,
+                        sightings: &Vec<Sightings>, places: &BTreeMap<u32, Place>, options: &SettingsText) -> Result<Box<dyn Store>, String> {
+    let db_existed = Path::new(&SqliteStore::db_path()).exists();
+    let r_to = open_store_by_name(to_name);
+    if r_to.is_err(){
+        return Err(r_to.err().unwrap())
+    }
+    let mut to = r_to.unwrap();
+
+    if to.name() == from.name() {
+        return Err(format!("You are already using the {} store", from.name()));
+    }
+
+    let res = fill_new_store(&mut to, birds, sightings, places, options);
+    if res.is_err(){
+        if to.name() == "sqlite" && !db_existed {
+            let _ = std::fs::remove_file(SqliteStore::db_path());
+        }
+        return Err(res.err().unwrap())
+    }
+
+    let res = from.retire();
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    Ok(to)
+}
+
+
+// Function to write everything into a store that has to be empty
+fn fill_new_store(to: &mut Box<dyn Store>, birds: &BTreeMap<String, Species>,
+                    sightings: &Vec<Sightings>, places: &BTreeMap<u32, Place>, options: &SettingsText) -> Result<(), String> {
+    // Never write over data that is already there
+    let has_species = match to.load_species() {
+        Ok(old) => { old.len() > 0 }
+        Err(e)  => { e.is_damaged() }
+    };
+    let has_sightings = match to.load_sightings() {
+        Ok(old) => { old.len() > 0 }
+        Err(e)  => { e.is_damaged() }
+    };
+    if has_species || has_sightings {
+        return Err(format!("There is already data in the {} store ({}), move it out of the way first", to.name(), to.species_file()));
+    }
+
+    let res = to.save_species(birds);
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    let res = to.save_sightings(sightings);
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    let res = to.save_locations(places);
+    if res.is_err(){
+        return Err(res.err().unwrap())
+    }
+    to.save_settings(options)
+}
+
+
+// Function to write only the species and sightings a command changed, one record at a time.
+// The entry holds them as they were before and as they are after, what is only in before is gone.
+pub fn save_changes(store: &mut Box<dyn Store>, changes: &JournalEntry) -> Result<(), String> {
+    let kept_codes: HashSet<&String> = changes.species_after.iter().map(|s| &s.code).collect();
+    for species in &changes.species_after {
+        let res = store.upsert_species(species);
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+    }
+    for species in &changes.species_before {
+        if !kept_codes.contains(&species.code) {
+            let res = store.delete_species(&species.code);
+            if res.is_err(){
+                return Err(res.err().unwrap())
+            }
+        }
+    }
+
+    let kept_ids: HashSet<&String> = changes.sightings_after.iter().map(|s| &s.id).collect();
+    for sighting in &changes.sightings_after {
+        let res = store.upsert_sighting(sighting);
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+    }
+    for sighting in &changes.sightings_before {
+        if !kept_ids.contains(&sighting.id) {
+            let res = store.delete_sighting(&sighting.id);
+            if res.is_err(){
+                return Err(res.err().unwrap())
+            }
+        }
+    }
+
+    store.commit()
+}
+
+
+// Function to rename a file to file.migrated, if it is there
+pub fn rename_to_migrated(file: &str) -> Result<(), String> {
+    let path = Path::new(file);
+    if !path.exists() {
+        return Ok(())
+    }
+    let to = format!("{}.{}", file, MIGRATED_EXTENSION);
+    if rename(path, &to).is_err() {
+        return Err(format!("Could not rename {} to {}", file, to));
+    }
+    let message = format!("Renamed {} to {}", file, to);
+    feedback(Feedback::Info, message);
+    Ok(())
+}
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  BinStore  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+// The original bincode files, everything is rewritten on every save. Single records are changed
+// in a copy of the whole file, that is written on commit.
+pub struct BinStore {
+    species_file: String,
+    sightings_file: String,
+    locations_file: String,
+    options_file: String,
+    species: Option<BTreeMap<String, Species>>,
+    sightings: Option<Vec<Sightings>>,
+}
+
+
+impl BinStore {
+    pub fn new() -> BinStore {
+        BinStore {
+            species_file: Species::bin_path(),
+            sightings_file: Sightings::bin_path(),
+            locations_file: data_file(LOCATIONS_FILENAME),
+            options_file: SettingsText::options_path(),
+            species: None,
+            sightings: None,
+        }
+    }
+
+
+    // Function to get the species that single changes go into, read from the file the first time
+    fn changed_species(&mut self) -> Result<&mut BTreeMap<String, Species>, String> {
+        if self.species.is_none() {
+            let birds = match self.load_species() {
+                Ok(birds)               => { birds }
+                Err(LoadError::Missing) => { BTreeMap::new() }
+                Err(e)                  => { return Err(e.to_string()) }
+            };
+            self.species = Some(birds);
+        }
+        Ok(self.species.as_mut().unwrap())
+    }
+
+
+    // Function to get the sightings that single changes go into, read from the file the first time
+    fn changed_sightings(&mut self) -> Result<&mut Vec<Sightings>, String> {
+        if self.sightings.is_none() {
+            let sightings = match self.load_sightings() {
+                Ok(sightings)           => { sightings }
+                Err(LoadError::Missing) => { Vec::new() }
+                Err(e)                  => { return Err(e.to_string()) }
+            };
+            self.sightings = Some(sightings);
+        }
+        Ok(self.sightings.as_mut().unwrap())
+    }
+}
+
+
+impl Store for BinStore {
+    fn name(&self) -> &str {
+        "bincode"
+    }
+
+    fn species_file(&self) -> String {
+        self.species_file.clone()
+    }
+
+    fn sightings_file(&self) -> String {
+        self.sightings_file.clone()
+    }
+
+    fn locations_file(&self) -> String {
+        self.locations_file.clone()
+    }
+
+    fn load_species(&self) -> Result<BTreeMap<String, Species>, LoadError> {
+        Species::load(&self.species_file)
+    }
+
+    fn save_species(&mut self, birds: &BTreeMap<String, Species>) -> Result<(), String> {
+        Species::save(&self.species_file, birds)
+    }
+
+    fn load_sightings(&self) -> Result<Vec<Sightings>, LoadError> {
+        Sightings::load(&self.sightings_file)
+    }
+
+    fn save_sightings(&mut self, sightings: &Vec<Sightings>) -> Result<(), String> {
+        Sightings::save(&self.sightings_file, sightings)
+    }
+
+    fn load_locations(&self) -> Result<BTreeMap<u32, Place>, String> {
+        load_places(&self.locations_file)
+    }
+
+    fn save_locations(&mut self, places: &BTreeMap<u32, Place>) -> Result<(), String> {
+        save_places(&self.locations_file, places)
+    }
+
+    fn load_settings(&self) -> SettingsText {
+        SettingsText::new(&self.options_file)
+    }
+
+    fn save_settings(&mut self, options: &SettingsText) -> Result<(), String> {
+        options.export(&self.options_file)
+    }
+
+    fn upsert_species(&mut self, species: &Species) -> Result<(), String> {
+        let birds = self.changed_species();
+        if birds.is_err(){
+            return Err(birds.err().unwrap())
+        }
+        birds.unwrap().insert(species.code.clone(), species.clone());
+        Ok(())
+    }
+
+    fn delete_species(&mut self, code: &str) -> Result<(), String> {
+        let birds = self.changed_species();
+        if birds.is_err(){
+            return Err(birds.err().unwrap())
+        }
+        birds.unwrap().remove(code);
+        Ok(())
+    }
+
+    fn upsert_sighting(&mut self, sighting: &Sightings) -> Result<(), String> {
+        let sightings = self.changed_sightings();
+        if sightings.is_err(){
+            return Err(sightings.err().unwrap())
+        }
+        let sightings = sightings.unwrap();
+        sightings.retain(|s| s.id != sighting.id);
+        sightings.push(sighting.clone());
+        sightings.sort();
+        Ok(())
+    }
+
+    fn delete_sighting(&mut self, id: &str) -> Result<(), String> {
+        let sightings = self.changed_sightings();
+        if sightings.is_err(){
+            return Err(sightings.err().unwrap())
+        }
+        sightings.unwrap().retain(|s| s.id != id);
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        if let Some(birds) = self.species.take() {
+            let res = Species::save(&self.species_file, &birds);
+            if res.is_err(){
+                return Err(res.err().unwrap())
+            }
+        }
+        if let Some(sightings) = self.sightings.take() {
+            let res = Sightings::save(&self.sightings_file, &sightings);
+            if res.is_err(){
+                return Err(res.err().unwrap())
+            }
+        }
+        Ok(())
+    }
+
+    fn saves_by_record(&self) -> bool {
+        false
+    }
+
+    fn is_outdated(&self) -> bool {
+        is_older_format(&self.species_file, FileKind::Species, SPECIES_FORMAT_VERSION)
+            || is_older_format(&self.sightings_file, FileKind::Sightings, SIGHTINGS_FORMAT_VERSION)
+    }
+
+    fn back_up(&mut self, file: &str, keep: usize) -> Result<(), String> {
+        rotate_backup(file, keep)
+    }
+
+    // The files are only open while they are read or written
+    fn release(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn retire(&mut self) -> Result<(), String> {
+        for file in [&self.species_file, &self.sightings_file, &self.locations_file, &self.options_file] {
+            let res = rename_to_migrated(file);
+            if res.is_err(){
+                return Err(res.err().unwrap())
+            }
+        }
+        Ok(())
+    }
+}
+
+
+
+
+
+
+
+
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@  Tests  @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+// @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@         @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{copy, create_dir_all, remove_dir_all};
+
+
+    #[ignore]
+    #[test]
+    fn t001_bin_to_sqlite_and_back() {
+        std::env::set_var(DATA_DIR_ENV, "./test/store_migrate");
+        create_dir_all("./test/store_migrate").unwrap();
+        copy("./test/store/species/species.bin", data_file("species.bin")).expect("Failed to copy");
+        copy("./test/store/sightings/sightings.bin", data_file("sightings.bin")).expect("Failed to copy");
+
+        let bin = open_store().unwrap();
+        let birds = bin.load_species().unwrap();
+        let sightings = bin.load_sightings().unwrap();
+        let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+        register_sightings(&mut places, &mut sightings.clone());
+
+        let mut sql = open_store_by_name("sqlite").unwrap();
+        sql.save_species(&birds).unwrap();
+        sql.save_sightings(&sightings).unwrap();
+        sql.save_locations(&places).unwrap();
+        let which = open_store().unwrap().name().to_string();
+
+        let birds2 = sql.load_species().unwrap();
+        let sightings2 = sql.load_sightings().unwrap();
+        let places2 = sql.load_locations().unwrap();
+        remove_dir_all("./test/store_migrate").unwrap();
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert_eq!(which, "sqlite");
+        assert_eq!(birds.len(), birds2.len());
+        assert_eq!(sightings, sightings2);
+        assert_eq!(places, places2);
+    }
+
+
+    #[ignore]
+    #[test]
+    fn t002_sqlite_single_records() {
+        create_dir_all("./test/store_records").unwrap();
+        let path = "./test/store_records/mybirding.db";
+        let mut store: Box<dyn Store> = Box::new(SqliteStore::open(path).unwrap());
+        let mut sightings = Sightings::load("./test/store/sightings/sightings.bin").unwrap();
+        store.save_sightings(&sightings).unwrap();
+
+        // Change one, take one out and add one, as a command would
+        let before = sightings.clone();
+        let gone = sightings.remove(3);
+        sightings[0].comments = "Changed".to_string();
+        let mut new = sightings[1].clone();
+        new.id = new_sighting_id();
+        sightings.push(new.clone());
+        sightings.sort();
+        let changes = JournalEntry::from_diff("", &BTreeMap::new(), &BTreeMap::new(), &before, &sightings,
+                                                &BTreeMap::new(), &BTreeMap::new(), "", "").unwrap();
+        save_changes(&mut store, &changes).unwrap();
+
+        let reopened = SqliteStore::open(path).unwrap();
+        let loaded = reopened.load_sightings().unwrap();
+        remove_dir_all("./test/store_records").unwrap();
+
+        assert_eq!(loaded, sightings);
+        assert_eq!(loaded.iter().any(|s| s.id == gone.id), false);
+        assert_eq!(loaded.contains(&new), true);
+    }
+}
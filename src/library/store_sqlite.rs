@@ -0,0 +1,440 @@
+/*
+        The SQLite store. Everything lives in one mybirding.db file in the data directory.
+
+        Every species and sighting is kept as json in a data column, keyed by its code or id, with
+        the columns that are searched on (sname, date) next to it. A command only writes the rows
+        it changed, so a big sightings database is not rewritten when one sighting is added.
+        Those changes are held in one transaction until commit.
+
+        The format version is kept in PRAGMA user_version.
+            1   species by code, sightings by id, locations by id and the settings
+
+        2026.09.23   Sven Ponelat
+*/
+
+use crate::library::bird_species::*;
+use crate::library::bird_sightings::*;
+use crate::library::bird_locations::*;
+use crate::library::bin_format::*;
+use crate::library::settings::*;
+use crate::library::store::*;
+use crate::library::data_dir::*;
+use crate::library::backups::*;
+use rusqlite::{params, Connection, DatabaseName, Params};
+use rusqlite::types::FromSql;
+use std::collections::{BTreeMap, HashMap};
+
+
+pub const DB_FILENAME:          &str = "mybirding.db";
+pub const DB_FORMAT_VERSION:    i64 = 1;
+
+
+pub struct SqliteStore {
+    path: String,
+    conn: Connection,
+    // The database is backed up once, however many of its tables a command changes
+    backed_up: bool,
+}
+
+
+// Function to turn a SQLite error into the usual String error
+fn sql_err(what: &str, e: rusqlite::Error) -> String {
+    format!("Problem with the database ({}): {}", what, e)
+}
+
+
+// Function to read a key column and the json next to it
+fn read_keyed<K: FromSql>(conn: &Connection, sql: &str) -> Result<Vec<(K, String)>, String> {
+    let mut stmt = match conn.prepare(sql) {
+        Ok(stmt) => { stmt }
+        Err(e)   => { return Err(sql_err("reading", e)); }
+    };
+    let rows = match stmt.query_map([], |row| Ok((row.get::<_, K>(0)?, row.get::<_, String>(1)?))) {
+        Ok(rows) => { rows }
+        Err(e)   => { return Err(sql_err("reading", e)); }
+    };
+
+    let mut ret: Vec<(K, String)> = Vec::new();
+    for row in rows {
+        match row {
+            Ok(pair) => { ret.push(pair) }
+            Err(e)   => { return Err(sql_err("reading a row", e)); }
+        }
+    }
+    Ok(ret)
+}
+
+
+impl SqliteStore {
+
+    // The full path of the database in the data directory
+    pub fn db_path() -> String {
+        data_file(DB_FILENAME)
+    }
+
+
+    // Function to open (or create) the database and make sure the tables are there
+    pub fn open(path: &str) -> Result<SqliteStore, String> {
+        let conn = match Connection::open(path) {
+            Ok(conn) => { conn }
+            Err(e)   => { return Err(sql_err("opening", e)); }
+        };
+
+        let version: i64 = match conn.query_row("PRAGMA user_version", [], |row| row.get(0)) {
+            Ok(version) => { version }
+            Err(e)      => { return Err(sql_err("reading the version", e)); }
+        };
+        if version > DB_FORMAT_VERSION {
+            let message = format!("The database is format version {}, this version of mybirding only reads up to {}. Please upgrade mybirding.",
+                                    version, DB_FORMAT_VERSION);
+            return Err(message);
+        }
+
+        let create = "
+            CREATE TABLE IF NOT EXISTS species (
+                code    TEXT PRIMARY KEY,
+                sname   TEXT NOT NULL,
+                data    TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS sightings (
+                id      TEXT PRIMARY KEY,
+                date    INTEGER NOT NULL,
+                sname   TEXT NOT NULL,
+                data    TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS sightings_sname ON sightings (sname);
+            CREATE INDEX IF NOT EXISTS sightings_date  ON sightings (date);
+            CREATE TABLE IF NOT EXISTS locations (
+                id      INTEGER PRIMARY KEY,
+                data    TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS settings (
+                key     TEXT PRIMARY KEY,
+                value   TEXT NOT NULL,
+                show    INTEGER NOT NULL
+            );";
+        if let Err(e) = conn.execute_batch(create) {
+            return Err(sql_err("creating the tables", e));
+        }
+
+        if let Err(e) = conn.execute_batch(&format!("PRAGMA user_version = {};", DB_FORMAT_VERSION)) {
+            return Err(sql_err("setting the version", e));
+        }
+        Ok(SqliteStore { path: path.to_string(), conn: conn, backed_up: false })
+    }
+
+
+    // Function to start the transaction single changes go into, unless it is already going
+    fn begin(&self) -> Result<(), String> {
+        if !self.conn.is_autocommit() {
+            return Ok(())
+        }
+        match self.conn.execute_batch("BEGIN;") {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err("starting the changes", e)) }
+        }
+    }
+
+
+    // Function to run one statement that changes a single record, inside the transaction
+    fn change<P: Params>(&self, what: &str, sql: &str, params: P) -> Result<(), String> {
+        let res = self.begin();
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+        match self.conn.execute(sql, params) {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err(what, e)) }
+        }
+    }
+
+
+    // Function to read all the json of one table
+    fn read_data(&self, sql: &str) -> Result<Vec<String>, LoadError> {
+        let mut stmt = match self.conn.prepare(sql) {
+            Ok(stmt) => { stmt }
+            Err(e)   => { return Err(LoadError::Unreadable(sql_err("reading", e))); }
+        };
+        let rows = match stmt.query_map([], |row| row.get::<_, String>(0)) {
+            Ok(rows) => { rows }
+            Err(e)   => { return Err(LoadError::Unreadable(sql_err("reading", e))); }
+        };
+
+        let mut ret: Vec<String> = Vec::new();
+        for row in rows {
+            match row {
+                Ok(data) => { ret.push(data) }
+                Err(e)   => { return Err(LoadError::Corrupt(sql_err("reading a row", e))); }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+
+impl Store for SqliteStore {
+    fn name(&self) -> &str {
+        "sqlite"
+    }
+
+    fn species_file(&self) -> String {
+        self.path.clone()
+    }
+
+    fn sightings_file(&self) -> String {
+        self.path.clone()
+    }
+
+    fn locations_file(&self) -> String {
+        self.path.clone()
+    }
+
+    fn load_species(&self) -> Result<BTreeMap<String, Species>, LoadError> {
+        let rows = self.read_data("SELECT data FROM species");
+        if rows.is_err(){
+            return Err(rows.err().unwrap())
+        }
+
+        let mut ret: BTreeMap<String, Species> = BTreeMap::new();
+        for data in rows.unwrap() {
+            let species: Species = match serde_json::from_str(&data) {
+                Ok(species) => { species }
+                Err(_)      => { return Err(LoadError::Corrupt(format!("A species in the database is corrupt -> {}", data))); }
+            };
+            ret.insert(species.code.clone(), species);
+        }
+        Ok(ret)
+    }
+
+
+    // All the species are written, what was there before is replaced
+    fn save_species(&mut self, birds: &BTreeMap<String, Species>) -> Result<(), String> {
+        let tx = match self.conn.transaction() {
+            Ok(tx) => { tx }
+            Err(e) => { return Err(sql_err("saving species", e)); }
+        };
+        if let Err(e) = tx.execute("DELETE FROM species", []) {
+            return Err(sql_err("saving species", e));
+        }
+        for (code, species) in birds {
+            let data = serde_json::to_string(species).unwrap();
+            if let Err(e) = tx.execute("INSERT OR REPLACE INTO species (code, sname, data) VALUES (?1, ?2, ?3)",
+                        params![code, species.sname, data]) {
+                return Err(sql_err("saving species", e));
+            }
+        }
+
+        match tx.commit() {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err("saving species", e)) }
+        }
+    }
+
+
+    fn load_sightings(&self) -> Result<Vec<Sightings>, LoadError> {
+        let rows = self.read_data("SELECT data FROM sightings ORDER BY date, sname");
+        if rows.is_err(){
+            return Err(rows.err().unwrap())
+        }
+
+        let mut ret: Vec<Sightings> = Vec::new();
+        for data in rows.unwrap() {
+            let sighting: Sightings = match serde_json::from_str(&data) {
+                Ok(sighting) => { sighting }
+                Err(_)       => { return Err(LoadError::Corrupt(format!("A sighting in the database is corrupt -> {}", data))); }
+            };
+            ret.push(sighting);
+        }
+        ret.sort();
+        Ok(ret)
+    }
+
+
+    // All the sightings are written, what was there before is replaced
+    fn save_sightings(&mut self, sightings: &Vec<Sightings>) -> Result<(), String> {
+        let tx = match self.conn.transaction() {
+            Ok(tx) => { tx }
+            Err(e) => { return Err(sql_err("saving sightings", e)); }
+        };
+        if let Err(e) = tx.execute("DELETE FROM sightings", []) {
+            return Err(sql_err("saving sightings", e));
+        }
+        for sighting in sightings {
+            let data = serde_json::to_string(sighting).unwrap();
+            if let Err(e) = tx.execute("INSERT OR REPLACE INTO sightings (id, date, sname, data) VALUES (?1, ?2, ?3, ?4)",
+                        params![sighting.id, sighting.date, sighting.sname, data]) {
+                return Err(sql_err("saving sightings", e));
+            }
+        }
+
+        match tx.commit() {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err("saving sightings", e)) }
+        }
+    }
+
+
+    fn load_locations(&self) -> Result<BTreeMap<u32, Place>, String> {
+        let rows = self.read_data("SELECT data FROM locations");
+        if rows.is_err(){
+            return Err(rows.err().unwrap().to_string())
+        }
+
+        let mut ret: BTreeMap<u32, Place> = BTreeMap::new();
+        for data in rows.unwrap() {
+            let place: Place = match serde_json::from_str(&data) {
+                Ok(place) => { place }
+                Err(_)    => { return Err(format!("A location in the database is corrupt -> {}", data)); }
+            };
+            ret.insert(place.id, place);
+        }
+        Ok(ret)
+    }
+
+
+    // Only the locations that changed are written, the ones that are gone (merged) are deleted
+    fn save_locations(&mut self, places: &BTreeMap<u32, Place>) -> Result<(), String> {
+        let tx = match self.conn.transaction() {
+            Ok(tx) => { tx }
+            Err(e) => { return Err(sql_err("saving locations", e)); }
+        };
+
+        let rows = read_keyed::<u32>(&tx, "SELECT id, data FROM locations");
+        if rows.is_err(){
+            return Err(rows.err().unwrap())
+        }
+        let mut existing: HashMap<u32, String> = rows.unwrap().into_iter().collect();
+
+        for (id, place) in places {
+            let data = serde_json::to_string(place).unwrap();
+            if existing.remove(id) == Some(data.clone()) {
+                continue;
+            }
+            if let Err(e) = tx.execute("INSERT OR REPLACE INTO locations (id, data) VALUES (?1, ?2)", params![id, data]) {
+                return Err(sql_err("saving locations", e));
+            }
+        }
+        for id in existing.keys() {
+            if let Err(e) = tx.execute("DELETE FROM locations WHERE id = ?1", params![id]) {
+                return Err(sql_err("deleting locations", e));
+            }
+        }
+
+        match tx.commit() {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err("saving locations", e)) }
+        }
+    }
+
+
+    fn load_settings(&self) -> SettingsText {
+        let mut ret = SettingsText { map: BTreeMap::new() };
+
+        if let Ok(mut stmt) = self.conn.prepare("SELECT key, value, show FROM settings") {
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, bool>(2)?))
+            });
+            if let Ok(rows) = rows {
+                for (key, value, show) in rows.flatten() {
+                    ret.map.insert(key, Sdata { value: value, show: show });
+                }
+            }
+        }
+
+        ret.add_missing_keys();
+        ret
+    }
+
+
+    fn save_settings(&mut self, options: &SettingsText) -> Result<(), String> {
+        let tx = match self.conn.transaction() {
+            Ok(tx) => { tx }
+            Err(e) => { return Err(sql_err("saving settings", e)); }
+        };
+        for (key, sdata) in &options.map {
+            if let Err(e) = tx.execute("INSERT OR REPLACE INTO settings (key, value, show) VALUES (?1, ?2, ?3)",
+                        params![key, sdata.value, sdata.show]) {
+                return Err(sql_err("saving settings", e));
+            }
+        }
+        match tx.commit() {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err("saving settings", e)) }
+        }
+    }
+
+    fn upsert_species(&mut self, species: &Species) -> Result<(), String> {
+        let data = serde_json::to_string(species).unwrap();
+        self.change("saving a species", "INSERT OR REPLACE INTO species (code, sname, data) VALUES (?1, ?2, ?3)",
+                        params![species.code, species.sname, data])
+    }
+
+    fn delete_species(&mut self, code: &str) -> Result<(), String> {
+        self.change("deleting a species", "DELETE FROM species WHERE code = ?1", params![code])
+    }
+
+    fn upsert_sighting(&mut self, sighting: &Sightings) -> Result<(), String> {
+        let data = serde_json::to_string(sighting).unwrap();
+        self.change("saving a sighting", "INSERT OR REPLACE INTO sightings (id, date, sname, data) VALUES (?1, ?2, ?3, ?4)",
+                        params![sighting.id, sighting.date, sighting.sname, data])
+    }
+
+    fn delete_sighting(&mut self, id: &str) -> Result<(), String> {
+        self.change("deleting a sighting", "DELETE FROM sightings WHERE id = ?1", params![id])
+    }
+
+    fn commit(&mut self) -> Result<(), String> {
+        if self.conn.is_autocommit() {
+            return Ok(())
+        }
+        match self.conn.execute_batch("COMMIT;") {
+            Ok(_)  => { Ok(()) }
+            Err(e) => { Err(sql_err("saving the changes", e)) }
+        }
+    }
+
+    fn saves_by_record(&self) -> bool {
+        true
+    }
+
+    // There is only one version of the database so far
+    fn is_outdated(&self) -> bool {
+        false
+    }
+
+    // The species, sightings and locations are all in the one file. The copy is made through the
+    // connection with SQLite's online backup, so it is whole even with a journal next to the file.
+    fn back_up(&mut self, _file: &str, keep: usize) -> Result<(), String> {
+        if self.backed_up {
+            return Ok(())
+        }
+        let res = rotate_backup_with(&self.path, keep, |backup| {
+            match self.conn.backup(DatabaseName::Main, backup, None) {
+                Ok(_)  => { Ok(()) }
+                Err(e) => { Err(sql_err("making a backup", e)) }
+            }
+        });
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+        self.backed_up = true;
+        Ok(())
+    }
+
+    // Closing the connection finishes its journal, after that the file can be replaced
+    fn release(&mut self) -> Result<(), String> {
+        let closed = std::mem::replace(&mut self.conn, Connection::open_in_memory().unwrap());
+        if let Err((_, e)) = closed.close() {
+            return Err(sql_err("closing", e));
+        }
+        Ok(())
+    }
+
+    fn retire(&mut self) -> Result<(), String> {
+        let res = self.release();
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+        rename_to_migrated(&self.path)
+    }
+}
@@ -6,9 +6,19 @@ use std::process::exit;
 use library::settings::*;
 use library::my_file_funcs::*;
 use library::bird_species::*;
+use library::bird_species_merge::*;
+use library::bird_checklist::*;
+use library::bird_taxonomy::*;
+use library::bird_lists::*;
+use library::bird_counts::*;
+use library::bird_locations::*;
 use library::help::*;
-use std::fs::copy;
-use std::path::Path;
+use library::data_dir::*;
+use library::backups::*;
+use library::bin_format::*;
+use library::store::*;
+use library::journal::*;
+use library::confirm::*;
 use std::env;
 use termion::{color, style};
 use thousands::{Separable};
@@ -16,6 +26,7 @@ use std::time::{SystemTime};
 use crate::library::bird_species_box::*;
 use crate::library::bird_species_support::*;
 use crate::library::bird_sightings_supp::*;
+use crate::library::bird_sightings_merge::*;
 
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -26,11 +37,35 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 #[rustfmt::skip]
 fn main() {
     let now = SystemTime::now();
-    let arguments: Vec<String> = env::args().collect();
+    let mut arguments: Vec<String> = env::args().collect();
     let mut command = None;
     let mut sub1 = None;
     let mut sub2 = None;
-    // let mut sub3 = None;
+    let mut sub3 = None;
+
+    // Where the data lives, has to be sorted out before anything is read
+    match take_data_dir_flag(&mut arguments) {
+        Ok(Some(dir)) => {
+            if let Err(message) = set_data_dir_override(&dir) {
+                feedback(Feedback::Error, message);
+                exit(17);
+            }
+        }
+        Ok(None) => { () }
+        Err(message) => {
+            feedback(Feedback::Error, message);
+            exit(17);
+        }
+    }
+    let assume_yes = take_flag(&mut arguments, &YES_FLAGS);
+    let dry_run = take_flag(&mut arguments, &[DRY_RUN_FLAG]);
+    take_end_of_flags(&mut arguments);
+    
+    if let Err(message) = make_data_dirs() {
+        feedback(Feedback::Error, message);
+        exit(1);
+    }
+    migrate_cwd_files();
 
     // It seems I need to do this,otherwise temporary variables get dropped
     match arguments.len() {
@@ -50,7 +85,7 @@ fn main() {
             command = Some(arguments[1].to_lowercase().trim().to_owned());
             sub1 = Some(arguments[2].trim().to_owned());
             sub2 = Some(arguments[3].trim().to_owned());
-            // sub3 = Some(arguments[4].trim().to_owned());
+            sub3 = Some(arguments[4].trim().to_owned());
         },
 
         _ => { () }
@@ -60,27 +95,45 @@ fn main() {
     let mut birds:  BTreeMap<String, Species> = BTreeMap::new();
     let mut file_change_birds = false;
     let mut file_change_sightings = false;
+    let mut file_change_locations = false;
+    // The data was changed before the command (an older format, the registry), so only whole files will do
+    let mut save_whole_files = false;
     let mut sightings: Vec<Sightings> = Vec::new();
     
     println!("");
     
     // Options
-    let mut options = SettingsText::new(OPTIONS_FILENAME);
+    // Where everything is kept (bincode files or a sqlite database)
+    let r_store = open_store();
+    if r_store.is_err(){
+        let message = r_store.err().unwrap();
+        feedback(Feedback::Error, message);
+        exit(1);
+    }
+    let mut store = r_store.unwrap();
+    let mut options = store.load_settings();
+    match options.get_species_order() {
+        Ok(order)    => { set_species_order(order) }
+        Err(message) => { feedback(Feedback::Warning, message) }
+    }
+    match options.get_language() {
+        Ok(language) => { set_language(language) }
+        Err(message) => { feedback(Feedback::Warning, message) }
+    }
     
     // Birds
-    let destination_birds = SPECIES_BIN_FILENAME;
-    // **************************************************************************************                       Remove Later
-    if ! Path::new(destination_birds).exists() {
-        let source_birds = "./test/store/species/species.bin";
-        copy(source_birds,destination_birds).expect("Failed to copy");
-    }
-    let birds_file = Species::load(destination_birds);
+    let birds_file = store.load_species();
     let mut birds_file_ok = false;
+    let mut birds_file_damaged = false;
     if birds_file.is_ok() {
         birds_file_ok = true;
 
         // This BTreeMap has the code as the key
         birds = birds_file.unwrap();
+    } else if birds_file.clone().err().unwrap().is_damaged() {
+        birds_file_damaged = true;
+        let message = birds_file.err().unwrap().to_string();
+        feedback(Feedback::Error, message);
     }
     // This BTreeMap has the sname as the key
     sbirds = make_sname_btree(&birds);
@@ -88,17 +141,54 @@ fn main() {
     
     
     // Sightings
-    let destination_sightings = SIGHTINGS_BIN_FILENAME;
-    // **************************************************************************************                       Remove Later
-    if ! Path::new(destination_sightings).exists() {
-        let source_sights = "./test/store/sightings/sightings.bin";
-        copy(source_sights,destination_sightings).expect("Failed to copy");
-    }
-    let sightings_file = Sightings::load(destination_sightings);
+    let sightings_file = store.load_sightings();
     let mut sightings_file_ok = false;
+    let mut sightings_file_damaged = false;
     if sightings_file.is_ok() {
         sightings_file_ok = true;
         sightings = sightings_file.unwrap();
+    } else if sightings_file.clone().err().unwrap().is_damaged() {
+        sightings_file_damaged = true;
+        let message = sightings_file.err().unwrap().to_string();
+        feedback(Feedback::Error, message);
+    }
+    
+    // Locations, every sighting is put in the registry
+    let mut places: BTreeMap<u32, Place> = BTreeMap::new();
+    let mut locations_file_damaged = false;
+    match store.load_locations() {
+        Ok(registry) => { places = registry }
+        Err(message) => {
+            locations_file_damaged = true;
+            feedback(Feedback::Error, message);
+        }
+    }
+    if !sightings_file_damaged && !locations_file_damaged {
+        let registered = register_sightings(&mut places, &mut sightings);
+        if registered > 0 {
+            file_change_sightings = true;
+            file_change_locations = true;
+            save_whole_files = true;
+            let message = format!("{} {} put in the location registry, it has {} {}.", registered.separate_with_spaces(),
+                                    plural("sighting", registered), count_places(&places).separate_with_spaces(), plural("location", count_places(&places)));
+            feedback(Feedback::Info, message);
+        }
+    }
+    
+    // What the data looked like before the command, for the journal
+    let birds_before = birds.clone();
+    let sightings_before = sightings.clone();
+    let places_before = places.clone();
+    let lists_before = list_registry(&options);
+    let mut journal_moved: Option<Journal> = None;
+    
+    // Files in an older format are written back straight away, so they stay the same from now on
+    if store.is_outdated() && !birds_file_damaged && !sightings_file_damaged {
+        file_change_birds = birds_file_ok;
+        file_change_sightings = sightings_file_ok;
+        save_whole_files = true;
+        let message = format!("The {} files are in an older format and will be saved in the new one.", store.name());
+        feedback(Feedback::Info, message);
     }
     
 
@@ -123,11 +213,17 @@ fn main() {
         }
 
     // Too many arguments
-    } else if arguments.len() >= 5 {
+    } else if arguments.len() >= 6 {
         let message = format!("There are too many arguments.");
         feedback(Feedback::Warning, message);
     
     
+    // These write the files themselves, so there is nothing to hold back
+    } else if dry_run && ["migrate-store", "repair", "restore"].contains(&command.clone().unwrap().as_str()) {
+        let message = format!("{} cannot be done as a dry run.", command.unwrap());
+        feedback(Feedback::Warning, message);
+        exit(17);
+    
     //majority of arguments    
     } else {
 
@@ -205,18 +301,20 @@ fn main() {
 
             "bb"  => {
                 let read = &options.get_number("lastSpeciesViewed");
+                let by_family = sub1.clone().map(|s| s.to_lowercase()) == Some("f".to_string());
 
                 if *read == 0 {
                     show_bird_number(0, &mut options, &mut birds, &sightings);
                     let message = format!("You are at the beginning of the species database.",);
                     feedback(Feedback::Info, message); 
                 } else {
-                    let wanted = read - 1;
+                    let wanted = if by_family { get_family_step(*read, &birds, false) } else { read - 1 };
+                    announce_family_change(*read, wanted, &birds);
                     show_bird_number(wanted, &mut options, &mut birds, &mut sightings);
                 }
 
-                if sub1.is_some(){
-                    let message = format!("bb does not take any arguments itsself.");
+                if sub1.is_some() && !by_family {
+                    let message = format!("bb only takes f, to go back to the previous family.");
                     feedback(Feedback::Warning, message);
                 }  //end of sub1.is_none()                                
             }// end of "bb"
@@ -230,6 +328,14 @@ fn main() {
                     match yes.what {
                         
                         Code::Code | Code::Number => {
+                            let species = yes.clone().species.unwrap();
+                            let lost = get_array_of_sname_indices_from_records(&species.sname, &sightings).len();
+                            let question = format!("Delete {} ({}) and the {} {} of it?", species.name, species.code,
+                                                    lost.separate_with_spaces(), plural("sighting", lost));
+                            if !dry_run && !confirm(&question, assume_yes) {
+                                cancelled();
+                            }
+                            
                             if delete_bird(yes, &mut options, &mut birds, &mut sightings).is_ok(){
                                 file_change_birds = true;
                                 file_change_sightings = true;
@@ -273,6 +379,7 @@ fn main() {
                                 // Result not good
                                 else {
                                     error_last_arg_edit_invalid(yes.clone().species.unwrap().name);
+                                    feedback(Feedback::Warning, result.err().unwrap());
                                 }
                             }
                             // Sub2 is None
@@ -296,6 +403,44 @@ fn main() {
                 }  
             } // end of "be"
             
+            "bm"  => {
+                if_birds_length_is_zero(&birds);
+                if sub1.is_none() || sub2.is_none(){
+                    let message = format!("bm needs the species that goes and the one it goes into (code or index).");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                
+                let from = what_code(&sub1.unwrap(), &mut birds);
+                let into = what_code(&sub2.unwrap(), &mut birds);
+                if from.what == Code::Neither || into.what == Code::Neither {
+                    error_neither();
+                    exit(17);
+                }
+                
+                let from_species = from.species.unwrap();
+                let into_species = into.species.unwrap();
+                let moving = get_array_of_sname_indices_from_records(&from_species.sname, &sightings).len();
+                let question = format!("Merge {} ({}) into {} ({}), moving {} {}?", from_species.name, from_species.code,
+                                        into_species.name, into_species.code, moving.separate_with_spaces(), plural("sighting", moving));
+                if !dry_run && !confirm(&question, assume_yes) {
+                    cancelled();
+                }
+                
+                let from_code = from_species.code;
+                let into_code = into_species.code;
+                let result = merge_two_species(&from_code, &into_code, &mut birds, &mut sightings);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                display_species_move_report(&result.unwrap());
+                file_change_birds = true;
+                file_change_sightings = true;
+            } // end of "bm"
+            
+            
             "bex"  => {
                 if_birds_length_is_zero(&birds);
                 
@@ -337,18 +482,20 @@ fn main() {
             "bf"  => {
                 let read = &options.get_number("lastSpeciesViewed");
                 let last = birds.len();
+                let by_family = sub1.clone().map(|s| s.to_lowercase()) == Some("f".to_string());
                 
                 if *read == last - 1 {
                     show_bird_number(*read, &mut options, &mut birds, &mut sightings);
                     let message = format!("You are at the end of the species database.",);
                     feedback(Feedback::Info, message); 
                 } else {
-                    let wanted = read + 1;
+                    let wanted = if by_family { get_family_step(*read, &birds, true) } else { read + 1 };
+                    announce_family_change(*read, wanted, &birds);
                     show_bird_number(wanted, &mut options, &mut birds, &mut sightings);
                 }
                 
-                if sub1.is_some(){
-                    let message = format!("bb does not take any arguments itsself.");
+                if sub1.is_some() && !by_family {
+                    let message = format!("bf only takes f, to go on to the next family.");
                     feedback(Feedback::Warning, message);
                 }  //end of sub1.is_none()   
                 
@@ -358,43 +505,115 @@ fn main() {
             "bim"  => {
                 if sub1.is_some(){
                     let file = sub1.unwrap().trim().to_owned();
-                    let ext = get_extension_from_filename(&file);
-                    if ext.is_none(){
-                        let message = format!("Wrong file extension given: (either json or csv)");
-                        feedback(Feedback::Error, message);
-                        exit(17);
-                    }
                     
-                    // lets clear out birds
-                    birds = BTreeMap::new();
-                    
-                    match ext.unwrap() {
-                        "csv" => {
-                            let result = Species::import_csv(&file, &mut birds);
+                    // Names in other languages go onto the species we have, nothing is replaced
+                    if sub2.clone().map(|s| s.to_lowercase()) == Some("names".to_string()) {
+                        if_birds_length_is_zero(&birds);
+                        let languages = match sub3.clone() {
+                            Some(text) => {
+                                let mut languages: Vec<String> = Vec::new();
+                                for part in text.split(',').filter(|p| p.trim().len() > 0) {
+                                    let language = Species::validate_language(part);
+                                    if language.is_err(){
+                                        let message = language.err().unwrap();
+                                        feedback(Feedback::Error, message);
+                                        exit(17);
+                                    }
+                                    languages.push(language.unwrap());
+                                }
+                                Some(languages)
+                            }
+                            None => { None }
+                        };
+                        let result = import_names(&file, languages, &mut birds);
+                        if result.is_err(){
+                            let message = result.err().unwrap();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        let report = result.unwrap();
+                        display_names_report(&report);
+                        file_change_birds = report.updated > 0;
+                        
+                    } else {
+                        // The import goes into a new map, the old one is only replaced once the user agrees
+                        let mut new_birds: BTreeMap<String, Species> = BTreeMap::new();
+                        
+                        // A published checklist, the file is read by its header
+                        let checklist = sub2.clone().and_then(|s| Checklist::from_arg(&s));
+                        let merge_arg = if checklist.is_some() { sub3.clone() } else { sub2.clone() };
+                        if merge_arg.is_some() && merge_arg.clone().map(|s| s.to_lowercase()) != Some("merge".to_string()) {
+                            let message = format!("After the file name comes: merge, names, or a checklist (ioc, ebird or clements) and then merge");
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        
+                        if checklist.is_some() {
+                            let checklist = checklist.unwrap();
+                            let result = import_checklist(&file, checklist, &mut new_birds);
                             if result.is_err(){
                                 let message = result.err().unwrap();
                                 feedback(Feedback::Error, message);
                                 exit(17);
                             }
-                            file_change_birds = true;
-                        }
+                            display_checklist_report(&mut options, checklist, &result.unwrap());
                         
-                        "json" => {
-                            let result = Species::import(&file);
-                            if result.is_err(){
-                                let message = result.err().unwrap();
+                        } else {
+                            let ext = get_extension_from_filename(&file);
+                            if ext.is_none(){
+                                let message = format!("Wrong file extension given: (either json or csv)");
                                 feedback(Feedback::Error, message);
                                 exit(17);
                             }
-                            birds = result.unwrap();
-                            file_change_birds = true;
+                            
+                            match ext.unwrap() {
+                                "csv" => {
+                                    let result = Species::import_csv(&file, &mut new_birds);
+                                    if result.is_err(){
+                                        let message = result.err().unwrap();
+                                        feedback(Feedback::Error, message);
+                                        exit(17);
+                                    }
+                                }
+                                
+                                "json" => {
+                                    let result = Species::import(&file);
+                                    if result.is_err(){
+                                        let message = result.err().unwrap();
+                                        feedback(Feedback::Error, message);
+                                        exit(17);
+                                    }
+                                    new_birds = result.unwrap();
+                                }
+                                
+                                // Other ones
+                                _ => {
+                                    let message = format!("Wrong file extension given: (either json or csv)");
+                                    feedback(Feedback::Error, message);
+                                    exit(17);
+                                }
+                            }
                         }
                         
-                        // Other ones
-                        _ => {
-                            let message = format!("Wrong file extension given: (either json or csv)");
-                            feedback(Feedback::Error, message);
-                            exit(17);
+                        // Match on sname, the codes we have stay the same
+                        if merge_arg.clone().map(|s| s.to_lowercase()) == Some("merge".to_string()) {
+                            let report = merge_species(&mut birds, new_birds, &sightings);
+                            display_species_merge_report(&mut options, &report);
+                            file_change_birds = report.added.len() > 0 || report.updated.len() > 0;
+                            
+                        } else {
+                            if birds.len() > 0 {
+                                let new_snames = make_sname_btree(&new_birds);
+                                let orphans = sightings.iter().filter(|s| !new_snames.contains_key(&s.sname)).count();
+                                let question = format!("Replace the {} species with the {} in {}? {} {} would be left without a species.",
+                                                        birds.len().separate_with_spaces(), new_birds.len().separate_with_spaces(), file,
+                                                        orphans.separate_with_spaces(), plural("sighting", orphans));
+                                if !dry_run && !confirm(&question, assume_yes) {
+                                    cancelled();
+                                }
+                            }
+                            birds = new_birds;
+                            file_change_birds = true;
                         }
                     }
                 }
@@ -426,6 +645,38 @@ fn main() {
                 } //end of sub1.is_none()      
             } // end of "bl"
             
+            
+            "btu"  => {
+                if_birds_length_is_zero(&birds);
+                if sub1.is_none(){
+                    let message = format!("btu needs the mapping file of old and new scientific names.");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                
+                let file = sub1.unwrap().trim().to_owned();
+                let changes = read_taxonomy_map(&file);
+                if changes.is_err(){
+                    let message = changes.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                
+                let report = apply_taxonomy_changes(&changes.unwrap(), &mut birds, &mut sightings);
+                display_taxonomy_report(&mut options, &report);
+                file_change_birds = report.species_renamed.len() > 0 || report.species_lumped.len() > 0;
+                file_change_sightings = report.sightings_moved.len() > 0;
+                
+                // A dry run leaves no trace
+                if !dry_run {
+                    let result = write_taxonomy_audit(&taxonomy_audit_path(), &file, &report);
+                    if result.is_err(){
+                        let message = result.err().unwrap();
+                        feedback(Feedback::Warning, message);
+                    }
+                }
+            } // end of "btu"
+            
 
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    h     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
@@ -434,6 +685,239 @@ fn main() {
             }
             
             
+            "history" => {
+                let journal = Journal::load(&Journal::path());
+                if journal.is_err(){
+                    let message = journal.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let journal = journal.unwrap();
+                if journal.entries.len() == 0 {
+                    let message = format!("There is nothing in the journal yet.");
+                    feedback(Feedback::Info, message);
+                } else {
+                    display_history(&mut options, &journal);
+                }
+            } // end of "history"
+            
+            
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    m     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            "migrate-store" => {
+                if sub1.is_none(){
+                    let message = format!("migrate-store needs to know where to: sqlite or bincode   (now using {})", store.name());
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                if birds_file_damaged || sightings_file_damaged || locations_file_damaged {
+                    let message = format!("Cannot migrate while a data file could not be read. Try: repair  or  restore");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                
+                let result = migrate_store(&mut store, &sub1.unwrap(), &birds, &sightings, &places, &options);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let old_name = store.name().to_string();
+                store = result.unwrap();
+                
+                let message = format!("Moved {} species and {} sightings from {} to {}",
+                                        birds.len().separate_with_spaces(), sightings.len().separate_with_spaces(), old_name, store.name());
+                feedback(Feedback::Info, message);
+            } // end of "migrate-store"
+            
+            
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    l     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            "l"   => {
+                let rules = CountingRules::from_options(&options);
+                if rules.is_err(){
+                    let message = rules.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let rules = rules.unwrap();
+                if sub1.is_none(){
+                    display_lists(&mut options, &rules, &birds, &sightings);
+                } else {
+                    let list = find_list(&sub1.unwrap(), &options, &birds);
+                    if list.is_err(){
+                        let message = list.err().unwrap();
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                    let report = list_report(&list.unwrap(), &rules, &birds, &sightings);
+                    display_list_report(&mut options, &report);
+                }
+            } // end of "l"
+            
+            
+            "la"  => {
+                if sub1.is_none() || sub2.is_none(){
+                    let message = format!("la needs the list and a file of species or search terms, e.g.  la \"Garden\" \"t=home\"");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let result = change_list(true, &sub1.unwrap(), &sub2.unwrap(), &options, &mut birds, &sightings, &places);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                feedback(Feedback::Info, result.unwrap());
+                file_change_birds = true;
+            } // end of "la"
+            
+            
+            "lr"  => {
+                if sub1.is_none() || sub2.is_none(){
+                    let message = format!("lr needs the list and a file of species or search terms, e.g.  lr \"Garden\" \"t=home\"");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let result = change_list(false, &sub1.unwrap(), &sub2.unwrap(), &options, &mut birds, &sightings, &places);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                feedback(Feedback::Info, result.unwrap());
+                file_change_birds = true;
+            } // end of "lr"
+            
+            
+            "lc"  => {
+                if sub1.is_none(){
+                    let message = format!("lc needs the name of the new list, e.g.  lc \"UK BOU\"");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let result = create_list(&sub1.unwrap(), &mut options, &birds);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let message = format!("The list {} was made, put species on it with la.", result.unwrap());
+                feedback(Feedback::Info, message);
+            } // end of "lc"
+            
+            
+            "ld"  => {
+                if sub1.is_none(){
+                    let message = format!("ld needs the name of the list to delete.");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let list = find_list(&sub1.unwrap(), &options, &birds);
+                if list.is_err(){
+                    let message = list.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let list = list.unwrap();
+                let on_list = birds.values().filter(|s| s.is_on_list(&list)).count();
+                let question = format!("Delete the list {}, taking it off {} species?", list, on_list.separate_with_spaces());
+                if !dry_run && !confirm(&question, assume_yes) {
+                    cancelled();
+                }
+                
+                let result = delete_list(&list, &mut options, &mut birds);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let message = format!("The list {} was deleted.", list);
+                feedback(Feedback::Info, message);
+                file_change_birds = true;
+            } // end of "ld"
+            
+            
+            "lo"  => {
+                display_places(&mut options, &places, &sightings, sub1.as_deref());
+            } // end of "lo"
+            
+            
+            "loe" => {
+                if sub1.is_none() || sub2.is_none(){
+                    let message = format!("loe needs the location (its id or name) and what to change, e.g.  loe 12 \"a=Delta Park#k=Delta\"");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                if locations_file_damaged {
+                    let message = format!("The locations file could not be read, so it cannot be changed. Try: restore");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let id = find_place(&sub1.unwrap(), &places);
+                if id.is_err(){
+                    let message = id.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let id = id.unwrap();
+                let result = edit_place(id, &sub2.unwrap(), &mut places, &mut sightings);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let changed = result.unwrap();
+                let message = format!("Location {} is now {}, {} {} changed.", id, places[&id].describe(),
+                                        changed.separate_with_spaces(), plural("sighting", changed));
+                feedback(Feedback::Info, message);
+                file_change_sightings = changed > 0;
+            } // end of "loe"
+            
+            
+            "lom" => {
+                if sub1.is_none() || sub2.is_none(){
+                    let message = format!("lom needs the location to merge and the one to merge it into, e.g.  lom 14 12");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                if locations_file_damaged {
+                    let message = format!("The locations file could not be read, so it cannot be changed. Try: restore");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let from = find_place(&sub1.unwrap(), &places);
+                if from.is_err(){
+                    let message = from.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let into = find_place(&sub2.unwrap(), &places);
+                if into.is_err(){
+                    let message = into.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let (from, into) = (from.unwrap(), into.unwrap());
+                let question = format!("Merge {} into {}?", places[&from].describe(), places[&into].describe());
+                if !dry_run && !confirm(&question, assume_yes) {
+                    cancelled();
+                }
+                
+                let result = merge_places(from, into, &mut places, &mut sightings);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let moved = result.unwrap();
+                let message = format!("Location {} was merged into {} ({}), {} {} moved.", from, into, places[&into].describe(),
+                                        moved.separate_with_spaces(), plural("sighting", moved));
+                feedback(Feedback::Info, message);
+                file_change_sightings = moved > 0;
+            } // end of "lom"
+            
+            
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    o     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
             "o"    =>  {
@@ -537,6 +1021,26 @@ fn main() {
             }// end of "ob"
             
             
+            "oc"  => {
+                if_sightings_length_is_zero(&sightings);
+                
+                let report = match sub1 {
+                    // Only the sightings that match the search terms
+                    Some(terms) => {
+                        let r_slice = get_searched_slice_of_sightings(&terms, &sbirds, &sightings, &places);
+                        if r_slice.is_err(){
+                            let message = r_slice.err().unwrap();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        count_report(&sbirds, &r_slice.unwrap().1)
+                    }
+                    None => { count_report(&sbirds, &sightings) }
+                };
+                display_count_report(&mut options, &report);
+            }// end of "oc"
+            
+            
             "od"  => {
                 if_sightings_length_is_zero(&sightings);
                 
@@ -547,6 +1051,13 @@ fn main() {
                         exit(17);
                     }
                     
+                    let sight = yes.clone().sighting.unwrap();
+                    let question = format!("Delete sighting {} of {} on {} at {}?", yes.number.unwrap() + 1,
+                                            yes.clone().species.unwrap().name, sight.display_date(), sight.location);
+                    if !dry_run && !confirm(&question, assume_yes) {
+                        cancelled();
+                    }
+                    
                     delete_sighting(yes, &mut options, &mut sbirds, &mut sightings);
                     file_change_sightings = true;
                 } 
@@ -678,6 +1189,7 @@ fn main() {
                         exit(17);
                     }
                     
+                    let new_sightings: Vec<Sightings>;
                     match ext.unwrap() {
                         "csv" => {
                             let result = Sightings::import_csv(&file, &sbirds);
@@ -686,8 +1198,7 @@ fn main() {
                                 feedback(Feedback::Error, message);
                                 exit(17);
                             }
-                            sightings = result.unwrap();
-                            file_change_sightings = true;
+                            new_sightings = result.unwrap();
                         }
                         
                         "json" => {
@@ -697,8 +1208,7 @@ fn main() {
                                 feedback(Feedback::Error, message);
                                 exit(17);
                             }
-                            sightings = result.unwrap();
-                            file_change_sightings = true;
+                            new_sightings = result.unwrap();
                         }
                         
                         // Other ones
@@ -708,6 +1218,37 @@ fn main() {
                             exit(17);
                         }
                     }
+                    
+                    // Add theirs to mine, nothing is lost
+                    if sub2.clone().map(|s| s.to_lowercase()) == Some("merge".to_string()) {
+                        let keep = Keep::from_arg(sub3.clone());
+                        if keep.is_err(){
+                            let message = keep.err().unwrap();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        let keep = keep.unwrap();
+                        let report = merge_sightings(&mut sightings, new_sightings, keep);
+                        ensure_sighting_ids(&mut sightings);
+                        display_merge_report(&mut options, &report, keep);
+                        file_change_sightings = report.added > 0 || report.replaced > 0;
+                        
+                    } else if sub2.is_some() {
+                        let message = format!("The only thing that can come after the file name is: merge   (and then mine or theirs)");
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    
+                    } else {
+                        if sightings.len() > 0 {
+                            let question = format!("Replace the {} sightings with the {} in {}?",
+                                                    sightings.len().separate_with_spaces(), new_sightings.len().separate_with_spaces(), file);
+                            if !dry_run && !confirm(&question, assume_yes) {
+                                cancelled();
+                            }
+                        }
+                        sightings = new_sightings;
+                        file_change_sightings = true;
+                    }
                 }
                 // sub1 is NONE
                 else {
@@ -738,6 +1279,178 @@ fn main() {
             
             
             
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    r     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@ 
+            
+            "redo" => {
+                let result = move_journal(Direction::Redo, birds_file_damaged || sightings_file_damaged || locations_file_damaged,
+                                            &mut birds, &mut sightings, &mut places, &mut options);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let (journal, entry) = result.unwrap();
+                file_change_birds = entry.changes_species();
+                file_change_sightings = entry.changes_sightings();
+                file_change_locations = entry.changes_locations();
+                journal_moved = Some(journal);
+                
+                let message = format!("Redone: {}   ({})", entry.command, entry.summary());
+                feedback(Feedback::Info, message);
+            } // end of "redo"
+            
+            
+            "repair" => {
+                if store.name() != "bincode" {
+                    let message = format!("repair works on the bincode files, you are using the {} store", store.name());
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                if sub1.is_none(){
+                    let message = format!("repair needs to know which file: species or sightings");
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                
+                match sub1.unwrap().to_lowercase().trim() {
+                    "species" => {
+                        let destination = Species::bin_path();
+                        let bytes = read_bin_file(&destination, FileKind::Species);
+                        if bytes.is_err(){
+                            let message = bytes.err().unwrap().to_string();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        
+                        let salvage = repair_species(&bytes.unwrap());
+                        if salvage.is_err(){
+                            let message = salvage.err().unwrap().to_string();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        let salvage = salvage.unwrap();
+                        let repaired: BTreeMap<String, Species> = salvage.records.into_iter().collect();
+                        
+                        let repaired_file = repaired_path(&destination);
+                        let result = Species::save(&repaired_file, &repaired);
+                        if result.is_err(){
+                            let message = result.err().unwrap();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        let message = format!("Salvaged {} of {} species ({} bytes skipped) into {}",
+                                                repaired.len(), salvage.expected, salvage.skipped_bytes, repaired_file);
+                        feedback(Feedback::Info, message);
+                        let message = format!("Check it and then rename it to {} to use it.", destination);
+                        feedback(Feedback::Info, message);
+                    }
+                    
+                    "sightings" => {
+                        let destination = Sightings::bin_path();
+                        let bytes = read_bin_file(&destination, FileKind::Sightings);
+                        if bytes.is_err(){
+                            let message = bytes.err().unwrap().to_string();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        
+                        let salvage = repair_sightings(&bytes.unwrap());
+                        if salvage.is_err(){
+                            let message = salvage.err().unwrap().to_string();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        let salvage = salvage.unwrap();
+                        let mut repaired: Vec<Sightings> = salvage.records;
+                        repaired.sort();
+                        
+                        let repaired_file = repaired_path(&destination);
+                        let result = Sightings::save(&repaired_file, &repaired);
+                        if result.is_err(){
+                            let message = result.err().unwrap();
+                            feedback(Feedback::Error, message);
+                            exit(17);
+                        }
+                        let message = format!("Salvaged {} of {} sightings ({} bytes skipped) into {}",
+                                                repaired.len(), salvage.expected, salvage.skipped_bytes, repaired_file);
+                        feedback(Feedback::Info, message);
+                        let message = format!("Check it and then rename it to {} to use it.", destination);
+                        feedback(Feedback::Info, message);
+                    }
+                    
+                    _ => {
+                        let message = format!("repair works on either species or sightings");
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                }
+            } // end of "repair"
+            
+            
+            "restore" => {
+                let backups = list_backups();
+                if backups.len() == 0 {
+                    let message = format!("There are no backups in {}", get_backup_dir().display());
+                    feedback(Feedback::Info, message);
+                    exit(17);
+                }
+
+                // Just show what is there
+                if sub1.is_none(){
+                    display_backups(&mut options, &backups);
+                }
+                
+                // Roll back to the chosen one
+                else {
+                    let r_number = sub1.unwrap().trim().parse::<usize>();
+                    if r_number.is_err() || r_number.clone().unwrap() == 0 || r_number.clone().unwrap() > backups.len() {
+                        let message = format!("Give the number of a backup, between 1 and {}", backups.len());
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                    let backup = &backups[r_number.unwrap() - 1];
+                    
+                    let live_files = vec![store.species_file(), store.sightings_file(), store.locations_file()];
+                    let live = live_file_of_backup(backup, &live_files);
+                    if live.is_none(){
+                        let message = format!("Not sure which file this backup belongs to -> {}", backup.display());
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                    
+                    // A database must be closed before it is swapped, and opened again after
+                    let result = store.release();
+                    if result.is_err(){
+                        let message = result.err().unwrap();
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                    let result = restore_backup(backup, &live.clone().unwrap(), options.get_number("numberOfBackups"));
+                    if result.is_err(){
+                        let message = result.err().unwrap();
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                    let reopened = open_store_by_name(store.name());
+                    if reopened.is_err(){
+                        let message = reopened.err().unwrap();
+                        feedback(Feedback::Error, message);
+                        exit(17);
+                    }
+                    store = reopened.unwrap();
+                    let message = format!("{} has been restored from {}", live.unwrap(), backup.display());
+                    feedback(Feedback::Info, message);
+                    
+                    // What was loaded is not what is on disk any more, so it must not be saved over it
+                    file_change_birds = false;
+                    file_change_sightings = false;
+                    file_change_locations = false;
+                }
+            } // end of "restore"
+            
+            
+            
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    s     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@ 
             
@@ -747,7 +1460,7 @@ fn main() {
                 
                 if sub1.is_some(){
                     
-                    let r_slice = get_searched_slice_of_sightings(&sub1.unwrap(), &sbirds, &sightings);
+                    let r_slice = get_searched_slice_of_sightings(&sub1.unwrap(), &sbirds, &sightings, &places);
                     if r_slice.is_err(){
                         let message = r_slice.err().unwrap();
                         feedback(Feedback::Error, message);
@@ -768,6 +1481,28 @@ fn main() {
                 }
             }//end of "sol"
             
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    u     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
+            
+            "undo" => {
+                let result = move_journal(Direction::Undo, birds_file_damaged || sightings_file_damaged || locations_file_damaged,
+                                            &mut birds, &mut sightings, &mut places, &mut options);
+                if result.is_err(){
+                    let message = result.err().unwrap();
+                    feedback(Feedback::Error, message);
+                    exit(17);
+                }
+                let (journal, entry) = result.unwrap();
+                file_change_birds = entry.changes_species();
+                file_change_sightings = entry.changes_sightings();
+                file_change_locations = entry.changes_locations();
+                journal_moved = Some(journal);
+                
+                let message = format!("Undone: {}   ({})", entry.command, entry.summary());
+                feedback(Feedback::Info, message);
+            } // end of "undo"
+            
+            
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@    v     @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
             // @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@          @@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@@                
             
@@ -837,9 +1572,80 @@ fn main() {
 
 
 
-    save_files(file_change_birds,birds, file_change_sightings, sightings);        
+    // Sightings that were added or changed are put in the location registry
+    if !sightings_file_damaged && !locations_file_damaged && journal_moved.is_none() {
+        if register_sightings(&mut places, &mut sightings) > 0 {
+            file_change_sightings = true;
+        }
+    }
+    if places != places_before {
+        file_change_locations = true;
+    }
+    
+    // Only show what would have changed
+    if dry_run {
+        let entry = JournalEntry::from_diff("", &birds_before, &birds, &sightings_before, &sightings, &places_before, &places,
+                                            &lists_before, &list_registry(&options));
+        show_dry_run(entry);
+        exit(0);
+    }
+    
+    // Never save over a file that could not be read, it might still be repaired
+    if file_change_birds && birds_file_damaged {
+        let message = format!("The species file could not be read, so it has not been saved over. Try: repair species  or  restore");
+        feedback(Feedback::Error, message);
+        file_change_birds = false;
+    }
+    if file_change_sightings && sightings_file_damaged {
+        let message = format!("The sightings file could not be read, so it has not been saved over. Try: repair sightings  or  restore");
+        feedback(Feedback::Error, message);
+        file_change_sightings = false;
+    }
+    if file_change_locations && locations_file_damaged {
+        let message = format!("The locations file could not be read, so it has not been saved over. Try: restore");
+        feedback(Feedback::Error, message);
+        file_change_locations = false;
+    }
+
+    // What the command changed, it is saved record by record where the store can
+    let mut changes: Option<JournalEntry> = None;
+    let lists = list_registry(&options);
+    if file_change_birds || file_change_sightings || file_change_locations || lists != lists_before {
+        let command_line = arguments[1..].join(" ");
+        changes = JournalEntry::from_diff(&command_line, &birds_before, &birds, &sightings_before, &sightings, &places_before, &places,
+                                            &lists_before, &lists);
+    }
+
+    // Remember what changed, so it can be undone. Undo and redo only move the position in the journal.
+    if journal_moved.is_none() && changes.is_some() {
+        match Journal::load(&Journal::path()) {
+            Ok(mut journal) => {
+                let entry = changes.clone().unwrap();
+                if entry.is_too_big() {
+                    let message = format!("{} records changed, too many to keep in the journal (it takes {}). This cannot be undone, restore a backup instead. The journal was started afresh.",
+                                            entry.records().separate_with_spaces(), JOURNAL_MAX_RECORDS.separate_with_spaces());
+                    feedback(Feedback::Warning, message);
+                    journal.clear();
+                }
+                else {
+                    journal.record(entry, options.get_number("numberOfUndos"));
+                }
+                journal_moved = Some(journal);
+            }
+            Err(message) => { feedback(Feedback::Warning, message) }
+        }
+    }
+
+    save_files(&mut store, file_change_birds,birds, file_change_sightings, sightings, file_change_locations, places,
+                changes, save_whole_files, options.get_number("numberOfBackups"));        
+    
+    if journal_moved.is_some(){
+        if let Err(message) = journal_moved.unwrap().save(&Journal::path()) {
+            feedback(Feedback::Warning, message)
+        }
+    }
     
-    cleanup(options.clone());    
+    cleanup(&mut store, options.clone());    
         
 
     // Show reponse times
@@ -896,8 +1702,8 @@ fn main() {
 
 
 // This function saves the option file
-pub fn cleanup(options: SettingsText){
-    if options.export(OPTIONS_FILENAME).is_err(){
+pub fn cleanup(store: &mut Box<dyn Store>, options: SettingsText){
+    if store.save_settings(&options).is_err(){
         let message = format!("Problem with writing options file");
         feedback(Feedback::Error, message)
     }
@@ -905,28 +1711,119 @@ pub fn cleanup(options: SettingsText){
 }        
 
 
-// Function to update the data files
-pub fn save_files(b_birds: bool, birds: BTreeMap<String,Species>, b_sightings: bool, sightings: Vec<Sightings>){
+// Function to undo or redo the next change in the journal
+pub fn move_journal(direction: Direction, damaged: bool, birds: &mut BTreeMap<String,Species>,
+                        sightings: &mut Vec<Sightings>, places: &mut BTreeMap<u32, Place>, options: &mut SettingsText) -> Result<(Journal, JournalEntry), String> {
+    if damaged {
+        return Err(format!("Cannot undo or redo while a data file could not be read. Try: repair  or  restore"))
+    }
+    let journal = Journal::load(&Journal::path());
+    if journal.is_err(){
+        return Err(journal.err().unwrap())
+    }
+    let mut journal = journal.unwrap();
+    let mut lists = list_registry(options);
+    
+    let result = match direction {
+        Direction::Undo => { journal.undo(birds, sightings, places, &mut lists) }
+        Direction::Redo => { journal.redo(birds, sightings, places, &mut lists) }
+    };
+    if result.is_err(){
+        return Err(result.err().unwrap())
+    }
+    let entry = result.unwrap();
+    if entry.changes_lists() {
+        let res = set_list_registry(options, &lists);
+        if res.is_err(){
+            return Err(res.err().unwrap())
+        }
+    }
+    Ok((journal, entry))
+}
+
+
+// Function to update the data files. A store that can write single records only gets what changed,
+// unless the data was changed before the command as well.
+pub fn save_files(store: &mut Box<dyn Store>, b_birds: bool, birds: BTreeMap<String,Species>, b_sightings: bool, sightings: Vec<Sightings>,
+                    b_locations: bool, places: BTreeMap<u32, Place>, changes: Option<JournalEntry>, whole: bool, backups: usize){
     if b_birds {
-        let result = Species::save(SPECIES_BIN_FILENAME, &birds);
+        if let Err(message) = store.back_up(&store.species_file(), backups) {
+            feedback(Feedback::Warning, message)
+        }
+    }
+    if b_sightings{
+        // With a database both live in the same file, that has just been backed up
+        if !b_birds || store.sightings_file() != store.species_file() {
+            if let Err(message) = store.back_up(&store.sightings_file(), backups) {
+                feedback(Feedback::Warning, message)
+            }
+        }
+    }
+
+    if (b_birds || b_sightings) && !whole && store.saves_by_record() {
+        let mut changes = changes.unwrap_or_default();
+        if !b_birds {
+            changes.species_before.clear();
+            changes.species_after.clear();
+        }
+        if !b_sightings {
+            changes.sightings_before.clear();
+            changes.sightings_after.clear();
+        }
+        let result = save_changes(store, &changes);
         if result.is_err(){
-            let message = format!("Problem with writing birds csp file");
+            let message = format!("Problem with writing the changes: {}", result.err().unwrap());
             feedback(Feedback::Error, message)
         } else {
-            let records = &birds.len();
-            let message = format!("{} bird species records have been saved.", records);
-            feedback(Feedback::Info, message)
+            if b_birds {
+                let records = changes.species_after.len().max(changes.species_before.len());
+                let message = format!("{} bird species records have been saved.", records);
+                feedback(Feedback::Info, message)
+            }
+            if b_sightings {
+                let records = changes.sightings_after.len().max(changes.sightings_before.len());
+                let message = format!("{} bird sightings records have been saved.", records);
+                feedback(Feedback::Info, message)
+            }
+        }
+    } else {
+        if b_birds {
+            let result = store.save_species(&birds);
+            if result.is_err(){
+                let message = format!("Problem with writing birds file: {}", result.err().unwrap());
+                feedback(Feedback::Error, message)
+            } else {
+                let records = &birds.len();
+                let message = format!("{} bird species records have been saved.", records);
+                feedback(Feedback::Info, message)
+            }
+        }
+        if b_sightings {
+            let result = store.save_sightings(&sightings);
+            if result.is_err(){
+                let message = format!("Problem with writing sightings file: {}", result.err().unwrap());
+                feedback(Feedback::Error, message)
+            } else {
+                let records = &sightings.len();
+                let message = format!("{} bird sightings records have been saved.", records);
+                feedback(Feedback::Info, message)
+            }
         }
     }
     
-    if b_sightings{
-        let result = Sightings::save(SIGHTINGS_BIN_FILENAME, &sightings);
+    if b_locations {
+        if !(b_birds || b_sightings) || store.locations_file() != store.sightings_file() {
+            if let Err(message) = store.back_up(&store.locations_file(), backups) {
+                feedback(Feedback::Warning, message)
+            }
+        }
+        let result = store.save_locations(&places);
         if result.is_err(){
-            let message = format!("Problem with writing sightings csp file");
+            let message = format!("Problem with writing locations file: {}", result.err().unwrap());
             feedback(Feedback::Error, message)
         } else {
-            let records = &sightings.len();
-            let message = format!("{} bird sightings records have been saved.", records);
+            let records = &places.len();
+            let message = format!("{} locations have been saved.", records);
             feedback(Feedback::Info, message)
         }
     }